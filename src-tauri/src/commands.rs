@@ -1,31 +1,59 @@
-use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, HashSet};
 use std::hash::{DefaultHasher, Hash, Hasher};
-use std::io;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
-use image::ImageReader;
-use rusqlite::{params, Connection};
-use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use image::{DynamicImage, ImageReader, RgbaImage};
+use resvg::{tiny_skia, usvg};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
 use uuid::Uuid;
 
+use crate::content_index;
 use crate::errors::CommandError;
+use crate::frecency;
+use crate::index_config;
+use crate::indexing;
+use crate::journal::{self, JournalStep};
 use crate::models::{
-    AppBootstrap, AppCapabilities, AppSettings, AppearanceMode, CloudState, DirectoryListRequest,
-    DirectoryListing, EventNames, FileAccessState, FileAccessStatus, FileEntry, FileIcon,
-    FolderViewState, IndexingState, IndexingStatus, PlatformInfo, SearchMatchReason, SearchResult,
-    SidebarItem, SidebarItemType, SidebarSectionId, SidebarSectionState, SidebarState,
-    SortDirection, SortKey, SortState, TabState, ThumbnailDescriptor, ViewMode, WindowGeometry,
-    WindowState,
+    AppBootstrap, AppCapabilities, AppSettings, AppearanceMode, CloudState, ConflictPolicy,
+    DirectoryListRequest, DirectoryListing, EventNames, FileAccessState, FileAccessStatus,
+    FileEntry, FileIcon, FolderSearchPage, FolderSearchRequest, FolderSearchSource,
+    FolderViewState, IndexConfiguration, IndexExclusionKind, IndexingState, IndexingStatus,
+    OperationActivity, PlatformInfo, PreviewDescriptor, PreviewRenderer, SearchHistoryEntry,
+    SearchMatchReason, SearchPatternMode, SearchResult, SearchResultBatch, SearchResultPage,
+    SearchSession, SearchSuggestion, SidebarItem, SidebarItemType, SidebarSectionId,
+    SidebarSectionState, SidebarState, SortDirection, SortKey, SortState, TabState, TextPreview,
+    ThumbnailCacheStats, ThumbnailDescriptor, TrashedItem, ViewMode, WindowGeometry, WindowState,
 };
+use crate::normalization::{self, NameSortKey, SearchNormalization};
+use crate::operations;
+use crate::pattern_search::NamePattern;
 use crate::persistence;
+use crate::photo_metadata::{self, PhotoMetadata};
+use crate::preview;
+use crate::search_history;
+use crate::search_query::{FilterCondition, QueryFilter, SearchQuery};
+use crate::search_session::{self, SearchSessions};
+use crate::syntax;
+use crate::thumbnail_cache;
+use crate::trash_bin;
+use crate::watcher;
 
 const RECENTS_VIRTUAL_PATH: &str = "recents";
+const SMART_FOLDER_PATH_PREFIX: &str = "smart-folder:";
+const SMART_FOLDER_MAX_RESULTS: usize = 1_000;
+const THUMBNAIL_MAX_EDGE: u32 = 320;
 
 pub(crate) fn restored_windows_for_app(app: &tauri::AppHandle) -> Result<Vec<WindowState>> {
     let database_path = app
@@ -72,7 +100,7 @@ pub fn bootstrap_app(app: tauri::AppHandle) -> Result<AppBootstrap, CommandError
 
     if matches!(&access.status, FileAccessStatus::Granted) {
         if let Some(home) = home_dir.as_deref() {
-            if let Err(error) = crate::indexing::ensure_metadata_index_started(
+            if let Err(error) = indexing::ensure_metadata_index_started(
                 &app,
                 database_path.clone(),
                 PathBuf::from(home),
@@ -112,7 +140,14 @@ pub fn save_session_state(
         .map_err(|error| CommandError::internal(error.to_string()))?
         .join("frogger.sqlite3");
     let mut conn = persistence::open_database(&database_path).map_err(CommandError::from)?;
-    save_windows(&mut conn, &windows).map_err(CommandError::from)
+    save_windows(&mut conn, &windows).map_err(CommandError::from)?;
+
+    // The saved session lists every open tab, so it decides which folders
+    // stay watched.
+    if let Some(folder_watcher) = watcher::shared_watcher(&app) {
+        folder_watcher.watch_folders(&watcher::folders_for_windows(&windows));
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -197,6 +232,17 @@ pub fn list_directory(
         );
     }
 
+    if let Some(smart_folder_id) = smart_folder_id_from_path(&request.path) {
+        let conn = open_app_database(&app)?;
+        return list_smart_folder_directory_impl(
+            &conn,
+            smart_folder_id,
+            &request,
+            home_dir_string().as_deref(),
+            Local::now(),
+        );
+    }
+
     list_directory_impl(
         request.path,
         &request.sort,
@@ -215,9 +261,167 @@ pub fn search_metadata(
     limit: Option<usize>,
 ) -> Result<Vec<SearchResult>, CommandError> {
     let conn = open_app_database(&app)?;
+    let normalization = SearchNormalization::load(&conn).map_err(CommandError::from)?;
+    let query = SearchQuery::parse(
+        &query,
+        home_dir_string().as_deref(),
+        Local::now(),
+        normalization,
+    )?;
     search_metadata_impl(&conn, &query, limit).map_err(CommandError::from)
 }
 
+#[tauri::command]
+pub fn search_pattern(
+    app: tauri::AppHandle,
+    pattern: String,
+    mode: SearchPatternMode,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, CommandError> {
+    let pattern = NamePattern::compile(&pattern, mode)?;
+    let conn = open_app_database(&app)?;
+    let sessions = search_session::shared_search_sessions();
+    let id = sessions.begin(conn.get_interrupt_handle());
+    match search_pattern_impl(&conn, &pattern, limit, || sessions.is_current(&id)) {
+        Ok(results) if sessions.finish(&id, results.clone()) => Ok(results),
+        Err(error) if sessions.is_current(&id) => {
+            let _ = sessions.cancel(&id);
+            Err(CommandError::from(error))
+        }
+        _ => Err(CommandError::unavailable(
+            "A newer search replaced this one.",
+            Some(id),
+        )),
+    }
+}
+
+/// Records a query the user submitted and returns the updated history.
+#[tauri::command]
+pub fn record_search_history(
+    app: tauri::AppHandle,
+    query: String,
+) -> Result<Vec<SearchHistoryEntry>, CommandError> {
+    let conn = open_app_database(&app)?;
+    search_history::record(&conn, &query, Utc::now()).map_err(CommandError::from)?;
+    search_history::list(&conn, None).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn list_search_history(
+    app: tauri::AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHistoryEntry>, CommandError> {
+    let conn = open_app_database(&app)?;
+    search_history::list(&conn, limit).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn delete_search_history_entry(
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<SearchHistoryEntry>, CommandError> {
+    let conn = open_app_database(&app)?;
+    search_history::delete(&conn, &id).map_err(CommandError::from)?;
+    search_history::list(&conn, None).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn clear_search_history(
+    app: tauri::AppHandle,
+) -> Result<Vec<SearchHistoryEntry>, CommandError> {
+    let conn = open_app_database(&app)?;
+    search_history::clear(&conn).map_err(CommandError::from)?;
+    Ok(Vec::new())
+}
+
+#[tauri::command]
+pub fn get_search_suggestions(
+    app: tauri::AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchSuggestion>, CommandError> {
+    let conn = open_app_database(&app)?;
+    let normalization = SearchNormalization::load(&conn).map_err(CommandError::from)?;
+    search_history::suggestions(
+        &conn,
+        &query,
+        normalization,
+        limit.unwrap_or(8).clamp(1, 20),
+    )
+    .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn start_search(
+    app: tauri::AppHandle,
+    query: String,
+    page_size: Option<usize>,
+) -> Result<SearchSession, CommandError> {
+    let conn = open_app_database(&app)?;
+    let normalization = SearchNormalization::load(&conn).map_err(CommandError::from)?;
+    let parsed = SearchQuery::parse(
+        &query,
+        home_dir_string().as_deref(),
+        Local::now(),
+        normalization,
+    )?;
+    let page_size = page_size.unwrap_or(50).clamp(1, 200);
+    let sessions = search_session::shared_search_sessions();
+    let id = sessions.begin(conn.get_interrupt_handle());
+
+    let thread_id = id.clone();
+    let thread_query = query.clone();
+    thread::Builder::new()
+        .name("frogger-search".to_string())
+        .spawn(move || {
+            let events = EventNames::default();
+            let result = run_search_session(
+                &conn,
+                sessions,
+                &thread_id,
+                &thread_query,
+                &parsed,
+                page_size,
+                |batch| {
+                    let _ = app.emit(&events.search_results, batch);
+                },
+            );
+            if let Err(_error) = result {
+                #[cfg(debug_assertions)]
+                eprintln!("[frogger] search session failed: {_error:?}");
+            }
+        })
+        .map_err(|error| {
+            let _ = sessions.cancel(&id);
+            CommandError::internal(format!("Failed to start search: {error}"))
+        })?;
+
+    Ok(SearchSession { id, query })
+}
+
+#[tauri::command]
+pub fn cancel_search(session_id: String) -> Result<(), CommandError> {
+    search_session::shared_search_sessions().cancel(&session_id)
+}
+
+#[tauri::command]
+pub fn get_search_page(
+    session_id: String,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<SearchResultPage, CommandError> {
+    search_session::shared_search_sessions().page(&session_id, cursor.as_deref(), limit)
+}
+
+#[tauri::command]
+pub fn search_in_folder(
+    app: tauri::AppHandle,
+    request: FolderSearchRequest,
+) -> Result<FolderSearchPage, CommandError> {
+    let conn = open_app_database(&app)?;
+    search_in_folder_impl(&conn, &request, home_dir_string().as_deref(), Local::now())
+}
+
 #[tauri::command]
 pub fn open_file_with_default_app(
     app: tauri::AppHandle,
@@ -241,6 +445,42 @@ pub fn open_file_with_default_app(
     load_sidebar_state(&conn, home_dir_string()).map_err(CommandError::from)
 }
 
+#[tauri::command]
+pub fn record_recent_item(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<SidebarState, CommandError> {
+    if !Path::new(&path).exists() {
+        return Err(CommandError::unavailable(
+            "Only existing items can be added to Recents.",
+            Some(path),
+        ));
+    }
+
+    let conn = open_app_database(&app)?;
+    record_recent_path(&conn, &path)?;
+    load_sidebar_state(&conn, home_dir_string()).map_err(CommandError::from)
+}
+
+/// Records that a search result was picked. The open itself is recorded by
+/// whatever opened it; this only teaches search ranking about the choice.
+#[tauri::command]
+pub fn record_search_result_open(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<SidebarState, CommandError> {
+    if !Path::new(&path).exists() {
+        return Err(CommandError::unavailable(
+            "Only existing items can be ranked from search.",
+            Some(path),
+        ));
+    }
+
+    let conn = open_app_database(&app)?;
+    frecency::record_search_pick(&conn, &path, Utc::now()).map_err(CommandError::from)?;
+    load_sidebar_state(&conn, home_dir_string()).map_err(CommandError::from)
+}
+
 #[tauri::command]
 pub fn get_sidebar_state(app: tauri::AppHandle) -> Result<SidebarState, CommandError> {
     let conn = open_app_database(&app)?;
@@ -324,10 +564,69 @@ pub fn set_sidebar_section_visibility(
     load_sidebar_state(&conn, home_dir_string()).map_err(CommandError::from)
 }
 
+#[tauri::command]
+pub fn save_smart_folder(
+    app: tauri::AppHandle,
+    id: Option<String>,
+    label: String,
+    query: String,
+) -> Result<SidebarState, CommandError> {
+    let conn = open_app_database(&app)?;
+    save_smart_folder_impl(
+        &conn,
+        id.as_deref(),
+        &label,
+        &query,
+        home_dir_string().as_deref(),
+    )?;
+    load_sidebar_state(&conn, home_dir_string()).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn remove_smart_folder(
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<SidebarState, CommandError> {
+    let conn = open_app_database(&app)?;
+    conn.execute("DELETE FROM smart_folders WHERE id = ?1", [id])
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)?;
+    load_sidebar_state(&conn, home_dir_string()).map_err(CommandError::from)
+}
+
 #[tauri::command]
 pub fn cleanup_thumbnail_cache(app: tauri::AppHandle) -> Result<usize, CommandError> {
     let conn = open_app_database(&app)?;
-    cleanup_stale_thumbnail_metadata(&conn)
+    let removed = cleanup_stale_thumbnail_metadata(&conn)?;
+    let evicted = thumbnail_cache::evict_over_budget(&conn, None).map_err(CommandError::from)?;
+    Ok(removed + evicted)
+}
+
+#[tauri::command]
+pub fn get_thumbnail_cache_stats(
+    app: tauri::AppHandle,
+) -> Result<ThumbnailCacheStats, CommandError> {
+    let conn = open_app_database(&app)?;
+    thumbnail_cache::stats(&conn).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_thumbnail_cache_budget(
+    app: tauri::AppHandle,
+    budget_bytes: u64,
+) -> Result<AppSettings, CommandError> {
+    if budget_bytes < thumbnail_cache::MIN_CACHE_BUDGET_BYTES {
+        return Err(CommandError::invalid_input(
+            "Thumbnail cache budget is too small.",
+            Some(format!(
+                "Use at least {} bytes.",
+                thumbnail_cache::MIN_CACHE_BUDGET_BYTES
+            )),
+        ));
+    }
+    let conn = open_app_database(&app)?;
+    thumbnail_cache::set_budget(&conn, budget_bytes).map_err(CommandError::from)?;
+    load_settings(&conn).map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -363,20 +662,28 @@ pub fn get_thumbnail(
 }
 
 #[tauri::command]
-pub fn record_recent_item(
+pub fn get_preview(app: tauri::AppHandle, path: String) -> Result<PreviewDescriptor, CommandError> {
+    let conn = open_app_database(&app)?;
+    let settings = load_settings(&conn).map_err(CommandError::from)?;
+    build_preview_descriptor(Path::new(&path), settings.previews_enabled)
+}
+
+#[tauri::command]
+pub fn get_text_preview(
     app: tauri::AppHandle,
     path: String,
-) -> Result<SidebarState, CommandError> {
-    if !Path::new(&path).exists() {
-        return Err(CommandError::unavailable(
-            "Only existing items can be added to Recents.",
-            Some(path),
-        ));
+    max_bytes: Option<usize>,
+) -> Result<TextPreview, CommandError> {
+    let conn = open_app_database(&app)?;
+    let settings = load_settings(&conn).map_err(CommandError::from)?;
+    if !settings.previews_enabled {
+        return Err(CommandError::unavailable("Previews are turned off.", None));
     }
 
-    let conn = open_app_database(&app)?;
-    record_recent_path(&conn, &path)?;
-    load_sidebar_state(&conn, home_dir_string()).map_err(CommandError::from)
+    let max_bytes = max_bytes
+        .unwrap_or(preview::DEFAULT_TEXT_PREVIEW_BYTES)
+        .clamp(1, preview::MAX_TEXT_PREVIEW_BYTES);
+    read_text_preview(Path::new(&path), max_bytes)
 }
 
 #[tauri::command]
@@ -426,6 +733,290 @@ pub fn save_folder_view_state(
     save_folder_view_state_impl(&conn, &path, &state).map_err(CommandError::from)
 }
 
+#[tauri::command]
+pub fn create_folder(
+    app: tauri::AppHandle,
+    parent_path: String,
+    name: Option<String>,
+) -> Result<OperationActivity, CommandError> {
+    let conn = open_app_database(&app)?;
+    let activity = operations::create_folder(Path::new(&parent_path), name.as_deref())?;
+    journal_activity(
+        &conn,
+        &activity,
+        vec![JournalStep {
+            source: None,
+            target: activity.primary_path.as_ref().map(PathBuf::from),
+            replaced: None,
+        }],
+    );
+    Ok(activity)
+}
+
+#[tauri::command]
+pub fn rename_item(
+    app: tauri::AppHandle,
+    path: String,
+    new_name: String,
+) -> Result<OperationActivity, CommandError> {
+    let conn = open_app_database(&app)?;
+    let activity = operations::rename_item(Path::new(&path), &new_name)?;
+    journal_activity(
+        &conn,
+        &activity,
+        vec![JournalStep {
+            source: Some(PathBuf::from(path)),
+            target: activity.primary_path.as_ref().map(PathBuf::from),
+            replaced: None,
+        }],
+    );
+    Ok(activity)
+}
+
+#[tauri::command]
+pub fn move_items_to_trash(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+) -> Result<OperationActivity, CommandError> {
+    let conn = open_app_database(&app)?;
+    let paths = paths.into_iter().map(PathBuf::from).collect::<Vec<_>>();
+    let activity = trash_bin::move_to_trash(&conn, &paths)?;
+    journal_activity(
+        &conn,
+        &activity,
+        paths
+            .into_iter()
+            .map(|path| JournalStep {
+                source: Some(path),
+                target: None,
+                replaced: None,
+            })
+            .collect(),
+    );
+    Ok(activity)
+}
+
+#[tauri::command]
+pub fn copy_items(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    destination: String,
+    conflict_policy: Option<ConflictPolicy>,
+) -> Result<OperationActivity, CommandError> {
+    operations::shared_queue(&app).enqueue_copy(
+        paths.into_iter().map(PathBuf::from).collect(),
+        PathBuf::from(destination),
+        conflict_policy.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+pub fn move_items(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    destination: String,
+    conflict_policy: Option<ConflictPolicy>,
+) -> Result<OperationActivity, CommandError> {
+    operations::shared_queue(&app).enqueue_move(
+        paths.into_iter().map(PathBuf::from).collect(),
+        PathBuf::from(destination),
+        conflict_policy.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+pub fn list_file_operations(app: tauri::AppHandle) -> Vec<OperationActivity> {
+    operations::shared_queue(&app).activities()
+}
+
+#[tauri::command]
+pub fn cancel_file_operation(
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<OperationActivity, CommandError> {
+    operations::shared_queue(&app).cancel(&id)
+}
+
+#[tauri::command]
+pub fn resolve_file_operation_conflict(
+    app: tauri::AppHandle,
+    id: String,
+    resolution: ConflictPolicy,
+    apply_to_all: bool,
+) -> Result<OperationActivity, CommandError> {
+    operations::shared_queue(&app).resolve_conflict(&id, resolution, apply_to_all)
+}
+
+#[tauri::command]
+pub fn list_trash(app: tauri::AppHandle) -> Result<Vec<TrashedItem>, CommandError> {
+    let conn = open_app_database(&app)?;
+    trash_bin::list_trash(&conn)
+}
+
+#[tauri::command]
+pub fn put_back_items(
+    app: tauri::AppHandle,
+    ids: Vec<String>,
+) -> Result<OperationActivity, CommandError> {
+    let conn = open_app_database(&app)?;
+    trash_bin::put_back(&conn, &ids)
+}
+
+#[tauri::command]
+pub fn empty_trash(app: tauri::AppHandle) -> Result<OperationActivity, CommandError> {
+    let conn = open_app_database(&app)?;
+    trash_bin::empty_trash(&conn)
+}
+
+#[tauri::command]
+pub fn undo_file_operation(app: tauri::AppHandle) -> Result<OperationActivity, CommandError> {
+    let conn = open_app_database(&app)?;
+    journal::undo(&conn)
+}
+
+#[tauri::command]
+pub fn redo_file_operation(app: tauri::AppHandle) -> Result<OperationActivity, CommandError> {
+    let conn = open_app_database(&app)?;
+    journal::redo(&conn)
+}
+
+#[tauri::command]
+pub fn pause_indexing() -> Result<(), CommandError> {
+    indexing::shared_index_control().pause()
+}
+
+/// Unpauses a running indexer. When the indexer was cancelled or has stopped,
+/// starts a new run that continues from the stored checkpoint.
+#[tauri::command]
+pub fn resume_indexing(app: tauri::AppHandle) -> Result<(), CommandError> {
+    if indexing::shared_index_control().resume().is_ok() {
+        return Ok(());
+    }
+
+    let home_dir = home_dir_string().ok_or_else(|| {
+        CommandError::unavailable("Frogger could not find your home folder.", None)
+    })?;
+    indexing::ensure_metadata_index_started(&app, app_database_path(&app)?, home_dir.into())
+        .map_err(CommandError::from)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_indexing() -> Result<(), CommandError> {
+    indexing::shared_index_control().cancel()
+}
+
+#[tauri::command]
+pub fn set_indexing_throttle(
+    app: tauri::AppHandle,
+    entries_per_second: Option<u32>,
+) -> Result<AppSettings, CommandError> {
+    let conn = open_app_database(&app)?;
+    indexing::set_entries_per_second(&conn, entries_per_second).map_err(CommandError::from)?;
+    load_settings(&conn).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_search_ignore_diacritics(
+    app: tauri::AppHandle,
+    ignore_diacritics: bool,
+) -> Result<AppSettings, CommandError> {
+    let mut conn = open_app_database(&app)?;
+    indexing::set_search_normalization(&mut conn, SearchNormalization { ignore_diacritics })
+        .map_err(CommandError::from)?;
+    load_settings(&conn).map_err(CommandError::from)
+}
+
+/// Turns content search on or off. Enabling it leaves reading documents to
+/// the index maintenance loop, which backfills them in small batches.
+#[tauri::command]
+pub fn set_content_indexing(
+    app: tauri::AppHandle,
+    enabled: bool,
+) -> Result<AppSettings, CommandError> {
+    let mut conn = open_app_database(&app)?;
+    content_index::set_enabled(&mut conn, enabled).map_err(CommandError::from)?;
+    load_settings(&conn).map_err(CommandError::from)
+}
+
+/// Turns photo metadata indexing on or off. Like content search, the backfill
+/// happens in the index maintenance loop.
+#[tauri::command]
+pub fn set_photo_metadata_indexing(
+    app: tauri::AppHandle,
+    enabled: bool,
+) -> Result<AppSettings, CommandError> {
+    let mut conn = open_app_database(&app)?;
+    photo_metadata::set_enabled(&mut conn, enabled).map_err(CommandError::from)?;
+    load_settings(&conn).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn get_index_configuration(app: tauri::AppHandle) -> Result<IndexConfiguration, CommandError> {
+    let conn = open_index_configuration(&app)?;
+    index_config::load_configuration(&conn).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn add_index_root(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<IndexConfiguration, CommandError> {
+    let conn = open_index_configuration(&app)?;
+    index_config::add_index_root(&conn, &path)
+}
+
+#[tauri::command]
+pub fn remove_index_root(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<IndexConfiguration, CommandError> {
+    let conn = open_index_configuration(&app)?;
+    index_config::remove_index_root(&conn, &path)
+}
+
+#[tauri::command]
+pub fn add_index_exclusion(
+    app: tauri::AppHandle,
+    kind: IndexExclusionKind,
+    pattern: String,
+) -> Result<IndexConfiguration, CommandError> {
+    let conn = open_index_configuration(&app)?;
+    index_config::add_index_exclusion(&conn, kind, &pattern)
+}
+
+#[tauri::command]
+pub fn remove_index_exclusion(
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<IndexConfiguration, CommandError> {
+    let conn = open_index_configuration(&app)?;
+    index_config::remove_index_exclusion(&conn, &id)
+}
+
+/// Opens the database with the current user's default index roots and
+/// exclusions seeded, so they can be listed before the first index run.
+fn open_index_configuration(app: &tauri::AppHandle) -> Result<Connection, CommandError> {
+    let conn = open_app_database(app)?;
+    if let Some(home) = home_dir_string() {
+        index_config::ensure_default_configuration(&conn, Path::new(&home))
+            .map_err(CommandError::from)?;
+    }
+    Ok(conn)
+}
+
+/// Journals a finished operation for undo. The operation already happened, so
+/// a journal failure is only logged.
+fn journal_activity(conn: &Connection, activity: &OperationActivity, steps: Vec<JournalStep>) {
+    if let Err(_error) = journal::record(conn, activity.operation.clone(), &steps) {
+        #[cfg(debug_assertions)]
+        eprintln!(
+            "[frogger] failed to journal {:?}: {_error}",
+            activity.operation
+        );
+    }
+}
+
 fn list_recents_directory_impl(
     conn: &Connection,
     hidden_files_visible: bool,
@@ -458,6 +1049,79 @@ fn list_recents_directory_impl(
         }
     }
 
+    Ok(virtual_directory_page(
+        RECENTS_VIRTUAL_PATH.to_string(),
+        entries,
+        cursor,
+        limit,
+    ))
+}
+
+/// Lists a smart folder by running its saved query against the index. The
+/// query is parsed again on every open, so relative dates stay current.
+fn list_smart_folder_directory_impl(
+    conn: &Connection,
+    id: &str,
+    request: &DirectoryListRequest,
+    home_dir: Option<&str>,
+    now: DateTime<Local>,
+) -> Result<DirectoryListing, CommandError> {
+    let saved_query = conn
+        .query_row(
+            "SELECT query FROM smart_folders WHERE id = ?1",
+            [id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)?
+        .ok_or_else(|| {
+            CommandError::missing_path("That smart folder no longer exists.", Some(id.to_string()))
+        })?;
+    let normalization = SearchNormalization::load(conn).map_err(CommandError::from)?;
+    let mut query = SearchQuery::parse(&saved_query, home_dir, now, normalization)?;
+    if !request.hidden_files_visible {
+        query.filters.push(QueryFilter {
+            negated: false,
+            condition: FilterCondition::Hidden(false),
+        });
+    }
+
+    let results = search_ranked_results(
+        conn,
+        &query,
+        SMART_FOLDER_MAX_RESULTS,
+        CandidateSource::FullText,
+    )
+    .map_err(CommandError::from)?;
+    let mut entries = Vec::with_capacity(results.len());
+    for result in results {
+        let path = PathBuf::from(&result.path);
+        if let Ok(Some(entry)) = file_entry_from_path(
+            path.parent().unwrap_or_else(|| Path::new("")),
+            &path,
+            request.hidden_files_visible,
+            request.file_extensions_visible,
+        ) {
+            entries.push(entry);
+        }
+    }
+    sort_entries(&mut entries, &request.sort, request.folders_first);
+
+    Ok(virtual_directory_page(
+        smart_folder_path(id),
+        entries,
+        request.cursor.as_deref(),
+        request.limit,
+    ))
+}
+
+fn virtual_directory_page(
+    path: String,
+    entries: Vec<FileEntry>,
+    cursor: Option<&str>,
+    limit: Option<usize>,
+) -> DirectoryListing {
     let total_count = entries.len();
     let offset = cursor
         .and_then(|value| value.parse::<usize>().ok())
@@ -471,13 +1135,72 @@ fn list_recents_directory_impl(
         .take(requested_limit)
         .collect::<Vec<_>>();
 
-    Ok(DirectoryListing {
-        path: RECENTS_VIRTUAL_PATH.to_string(),
+    DirectoryListing {
+        path,
         entries: page,
         total_count,
         next_cursor: (next_offset < total_count).then(|| next_offset.to_string()),
         loading_complete: next_offset >= total_count,
-    })
+    }
+}
+
+/// Creates a smart folder, or updates the one with `id`. The query must parse
+/// now so a typo is reported when saving rather than every time it opens.
+fn save_smart_folder_impl(
+    conn: &Connection,
+    id: Option<&str>,
+    label: &str,
+    query: &str,
+    home_dir: Option<&str>,
+) -> Result<String, CommandError> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(CommandError::invalid_input(
+            "Smart folders need a name.",
+            None,
+        ));
+    }
+    let parsed = SearchQuery::parse(
+        query,
+        home_dir,
+        Local::now(),
+        SearchNormalization::default(),
+    )?;
+    if parsed.is_empty() {
+        return Err(CommandError::invalid_input(
+            "Smart folders need a search to save.",
+            Some(query.to_string()),
+        ));
+    }
+
+    if let Some(id) = id {
+        let updated = conn
+            .execute(
+                "UPDATE smart_folders
+                 SET label = ?1, query = ?2, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                 WHERE id = ?3",
+                params![label, query.trim(), id],
+            )
+            .map_err(anyhow::Error::from)
+            .map_err(CommandError::from)?;
+        if updated == 0 {
+            return Err(CommandError::missing_path(
+                "That smart folder no longer exists.",
+                Some(id.to_string()),
+            ));
+        }
+        return Ok(id.to_string());
+    }
+
+    let id = format!("smart-folder-{}", Uuid::new_v4());
+    conn.execute(
+        "INSERT INTO smart_folders (id, label, query, position)
+         SELECT ?1, ?2, ?3, COALESCE(MAX(position), -1) + 1 FROM smart_folders",
+        params![id, label, query.trim()],
+    )
+    .map_err(anyhow::Error::from)
+    .map_err(CommandError::from)?;
+    Ok(id)
 }
 
 fn list_directory_impl(
@@ -604,7 +1327,7 @@ fn file_entry_from_dir_entry(
     )
 }
 
-fn file_entry_from_path(
+pub(crate) fn file_entry_from_path(
     parent: &Path,
     path: &Path,
     hidden_files_visible: bool,
@@ -732,7 +1455,10 @@ enum FileCategory {
 
 fn file_category(extension: Option<&str>) -> FileCategory {
     match extension.map(|value| value.to_ascii_lowercase()).as_deref() {
-        Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "heic" | "svg") => FileCategory::Image,
+        Some(
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "heic" | "svg" | "bmp" | "tif" | "tiff"
+            | "ico",
+        ) => FileCategory::Image,
         Some("mov" | "mp4" | "m4v" | "avi" | "mkv" | "webm") => FileCategory::Video,
         Some("mp3" | "wav" | "aac" | "flac" | "m4a" | "ogg") => FileCategory::Audio,
         Some("pdf") => FileCategory::Pdf,
@@ -747,141 +1473,701 @@ fn file_category(extension: Option<&str>) -> FileCategory {
         Some("rs" | "ts" | "tsx" | "js" | "jsx" | "json" | "html" | "css" | "scss") => {
             FileCategory::SourceCode
         }
-        Some(_) | None => FileCategory::Document,
+        Some(_) | None => FileCategory::Document,
+    }
+}
+
+fn kind_for(is_dir: bool, extension: Option<&str>) -> &'static str {
+    if is_dir {
+        return "Folder";
+    }
+
+    match file_category(extension) {
+        FileCategory::Application => "Application",
+        FileCategory::Archive => "Archive",
+        FileCategory::Audio => "Audio",
+        FileCategory::Document => "Document",
+        FileCategory::Image => "Image",
+        FileCategory::Markdown => "Markdown Document",
+        FileCategory::Pdf => "PDF Document",
+        FileCategory::SourceCode => "Source Code",
+        FileCategory::Spreadsheet => "Spreadsheet",
+        FileCategory::Text => "Text Document",
+        FileCategory::Video => "Video",
+        FileCategory::WordDocument => "Word Document",
+    }
+}
+
+fn icon_for(is_dir: bool, extension: Option<&str>) -> FileIcon {
+    if is_dir {
+        return FileIcon {
+            name: "folder".to_string(),
+            color: Some("blue".to_string()),
+        };
+    }
+
+    let name = match file_category(extension) {
+        FileCategory::Archive => "archive",
+        FileCategory::Document => "generic",
+        FileCategory::Markdown => "markdown",
+        FileCategory::Pdf => "pdf",
+        FileCategory::Spreadsheet => "spreadsheet",
+        FileCategory::WordDocument => "word-document",
+        FileCategory::Application
+        | FileCategory::Audio
+        | FileCategory::Image
+        | FileCategory::SourceCode
+        | FileCategory::Text
+        | FileCategory::Video => "file",
+    };
+
+    FileIcon {
+        name: name.to_string(),
+        color: None,
+    }
+}
+
+#[derive(Debug)]
+struct SearchCandidate {
+    result: SearchResult,
+    search_text: String,
+    recent_boost: f64,
+    modified_boost: f64,
+}
+
+/// A search session ranks at most this many results for paging.
+const SEARCH_SESSION_MAX_RESULTS: usize = 2_000;
+
+/// Runs one search session: a quick first page ranked on its own, then the
+/// full ranking, which is kept for paging and whose first page replaces the
+/// quick one. Stops quietly once a newer search supersedes it.
+fn run_search_session(
+    conn: &Connection,
+    sessions: &SearchSessions,
+    id: &str,
+    query_text: &str,
+    query: &SearchQuery,
+    page_size: usize,
+    mut emit: impl FnMut(SearchResultBatch),
+) -> Result<()> {
+    let batch =
+        |results: Vec<SearchResult>, total_count: usize, complete: bool| SearchResultBatch {
+            session_id: id.to_string(),
+            query: query_text.to_string(),
+            results,
+            offset: 0,
+            total_count,
+            complete,
+        };
+    // A superseded session's query is interrupted, so its errors are expected.
+    let rank = |result_limit: usize| {
+        search_ranked_results(conn, query, result_limit, CandidateSource::FullText).inspect_err(
+            |_| {
+                if sessions.is_current(id) {
+                    let _ = sessions.cancel(id);
+                }
+            },
+        )
+    };
+
+    let first_page = match rank(page_size) {
+        Ok(results) => results,
+        Err(_) if !sessions.is_current(id) => return Ok(()),
+        Err(error) => return Err(error),
+    };
+    if !sessions.is_current(id) {
+        return Ok(());
+    }
+    let first_count = first_page.len();
+    emit(batch(first_page, first_count, false));
+
+    // Another search may have started while the first page was delivered.
+    if !sessions.is_current(id) {
+        return Ok(());
+    }
+    let results = match rank(SEARCH_SESSION_MAX_RESULTS) {
+        Ok(results) => results,
+        Err(_) if !sessions.is_current(id) => return Ok(()),
+        Err(error) => return Err(error),
+    };
+    let total_count = results.len();
+    let first_page = results.iter().take(page_size).cloned().collect::<Vec<_>>();
+    if sessions.finish(id, results) {
+        emit(batch(first_page, total_count, true));
+    }
+    Ok(())
+}
+
+/// Folder search ranks at most this many results; pages are cut from them.
+const FOLDER_SEARCH_MAX_RESULTS: usize = 1_000;
+/// Bounds for walking a folder the index does not cover.
+const LIVE_SEARCH_MAX_ENTRIES: usize = 50_000;
+const LIVE_SEARCH_TIME_BUDGET: Duration = Duration::from_secs(2);
+/// How long a live walk is reused for later pages of the same folder.
+const LIVE_SEARCH_SNAPSHOT_TTL: Duration = Duration::from_secs(60);
+
+/// The last live walk, kept so paging through its results does not walk the
+/// folder again and sees a stable order.
+struct LiveSearchSnapshot {
+    folder: String,
+    conn: Mutex<Connection>,
+    complete: bool,
+    taken_at: Instant,
+}
+
+/// The published walk, plus a generation bumped whenever a walk starts. Walks
+/// run without the lock held, so one that finishes after a newer walk began
+/// is not published over it.
+#[derive(Default)]
+struct LiveSearchState {
+    generation: u64,
+    snapshot: Option<Arc<LiveSearchSnapshot>>,
+}
+
+static LIVE_SEARCH_STATE: OnceLock<Mutex<LiveSearchState>> = OnceLock::new();
+
+fn search_in_folder_impl(
+    conn: &Connection,
+    request: &FolderSearchRequest,
+    home_dir: Option<&str>,
+    now: DateTime<Local>,
+) -> Result<FolderSearchPage, CommandError> {
+    let target = PathBuf::from(&request.path);
+    let metadata = std::fs::metadata(&target).map_err(|error| fs_access_error(&target, error))?;
+    if !metadata.is_dir() {
+        return Err(CommandError::unavailable(
+            "The requested path is not a folder.",
+            Some(request.path.clone()),
+        ));
+    }
+    let folder = target.to_string_lossy().into_owned();
+
+    let normalization = SearchNormalization::load(conn).map_err(CommandError::from)?;
+    let mut query = SearchQuery::parse(&request.query, home_dir, now, normalization)?;
+    let searchable = !query.is_empty();
+    query.filters.push(QueryFilter {
+        negated: false,
+        condition: FilterCondition::InFolder(folder.clone()),
+    });
+
+    let (results, source, walk_complete) = if !searchable {
+        (Vec::new(), FolderSearchSource::Index, true)
+    } else if indexing::covers_folder(conn, &target).map_err(CommandError::from)? {
+        let results = search_ranked_results(
+            conn,
+            &query,
+            FOLDER_SEARCH_MAX_RESULTS,
+            CandidateSource::FullText,
+        )
+        .map_err(CommandError::from)?;
+        (results, FolderSearchSource::Index, true)
+    } else {
+        let state = LIVE_SEARCH_STATE.get_or_init(Default::default);
+        let (reusable, generation) = {
+            let mut state = state
+                .lock()
+                .expect("live search state guard should not be poisoned");
+            let reusable = state.snapshot.clone().filter(|snapshot| {
+                request.cursor.is_some()
+                    && snapshot.folder == folder
+                    && snapshot.taken_at.elapsed() < LIVE_SEARCH_SNAPSHOT_TTL
+            });
+            if reusable.is_none() {
+                state.generation += 1;
+            }
+            (reusable, state.generation)
+        };
+        let snapshot = match reusable {
+            Some(snapshot) => snapshot,
+            None => {
+                let mut snapshot_conn =
+                    persistence::open_memory_database().map_err(CommandError::from)?;
+                let rules = index_config::IndexRules::load(conn).map_err(CommandError::from)?;
+                let complete = indexing::snapshot_folder(
+                    &mut snapshot_conn,
+                    &target,
+                    rules,
+                    normalization,
+                    LIVE_SEARCH_MAX_ENTRIES,
+                    Instant::now() + LIVE_SEARCH_TIME_BUDGET,
+                )
+                .map_err(CommandError::from)?;
+                let snapshot = Arc::new(LiveSearchSnapshot {
+                    folder: folder.clone(),
+                    conn: Mutex::new(snapshot_conn),
+                    complete,
+                    taken_at: Instant::now(),
+                });
+                let mut state = state
+                    .lock()
+                    .expect("live search state guard should not be poisoned");
+                if state.generation == generation {
+                    state.snapshot = Some(Arc::clone(&snapshot));
+                }
+                snapshot
+            }
+        };
+        let results = search_ranked_results(
+            &snapshot
+                .conn
+                .lock()
+                .expect("live search snapshot guard should not be poisoned"),
+            &query,
+            FOLDER_SEARCH_MAX_RESULTS,
+            CandidateSource::FullText,
+        )
+        .map_err(CommandError::from)?;
+        (results, FolderSearchSource::LiveWalk, snapshot.complete)
+    };
+
+    let total_count = results.len();
+    let offset = request
+        .cursor
+        .as_deref()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0)
+        .min(total_count);
+    let requested_limit = request.limit.unwrap_or(total_count.saturating_sub(offset));
+    let next_offset = offset.saturating_add(requested_limit).min(total_count);
+    let page = results
+        .into_iter()
+        .skip(offset)
+        .take(requested_limit)
+        .collect::<Vec<_>>();
+
+    Ok(FolderSearchPage {
+        path: folder,
+        query: request.query.clone(),
+        results: page,
+        total_count,
+        next_cursor: (next_offset < total_count).then(|| next_offset.to_string()),
+        loading_complete: next_offset >= total_count,
+        source,
+        walk_complete,
+    })
+}
+
+/// Where text search candidates come from. The trigram index finds substring
+/// hits without scanning, while a scan also finds the loose fuzzy matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CandidateSource {
+    FullText,
+    Scan,
+}
+
+/// Trigram matching needs at least three characters to use the index.
+const FULL_TEXT_MIN_QUERY_CHARS: usize = 3;
+
+fn search_metadata_impl(
+    conn: &Connection,
+    query: &SearchQuery,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>> {
+    search_metadata_from(conn, query, limit, CandidateSource::FullText)
+}
+
+fn search_metadata_from(
+    conn: &Connection,
+    query: &SearchQuery,
+    limit: Option<usize>,
+    source: CandidateSource,
+) -> Result<Vec<SearchResult>> {
+    let result_limit = limit.unwrap_or(50).clamp(1, 200);
+    search_ranked_results(conn, query, result_limit, source)
+}
+
+fn search_ranked_results(
+    conn: &Connection,
+    query: &SearchQuery,
+    result_limit: usize,
+    source: CandidateSource,
+) -> Result<Vec<SearchResult>> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let candidate_limit = (result_limit * 25).clamp(250, 5_000);
+    let source = if query.text.chars().count() < FULL_TEXT_MIN_QUERY_CHARS {
+        CandidateSource::Scan
+    } else {
+        source
+    };
+
+    // Substring hits always outrank fuzzy matches, so the scan only has to
+    // run when the index cannot fill the page on its own.
+    let mut ranked = rank_search_candidates(conn, query, source, candidate_limit)?;
+    if source == CandidateSource::FullText && ranked.len() < result_limit {
+        let hits = ranked
+            .iter()
+            .map(|result| result.path.clone())
+            .collect::<HashSet<_>>();
+        ranked.extend(
+            rank_search_candidates(conn, query, CandidateSource::Scan, candidate_limit)?
+                .into_iter()
+                .filter(|result| !hits.contains(&result.path)),
+        );
+    }
+
+    if query.text.chars().count() >= FULL_TEXT_MIN_QUERY_CHARS && content_index::is_enabled(conn)? {
+        merge_content_results(
+            &mut ranked,
+            rank_content_candidates(conn, query, candidate_limit)?,
+        );
+    }
+
+    sort_search_results(&mut ranked);
+    ranked.truncate(result_limit);
+    Ok(ranked)
+}
+
+/// Adds content hits to name hits. A file found both ways keeps its name
+/// rank, which is the stronger signal, and gains the snippet.
+fn merge_content_results(ranked: &mut Vec<SearchResult>, content: Vec<SearchResult>) {
+    let positions = ranked
+        .iter()
+        .enumerate()
+        .map(|(position, result)| (result.path.clone(), position))
+        .collect::<BTreeMap<_, _>>();
+    for hit in content {
+        match positions.get(&hit.path) {
+            Some(&position) => ranked[position].snippet = hit.snippet,
+            None => ranked.push(hit),
+        }
     }
 }
 
-fn kind_for(is_dir: bool, extension: Option<&str>) -> &'static str {
-    if is_dir {
-        return "Folder";
+/// Ranks files whose indexed text matches the free text and phrases of
+/// `query`. Content hits rank between substring and fuzzy name matches.
+fn rank_content_candidates(
+    conn: &Connection,
+    query: &SearchQuery,
+    candidate_limit: usize,
+) -> Result<Vec<SearchResult>> {
+    let Some(expression) = content_index::match_expression(query) else {
+        return Ok(Vec::new());
+    };
+    // Phrases are matched against the document instead of the file name.
+    let filters = SearchQuery {
+        phrases: Vec::new(),
+        ..query.clone()
     }
+    .compile();
+
+    let mut values = vec![
+        Value::Text(content_index::SNIPPET_MATCH_START.to_string()),
+        Value::Text(content_index::SNIPPET_MATCH_END.to_string()),
+        Value::Text(content_index::SNIPPET_ELLIPSIS.to_string()),
+        Value::Integer(content_index::SNIPPET_TOKENS),
+        Value::Text(expression),
+        Value::Integer(candidate_limit as i64),
+    ];
+    let mut clauses = vec!["1".to_string()];
+    clauses.extend(filters.clauses);
+    values.extend(filters.params);
+    values.push(Value::Integer(candidate_limit as i64));
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT path, parent_path, name, display_name, kind, is_dir, size, modified_at,
+                recent_boost, modified_boost, hits.snippet, hits.score
+         FROM metadata_index
+         JOIN (
+             SELECT content_index.path AS content_path,
+                    snippet(content_search, 0, ?, ?, ?, ?) AS snippet,
+                    bm25(content_search) AS score
+             FROM content_search
+             JOIN content_index ON content_index.rowid = content_search.rowid
+             WHERE content_search MATCH ?
+             ORDER BY score
+             LIMIT ?
+         ) AS hits ON hits.content_path = metadata_index.path
+         WHERE {}
+         ORDER BY hits.score
+         LIMIT ?",
+        clauses.join(" AND ")
+    ))?;
+
+    let rows = stmt.query_map(params_from_iter(values), |row| {
+        let size = row
+            .get::<_, Option<i64>>(6)?
+            .and_then(|value| (value >= 0).then_some(value as u64));
+        let recent_boost = (row.get::<_, f64>(8)? * 1_000.0).round() as i64;
+        let modified_boost = (row.get::<_, f64>(9)? * 1_000.0).round() as i64;
+        // bm25 is negative and grows in magnitude with relevance.
+        let relevance = (-row.get::<_, f64>(11)? * 100.0)
+            .round()
+            .clamp(0.0, 10_000.0) as i64;
+        Ok(SearchResult {
+            path: row.get(0)?,
+            parent_path: row.get(1)?,
+            name: row.get(2)?,
+            display_name: row.get(3)?,
+            kind: row.get(4)?,
+            is_dir: row.get::<_, i64>(5)? == 1,
+            size,
+            modified_at: row.get(7)?,
+            rank: 500_000 + relevance + recent_boost + modified_boost,
+            match_reason: SearchMatchReason::Content,
+            snippet: Some(content_index::parse_snippet(&row.get::<_, String>(10)?)),
+        })
+    })?;
 
-    match file_category(extension) {
-        FileCategory::Application => "Application",
-        FileCategory::Archive => "Archive",
-        FileCategory::Audio => "Audio",
-        FileCategory::Document => "Document",
-        FileCategory::Image => "Image",
-        FileCategory::Markdown => "Markdown Document",
-        FileCategory::Pdf => "PDF Document",
-        FileCategory::SourceCode => "Source Code",
-        FileCategory::Spreadsheet => "Spreadsheet",
-        FileCategory::Text => "Text Document",
-        FileCategory::Video => "Video",
-        FileCategory::WordDocument => "Word Document",
+    let mut results = Vec::new();
+    for row in rows {
+        let result = row?;
+        if query.excluded_terms.iter().any(|term| {
+            query
+                .normalization
+                .fold(&result.name)
+                .contains(term.as_str())
+        }) {
+            continue;
+        }
+        results.push(result);
     }
+    Ok(results)
 }
 
-fn icon_for(is_dir: bool, extension: Option<&str>) -> FileIcon {
-    if is_dir {
-        return FileIcon {
-            name: "folder".to_string(),
-            color: Some("blue".to_string()),
-        };
-    }
+/// How many rows a pattern scan checks between looks at whether a newer
+/// search has replaced it.
+const PATTERN_SCAN_CHECK_INTERVAL: usize = 4_096;
 
-    let name = match file_category(extension) {
-        FileCategory::Archive => "archive",
-        FileCategory::Document => "generic",
-        FileCategory::Markdown => "markdown",
-        FileCategory::Pdf => "pdf",
-        FileCategory::Spreadsheet => "spreadsheet",
-        FileCategory::WordDocument => "word-document",
-        FileCategory::Application
-        | FileCategory::Audio
-        | FileCategory::Image
-        | FileCategory::SourceCode
-        | FileCategory::Text
-        | FileCategory::Video => "file",
+/// Matches indexed rows against `pattern`. Patterns carry no text to rank
+/// by, so matches are ordered like filter-only searches: folders, then
+/// recent and freshly modified items. Globs with literal ends only scan rows
+/// that `LIKE` them; the scan gives up early once `is_current` turns false.
+fn search_pattern_impl(
+    conn: &Connection,
+    pattern: &NamePattern,
+    limit: Option<usize>,
+    is_current: impl Fn() -> bool,
+) -> Result<Vec<SearchResult>> {
+    let result_limit = limit.unwrap_or(50).clamp(1, 200);
+    let (condition, values) = match pattern.literal_affixes() {
+        Some((prefix, suffix)) => (
+            format!(
+                "WHERE {} LIKE ? ESCAPE '\\'",
+                if pattern.matches_path() {
+                    "path"
+                } else {
+                    "name"
+                }
+            ),
+            vec![format!(
+                "{}%{}",
+                escape_sql_like(prefix),
+                escape_sql_like(suffix)
+            )],
+        ),
+        None => (String::new(), Vec::new()),
     };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT path, parent_path, name, display_name, kind, is_dir, size, modified_at,
+                recent_boost, modified_boost
+         FROM metadata_index
+         {condition}"
+    ))?;
+    let mut rows = stmt.query(params_from_iter(values))?;
+
+    let mut matches = Vec::new();
+    let mut scanned = 0_usize;
+    while let Some(row) = rows.next()? {
+        scanned += 1;
+        if scanned.is_multiple_of(PATTERN_SCAN_CHECK_INTERVAL) && !is_current() {
+            return Ok(Vec::new());
+        }
+        let name = row.get_ref(2)?.as_str()?;
+        let path = row.get_ref(0)?.as_str()?;
+        if !pattern.is_match(name, path) {
+            continue;
+        }
 
-    FileIcon {
-        name: name.to_string(),
-        color: None,
+        let is_dir = row.get::<_, i64>(5)? == 1;
+        let folder_boost = if is_dir { 100_000 } else { 0 };
+        let recent_boost = (row.get::<_, f64>(8)? * 1_000.0).round() as i64;
+        let modified_boost = (row.get::<_, f64>(9)? * 1_000.0).round() as i64;
+        matches.push(SearchResult {
+            path: path.to_string(),
+            parent_path: row.get(1)?,
+            name: name.to_string(),
+            display_name: row.get(3)?,
+            kind: row.get(4)?,
+            is_dir,
+            size: row
+                .get::<_, Option<i64>>(6)?
+                .and_then(|value| (value >= 0).then_some(value as u64)),
+            modified_at: row.get(7)?,
+            rank: folder_boost + recent_boost + modified_boost,
+            match_reason: SearchMatchReason::Pattern,
+            snippet: None,
+        });
+
+        // Broad patterns can match most of the index; keep only the best few
+        // pages in memory while scanning.
+        if matches.len() >= result_limit * 8 {
+            sort_search_results(&mut matches);
+            matches.truncate(result_limit);
+        }
     }
+
+    sort_search_results(&mut matches);
+    matches.truncate(result_limit);
+    Ok(matches)
 }
 
-#[derive(Debug)]
-struct SearchCandidate {
-    result: SearchResult,
-    search_text: String,
-    recent_boost: f64,
-    modified_boost: f64,
+fn sort_search_results(results: &mut [SearchResult]) {
+    // Names are folded once per result rather than on every comparison.
+    results.sort_by_cached_key(|result| {
+        (
+            Reverse(result.rank),
+            Reverse(result.is_dir),
+            NameSortKey::new(&result.name),
+            result.path.clone(),
+        )
+    });
 }
 
-fn search_metadata_impl(
+fn rank_search_candidates(
     conn: &Connection,
-    query: &str,
-    limit: Option<usize>,
+    query: &SearchQuery,
+    source: CandidateSource,
+    candidate_limit: usize,
 ) -> Result<Vec<SearchResult>> {
-    let normalized_query = query.trim().to_ascii_lowercase();
-    if normalized_query.is_empty() {
-        return Ok(Vec::new());
+    let normalized_query = query.text.as_str();
+    let filters = query.compile();
+    let mut clauses = Vec::new();
+    let mut values = Vec::new();
+
+    if !normalized_query.is_empty() && source == CandidateSource::FullText {
+        clauses.push(
+            "rowid IN (SELECT rowid FROM metadata_search WHERE metadata_search MATCH ?)"
+                .to_string(),
+        );
+        values.push(Value::Text(format!(
+            "\"{}\"",
+            normalized_query.replace('"', "\"\"")
+        )));
+    } else if !normalized_query.is_empty() {
+        let escaped_query = escape_sql_like(normalized_query);
+        let prefix_pattern = format!("{escaped_query}%");
+        let contains_pattern = format!("%{escaped_query}%");
+        let first_char_pattern = normalized_query
+            .chars()
+            .next()
+            .map(|character| format!("%{}%", escape_sql_like(&character.to_string())))
+            .unwrap_or_else(|| contains_pattern.clone());
+
+        clauses.push(
+            "(lower(name) = ?
+              OR lower(display_name) = ?
+              OR lower(name) LIKE ? ESCAPE '\\'
+              OR search_text LIKE ? ESCAPE '\\'
+              OR lower(path) LIKE ? ESCAPE '\\'
+              OR lower(name) LIKE ? ESCAPE '\\')"
+                .to_string(),
+        );
+        values.extend(
+            [
+                normalized_query.to_string(),
+                normalized_query.to_string(),
+                prefix_pattern,
+                contains_pattern.clone(),
+                contains_pattern,
+                first_char_pattern,
+            ]
+            .map(Value::Text),
+        );
     }
+    clauses.extend(filters.clauses);
+    values.extend(filters.params);
+
+    // Every full-text hit is an exact, prefix or substring match, so ordering
+    // by the same score the ranker computes means the limit keeps exactly the
+    // best hits. Names are compared with the query's own fold rather than
+    // SQLite's `lower()`, which only knows ASCII.
+    let order_by = if source == CandidateSource::FullText && !normalized_query.is_empty() {
+        let normalization = query.normalization;
+        conn.create_scalar_function(
+            "search_fold",
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            move |context| Ok(normalization.fold(&context.get::<String>(0)?)),
+        )?;
+        let prefix_pattern = format!("{}%", escape_sql_like(normalized_query));
+        values.extend(
+            [
+                normalized_query.to_string(),
+                normalized_query.to_string(),
+                prefix_pattern.clone(),
+                prefix_pattern,
+            ]
+            .map(Value::Text),
+        );
+        "CASE
+             WHEN search_fold(name) = ? OR search_fold(display_name) = ? THEN 1000000
+             WHEN search_fold(name) LIKE ? ESCAPE '\\'
+                 OR search_fold(display_name) LIKE ? ESCAPE '\\'
+                 THEN 800000
+             ELSE 600000
+         END + is_dir * 100000 + round(recent_boost * 1000) + round(modified_boost * 1000) DESC,
+         is_dir DESC, search_fold(name) ASC, path ASC"
+    } else {
+        "is_dir DESC, recent_boost DESC, modified_at DESC, name ASC"
+    };
+    values.push(Value::Integer(candidate_limit as i64));
 
-    let result_limit = limit.unwrap_or(50).clamp(1, 200);
-    let candidate_limit = (result_limit * 25).clamp(250, 5_000);
-    let escaped_query = escape_sql_like(&normalized_query);
-    let prefix_pattern = format!("{escaped_query}%");
-    let contains_pattern = format!("%{escaped_query}%");
-    let first_char_pattern = normalized_query
-        .chars()
-        .next()
-        .map(|character| format!("%{}%", escape_sql_like(&character.to_string())))
-        .unwrap_or_else(|| contains_pattern.clone());
-
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare(&format!(
         "SELECT path, parent_path, name, display_name, kind, is_dir, size, modified_at,
                 search_text, recent_boost, modified_boost
          FROM metadata_index
-         WHERE lower(name) = ?1
-            OR lower(display_name) = ?1
-            OR lower(name) LIKE ?2 ESCAPE '\\'
-            OR search_text LIKE ?3 ESCAPE '\\'
-            OR lower(path) LIKE ?3 ESCAPE '\\'
-            OR lower(name) LIKE ?4 ESCAPE '\\'
-         ORDER BY is_dir DESC, recent_boost DESC, modified_at DESC, name ASC
-         LIMIT ?5",
-    )?;
-
-    let rows = stmt.query_map(
-        params![
-            &normalized_query,
-            &prefix_pattern,
-            &contains_pattern,
-            &first_char_pattern,
-            candidate_limit as i64,
-        ],
-        |row| {
-            let size = row
-                .get::<_, Option<i64>>(6)?
-                .and_then(|value| (value >= 0).then_some(value as u64));
-            Ok(SearchCandidate {
-                result: SearchResult {
-                    path: row.get(0)?,
-                    parent_path: row.get(1)?,
-                    name: row.get(2)?,
-                    display_name: row.get(3)?,
-                    kind: row.get(4)?,
-                    is_dir: row.get::<_, i64>(5)? == 1,
-                    size,
-                    modified_at: row.get(7)?,
-                    rank: 0,
-                    match_reason: SearchMatchReason::Substring,
-                },
-                search_text: row.get(8)?,
-                recent_boost: row.get(9)?,
-                modified_boost: row.get(10)?,
-            })
-        },
-    )?;
+         WHERE {}
+         ORDER BY {order_by}
+         LIMIT ?",
+        clauses.join(" AND ")
+    ))?;
+
+    let rows = stmt.query_map(params_from_iter(values), |row| {
+        let size = row
+            .get::<_, Option<i64>>(6)?
+            .and_then(|value| (value >= 0).then_some(value as u64));
+        Ok(SearchCandidate {
+            result: SearchResult {
+                path: row.get(0)?,
+                parent_path: row.get(1)?,
+                name: row.get(2)?,
+                display_name: row.get(3)?,
+                kind: row.get(4)?,
+                is_dir: row.get::<_, i64>(5)? == 1,
+                size,
+                modified_at: row.get(7)?,
+                rank: 0,
+                match_reason: SearchMatchReason::Substring,
+                snippet: None,
+            },
+            search_text: row.get(8)?,
+            recent_boost: row.get(9)?,
+            modified_boost: row.get(10)?,
+        })
+    })?;
 
     let matcher = SkimMatcherV2::default();
     let mut ranked = Vec::new();
     for row in rows {
         let candidate = row?;
+        // SQL can only exclude ASCII case variants, so check the folded names.
+        if query.excluded_terms.iter().any(|term| {
+            query
+                .normalization
+                .fold(&candidate.result.name)
+                .contains(term.as_str())
+                || query
+                    .normalization
+                    .fold(&candidate.result.display_name)
+                    .contains(term.as_str())
+        }) {
+            continue;
+        }
         if let Some((rank, reason)) =
-            score_search_candidate(&candidate, &normalized_query, &matcher)
+            score_search_candidate(&candidate, normalized_query, query.normalization, &matcher)
         {
             let mut result = candidate.result;
             result.rank = rank;
@@ -889,34 +2175,23 @@ fn search_metadata_impl(
             ranked.push(result);
         }
     }
-
-    ranked.sort_by(|left, right| {
-        right
-            .rank
-            .cmp(&left.rank)
-            .then_with(|| right.is_dir.cmp(&left.is_dir))
-            .then_with(|| {
-                left.name
-                    .to_ascii_lowercase()
-                    .cmp(&right.name.to_ascii_lowercase())
-            })
-            .then_with(|| left.path.cmp(&right.path))
-    });
-    ranked.truncate(result_limit);
     Ok(ranked)
 }
 
 fn score_search_candidate(
     candidate: &SearchCandidate,
     query: &str,
+    normalization: SearchNormalization,
     matcher: &SkimMatcherV2,
 ) -> Option<(i64, SearchMatchReason)> {
-    let name = candidate.result.name.to_ascii_lowercase();
-    let display_name = candidate.result.display_name.to_ascii_lowercase();
-    let path = candidate.result.path.to_ascii_lowercase();
-    let search_text = candidate.search_text.to_ascii_lowercase();
-
-    let (base, reason, fuzzy_score) = if name == query || display_name == query {
+    let name = normalization.fold(&candidate.result.name);
+    let display_name = normalization.fold(&candidate.result.display_name);
+    let path = normalization.fold(&candidate.result.path);
+    let search_text = &candidate.search_text;
+
+    let (base, reason, fuzzy_score) = if query.is_empty() {
+        (0_i64, SearchMatchReason::Filter, 0_i64)
+    } else if name == query || display_name == query {
         (1_000_000_i64, SearchMatchReason::Exact, 0_i64)
     } else if name.starts_with(query) || display_name.starts_with(query) {
         (800_000_i64, SearchMatchReason::Prefix, 0_i64)
@@ -924,10 +2199,10 @@ fn score_search_candidate(
         (600_000_i64, SearchMatchReason::Substring, 0_i64)
     } else {
         let fuzzy_score = [
-            matcher.fuzzy_match(&candidate.result.name, query),
-            matcher.fuzzy_match(&candidate.result.display_name, query),
-            matcher.fuzzy_match(&candidate.result.parent_path, query),
-            matcher.fuzzy_match(&candidate.search_text, query),
+            matcher.fuzzy_match(&name, query),
+            matcher.fuzzy_match(&display_name, query),
+            matcher.fuzzy_match(&normalization.fold(&candidate.result.parent_path), query),
+            matcher.fuzzy_match(search_text, query),
         ]
         .into_iter()
         .flatten()
@@ -956,31 +2231,60 @@ fn escape_sql_like(value: &str) -> String {
 }
 
 fn sort_entries(entries: &mut [FileEntry], sort: &SortState, folders_first: bool) {
-    entries.sort_by(|left, right| {
-        if folders_first && left.is_dir != right.is_dir {
-            return right.is_dir.cmp(&left.is_dir);
+    // Names are folded once per entry rather than on every comparison.
+    entries.sort_by_cached_key(|entry| {
+        let name = NameSortKey::new(&entry.display_name);
+        EntrySortKey {
+            folder_last: folders_first && !entry.is_dir,
+            primary: match sort.key {
+                SortKey::Name => EntrySortField::Name(name.clone()),
+                SortKey::DateModified => EntrySortField::ModifiedAt(entry.modified_at.clone()),
+                SortKey::Size => EntrySortField::Size(entry.size.unwrap_or(0)),
+                SortKey::Kind => EntrySortField::Kind(entry.kind.clone()),
+                SortKey::Path => EntrySortField::Path(entry.path.clone()),
+            },
+            descending: matches!(sort.direction, SortDirection::Desc),
+            name,
         }
+    });
+}
 
-        let primary = compare_entries(left, right, &sort.key);
-        let directed = match sort.direction {
-            SortDirection::Asc => primary,
-            SortDirection::Desc => primary.reverse(),
-        };
+/// Where an entry lands in a listing: folders first when asked, then the
+/// chosen field in the chosen direction, then the name.
+#[derive(PartialEq, Eq)]
+struct EntrySortKey {
+    folder_last: bool,
+    primary: EntrySortField,
+    descending: bool,
+    name: NameSortKey,
+}
 
-        directed.then_with(|| compare_entries(left, right, &SortKey::Name))
-    });
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum EntrySortField {
+    Name(NameSortKey),
+    ModifiedAt(Option<String>),
+    Size(u64),
+    Kind(String),
+    Path(String),
 }
 
-fn compare_entries(left: &FileEntry, right: &FileEntry, key: &SortKey) -> Ordering {
-    match key {
-        SortKey::Name => left
-            .display_name
-            .to_ascii_lowercase()
-            .cmp(&right.display_name.to_ascii_lowercase()),
-        SortKey::DateModified => left.modified_at.cmp(&right.modified_at),
-        SortKey::Size => left.size.unwrap_or(0).cmp(&right.size.unwrap_or(0)),
-        SortKey::Kind => left.kind.cmp(&right.kind),
-        SortKey::Path => left.path.cmp(&right.path),
+impl Ord for EntrySortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let primary = self.primary.cmp(&other.primary);
+        self.folder_last
+            .cmp(&other.folder_last)
+            .then(if self.descending {
+                primary.reverse()
+            } else {
+                primary
+            })
+            .then_with(|| self.name.cmp(&other.name))
+    }
+}
+
+impl PartialOrd for EntrySortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -1004,10 +2308,172 @@ fn is_supported_thumbnail_source(path: &Path) -> bool {
 
     matches!(
         extension.to_ascii_lowercase().as_str(),
-        "jpg" | "jpeg" | "png" | "webp"
+        "jpg" | "jpeg" | "png" | "webp" | "gif" | "bmp" | "tif" | "tiff" | "ico" | "svg"
     )
 }
 
+/// Describes how `path` should be previewed. The renderer comes from the
+/// file's leading bytes rather than its extension, and `metadata` carries
+/// whatever details that renderer can show cheaply. With previews turned off
+/// the file is never opened and only filesystem details are reported.
+fn build_preview_descriptor(
+    path: &Path,
+    previews_enabled: bool,
+) -> Result<PreviewDescriptor, CommandError> {
+    let metadata = std::fs::metadata(path).map_err(|error| preview_read_error(path, error))?;
+    let extension = path.extension().and_then(|value| value.to_str());
+    let mut descriptor = PreviewDescriptor {
+        path: path.to_string_lossy().into_owned(),
+        renderer: PreviewRenderer::FallbackMetadata,
+        display_name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string_lossy().into_owned()),
+        kind: kind_for(metadata.is_dir(), extension).to_string(),
+        size: (!metadata.is_dir()).then_some(metadata.len()),
+        metadata: BTreeMap::new(),
+    };
+    if let Ok(modified) = metadata.modified() {
+        descriptor
+            .metadata
+            .insert("modifiedAt".to_string(), system_time_to_rfc3339(modified));
+    }
+    if let Ok(created) = metadata.created() {
+        descriptor
+            .metadata
+            .insert("createdAt".to_string(), system_time_to_rfc3339(created));
+    }
+
+    if metadata.is_dir() {
+        let item_count = std::fs::read_dir(path)
+            .map_err(|error| preview_read_error(path, error))?
+            .count();
+        descriptor
+            .metadata
+            .insert("itemCount".to_string(), item_count.to_string());
+        return Ok(descriptor);
+    }
+    if !previews_enabled {
+        return Ok(descriptor);
+    }
+
+    let mut header = Vec::with_capacity(preview::SNIFF_BYTES);
+    std::fs::File::open(path)
+        .and_then(|file| {
+            file.take(preview::SNIFF_BYTES as u64)
+                .read_to_end(&mut header)
+        })
+        .map_err(|error| preview_read_error(path, error))?;
+
+    let details = &mut descriptor.metadata;
+    if let Some(signature) = preview::sniff(&header) {
+        descriptor.renderer = signature.renderer;
+        details.insert("format".to_string(), signature.format.to_string());
+        details.insert("mimeType".to_string(), signature.mime_type.to_string());
+        match signature.renderer {
+            PreviewRenderer::Image => {
+                let photo = photo_metadata::read(path).ok().flatten();
+                if let Some(photo) = &photo {
+                    photo.insert_into(details);
+                }
+                // Decoded dimensions replace EXIF ones, which are often stale
+                // after edits.
+                let dimensions = ImageReader::open(path)
+                    .and_then(|reader| reader.with_guessed_format())
+                    .ok()
+                    .and_then(|reader| reader.into_dimensions().ok());
+                if let Some((width, height)) = dimensions {
+                    let (width, height) =
+                        if photo.as_ref().is_some_and(PhotoMetadata::is_transposed) {
+                            (height, width)
+                        } else {
+                            (width, height)
+                        };
+                    details.insert("width".to_string(), width.to_string());
+                    details.insert("height".to_string(), height.to_string());
+                }
+            }
+            PreviewRenderer::Pdf => {
+                if let Some(version) = preview::pdf_version(&header) {
+                    details.insert("pdfVersion".to_string(), version);
+                }
+            }
+            PreviewRenderer::Audio => {
+                if let Some(wav) = preview::wav_format(&header) {
+                    details.insert("channels".to_string(), wav.channels.to_string());
+                    details.insert("sampleRate".to_string(), wav.sample_rate.to_string());
+                    details.insert("bitsPerSample".to_string(), wav.bits_per_sample.to_string());
+                }
+            }
+            PreviewRenderer::Video | PreviewRenderer::Text | PreviewRenderer::FallbackMetadata => {}
+        }
+    } else if preview::looks_like_text(&header) {
+        descriptor.renderer = PreviewRenderer::Text;
+        if let Some(language) = extension.and_then(preview::language_for) {
+            details.insert("language".to_string(), language.to_string());
+        }
+    }
+
+    Ok(descriptor)
+}
+
+/// Reads at most `max_bytes` from the start of `path` for the text renderer.
+/// Source files also get highlight spans.
+fn read_text_preview(path: &Path, max_bytes: usize) -> Result<TextPreview, CommandError> {
+    let metadata = std::fs::metadata(path).map_err(|error| preview_read_error(path, error))?;
+    if metadata.is_dir() {
+        return Err(CommandError::invalid_input(
+            "Folders cannot be previewed as text.",
+            Some(path.to_string_lossy().into_owned()),
+        ));
+    }
+
+    let mut bytes = Vec::with_capacity(max_bytes.min(metadata.len() as usize));
+    std::fs::File::open(path)
+        .and_then(|file| file.take(max_bytes as u64).read_to_end(&mut bytes))
+        .map_err(|error| preview_read_error(path, error))?;
+    let truncated = metadata.len() > bytes.len() as u64;
+    let decoded = preview::decode_text(&bytes, truncated);
+
+    let extension = path.extension().and_then(|value| value.to_str());
+    let language = extension
+        .filter(|_| !decoded.is_binary && file_category(extension) == FileCategory::SourceCode)
+        .and_then(syntax::Language::from_extension);
+    let spans = language
+        .map(|language| syntax::highlight(&decoded.content, language))
+        .unwrap_or_default();
+
+    Ok(TextPreview {
+        path: path.to_string_lossy().into_owned(),
+        line_count: decoded.content.lines().count(),
+        content: decoded.content,
+        encoding: decoded.encoding,
+        is_binary: decoded.is_binary,
+        truncated,
+        language: extension
+            .and_then(preview::language_for)
+            .map(str::to_string),
+        spans,
+    })
+}
+
+fn preview_read_error(path: &Path, error: io::Error) -> CommandError {
+    match error.kind() {
+        io::ErrorKind::NotFound => CommandError::missing_path(
+            "The file no longer exists.",
+            Some(path.to_string_lossy().into_owned()),
+        ),
+        io::ErrorKind::PermissionDenied => CommandError::permission_denied(
+            "Frogger does not have permission to read this file.",
+            Some(path.to_string_lossy().into_owned()),
+        ),
+        _ => CommandError::unavailable(
+            "The file could not be read for preview.",
+            Some(format!("{}: {error}", path.display())),
+        ),
+    }
+}
+
 fn thumbnail_cache_name(
     source_path: &Path,
     source_size: u64,
@@ -1043,21 +2509,12 @@ fn get_or_generate_thumbnail(
         source_size,
         source_modified_at,
     ));
-    let image = ImageReader::open(source_path)
-        .map_err(|error| {
-            CommandError::unavailable(
-                "The image could not be opened for thumbnailing.",
-                Some(error.to_string()),
-            )
-        })?
-        .decode()
-        .map_err(|error| {
-            CommandError::unavailable(
-                "The image could not be decoded for thumbnailing.",
-                Some(error.to_string()),
-            )
-        })?;
-    let thumbnail = image.thumbnail(320, 320);
+    let image = if is_svg_path(source_path) {
+        rasterize_svg(source_path)?
+    } else {
+        decode_raster_image(source_path)?
+    };
+    let thumbnail = image.thumbnail(THUMBNAIL_MAX_EDGE, THUMBNAIL_MAX_EDGE);
     thumbnail.save(&thumbnail_path).map_err(|error| {
         CommandError::internal(format!("thumbnail could not be written: {error}"))
     })?;
@@ -1073,6 +2530,8 @@ fn get_or_generate_thumbnail(
         width,
         height,
     )?;
+    thumbnail_cache::record_generated(conn, source_path, &thumbnail_path)
+        .map_err(CommandError::from)?;
 
     Ok(Some(ThumbnailDescriptor {
         source_path: source_path.to_string_lossy().into_owned(),
@@ -1085,6 +2544,96 @@ fn get_or_generate_thumbnail(
     }))
 }
 
+fn is_svg_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|value| value.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+}
+
+/// Decodes a raster image upright. Animated GIFs decode to their first frame
+/// and icons to their largest entry.
+fn decode_raster_image(path: &Path) -> Result<DynamicImage, CommandError> {
+    let mut image = ImageReader::open(path)
+        .map_err(|error| {
+            CommandError::unavailable(
+                "The image could not be opened for thumbnailing.",
+                Some(error.to_string()),
+            )
+        })?
+        .decode()
+        .map_err(|error| {
+            CommandError::unavailable(
+                "The image could not be decoded for thumbnailing.",
+                Some(error.to_string()),
+            )
+        })?;
+    // Missing or unreadable EXIF just leaves the image as stored.
+    let orientation = photo_metadata::read(path)
+        .ok()
+        .flatten()
+        .and_then(|photo| photo.orientation);
+    photo_metadata::apply_orientation(&mut image, orientation);
+    Ok(image)
+}
+
+/// Renders an SVG so its longer edge is `THUMBNAIL_MAX_EDGE` pixels. Relative
+/// image references resolve next to the file, and text uses system fonts.
+fn rasterize_svg(path: &Path) -> Result<DynamicImage, CommandError> {
+    let data = std::fs::read(path).map_err(|error| {
+        CommandError::unavailable(
+            "The image could not be opened for thumbnailing.",
+            Some(error.to_string()),
+        )
+    })?;
+    let options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        fontdb: svg_font_database(),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_data(&data, &options).map_err(|error| {
+        CommandError::unavailable(
+            "The image could not be decoded for thumbnailing.",
+            Some(error.to_string()),
+        )
+    })?;
+
+    let size = tree.size();
+    let scale = THUMBNAIL_MAX_EDGE as f32 / size.width().max(size.height());
+    let width = ((size.width() * scale).round() as u32).clamp(1, THUMBNAIL_MAX_EDGE);
+    let height = ((size.height() * scale).round() as u32).clamp(1, THUMBNAIL_MAX_EDGE);
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| CommandError::internal("thumbnail canvas could not be allocated"))?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(width, height, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| CommandError::internal("rendered SVG had an unexpected size"))
+}
+
+/// System fonts are scanned once and shared by every SVG render.
+fn svg_font_database() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut database = usvg::fontdb::Database::new();
+            database.load_system_fonts();
+            Arc::new(database)
+        })
+        .clone()
+}
+
 fn lookup_valid_thumbnail(
     conn: &Connection,
     source_path: &Path,
@@ -1222,6 +2771,16 @@ fn is_recents_virtual_path(path: &str) -> bool {
     path == RECENTS_VIRTUAL_PATH || path == "frogger://recents"
 }
 
+fn smart_folder_id_from_path(path: &str) -> Option<&str> {
+    path.strip_prefix(SMART_FOLDER_PATH_PREFIX)
+        .or_else(|| path.strip_prefix("frogger://smart-folder/"))
+        .filter(|id| !id.is_empty())
+}
+
+fn smart_folder_path(id: &str) -> String {
+    format!("{SMART_FOLDER_PATH_PREFIX}{id}")
+}
+
 fn system_time_to_rfc3339(value: SystemTime) -> String {
     DateTime::<Utc>::from(value).to_rfc3339()
 }
@@ -1244,38 +2803,19 @@ fn fs_access_error(path: &Path, error: io::Error) -> CommandError {
 }
 
 fn record_recent_path(conn: &Connection, path: &str) -> Result<(), CommandError> {
-    let kind = if Path::new(path).is_dir() {
-        "folder"
-    } else {
-        "file"
-    };
-
-    conn.execute(
-        "INSERT INTO recents (id, path, kind, opened_at, open_count)
-         VALUES (?1, ?2, ?3, ?4, 1)
-         ON CONFLICT(path) DO UPDATE SET
-            kind = excluded.kind,
-            opened_at = excluded.opened_at,
-            open_count = recents.open_count + 1",
-        params![
-            format!("recent-{}", Uuid::new_v4()),
-            path,
-            kind,
-            Utc::now().to_rfc3339()
-        ],
-    )
-    .map(|_| ())
-    .map_err(anyhow::Error::from)
-    .map_err(CommandError::from)
+    frecency::record_open(conn, path, Utc::now()).map_err(CommandError::from)
 }
 
 fn open_app_database(app: &tauri::AppHandle) -> Result<Connection, CommandError> {
-    let database_path = app
+    persistence::open_database(&app_database_path(app)?).map_err(CommandError::from)
+}
+
+fn app_database_path(app: &tauri::AppHandle) -> Result<PathBuf, CommandError> {
+    Ok(app
         .path()
         .app_data_dir()
         .map_err(|error| CommandError::internal(error.to_string()))?
-        .join("frogger.sqlite3");
-    persistence::open_database(&database_path).map_err(CommandError::from)
+        .join("frogger.sqlite3"))
 }
 
 fn home_dir_string() -> Option<String> {
@@ -1329,8 +2869,24 @@ fn load_settings(conn: &Connection) -> Result<AppSettings> {
         folders_first: bool_setting(&raw, "browser.foldersFirst", true),
         path_bar_visible: bool_setting(&raw, "browser.pathBarVisible", true),
         restore_enabled: bool_setting(&raw, "restore.enabled", true),
-        local_only_indexing: bool_setting(&raw, "privacy.localOnlyIndexing", true),
+        local_only_indexing: bool_setting(&raw, search_history::LOCAL_ONLY_INDEXING_SETTING, true),
         previews_enabled: bool_setting(&raw, "previews.enabled", true),
+        indexing_entries_per_second: raw
+            .get("indexing.entriesPerSecond")
+            .and_then(|value| value.parse::<u32>().ok())
+            .filter(|limit| *limit > 0),
+        search_ignore_diacritics: bool_setting(
+            &raw,
+            normalization::IGNORE_DIACRITICS_SETTING,
+            SearchNormalization::default().ignore_diacritics,
+        ),
+        search_content_indexing: bool_setting(&raw, content_index::CONTENT_INDEXING_SETTING, false),
+        search_photo_metadata_indexing: bool_setting(
+            &raw,
+            photo_metadata::PHOTO_METADATA_INDEXING_SETTING,
+            false,
+        ),
+        thumbnail_cache_budget_bytes: thumbnail_cache::budget_from_settings(&raw),
         list_column_visibility: list_column_visibility(&raw),
         list_column_widths: list_column_widths(&raw),
         raw,
@@ -1468,6 +3024,8 @@ fn load_indexing_state(conn: &Connection) -> Result<IndexingState> {
             "initial_build" => IndexingStatus::InitialBuild,
             "reconciling" => IndexingStatus::Reconciling,
             "ready" => IndexingStatus::Ready,
+            "paused" => IndexingStatus::Paused,
+            "cancelled" => IndexingStatus::Cancelled,
             "failed" => IndexingStatus::Failed,
             _ => IndexingStatus::NotStarted,
         },
@@ -1671,7 +3229,10 @@ fn load_valid_tabs_for_window(conn: &Connection, window_id: &str) -> Result<Vec<
     let mut tabs = Vec::new();
     for row in rows {
         let mut tab = row?;
-        if !is_recents_virtual_path(&tab.path) && !Path::new(&tab.path).is_dir() {
+        if !is_recents_virtual_path(&tab.path)
+            && smart_folder_id_from_path(&tab.path).is_none()
+            && !Path::new(&tab.path).is_dir()
+        {
             continue;
         }
 
@@ -1863,6 +3424,7 @@ fn load_sidebar_state(conn: &Connection, home_dir: Option<String>) -> Result<Sid
         recent_items: load_recent_items(conn)?,
         favorites: load_favorites(conn)?,
         locations: detect_locations(home_dir),
+        smart_folders: load_smart_folders(conn)?,
         recents_virtual_folder_id: "recents".to_string(),
     })
 }
@@ -1911,6 +3473,12 @@ fn load_sidebar_sections(conn: &Connection) -> Result<Vec<SidebarSectionState>>
                 visible: true,
                 position: 2,
             },
+            SidebarSectionState {
+                id: SidebarSectionId::SmartFolders,
+                label: "Smart Folders".to_string(),
+                visible: true,
+                position: 3,
+            },
         ];
     }
 
@@ -1968,6 +3536,26 @@ fn load_favorites(conn: &Connection) -> Result<Vec<SidebarItem>> {
     Ok(items)
 }
 
+fn load_smart_folders(conn: &Connection) -> Result<Vec<SidebarItem>> {
+    let mut stmt = conn.prepare("SELECT id, label FROM smart_folders ORDER BY position, label")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut items = Vec::new();
+    for row in rows {
+        let (id, label) = row?;
+        items.push(SidebarItem {
+            path: smart_folder_path(&id),
+            id,
+            label,
+            item_type: SidebarItemType::SmartFolder,
+        });
+    }
+
+    Ok(items)
+}
+
 fn detect_locations(home_dir: Option<String>) -> Vec<SidebarItem> {
     let mut locations_by_path: BTreeMap<String, SidebarItem> = BTreeMap::new();
 
@@ -2095,6 +3683,7 @@ fn sidebar_section_from_db(value: &str) -> Option<SidebarSectionId> {
         "recents" => Some(SidebarSectionId::Recents),
         "favorites" => Some(SidebarSectionId::Favorites),
         "locations" => Some(SidebarSectionId::Locations),
+        "smartFolders" => Some(SidebarSectionId::SmartFolders),
         _ => None,
     }
 }
@@ -2104,6 +3693,7 @@ fn sidebar_section_to_db(value: &SidebarSectionId) -> &'static str {
         SidebarSectionId::Recents => "recents",
         SidebarSectionId::Favorites => "favorites",
         SidebarSectionId::Locations => "locations",
+        SidebarSectionId::SmartFolders => "smartFolders",
     }
 }
 
@@ -2112,6 +3702,7 @@ fn sidebar_section_label(value: &SidebarSectionId) -> &'static str {
         SidebarSectionId::Recents => "Recents",
         SidebarSectionId::Favorites => "Favorites",
         SidebarSectionId::Locations => "Locations",
+        SidebarSectionId::SmartFolders => "Smart Folders",
     }
 }
 
@@ -2119,7 +3710,7 @@ fn build_capabilities() -> AppCapabilities {
     AppCapabilities {
         native_titlebar_tabs: cfg!(target_os = "macos"),
         open_with_chooser: true,
-        reliable_trash_undo: false,
+        reliable_trash_undo: trash_bin::PUT_BACK_SUPPORTED,
         outbound_file_drag: true,
         cloud_placeholder_detection: cfg!(any(target_os = "macos", target_os = "windows")),
     }
@@ -2128,6 +3719,8 @@ fn build_capabilities() -> AppCapabilities {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::SyntaxToken;
+    use crate::persistence::test_support::{insert_metadata_row, TestMetadataRow};
     use tempfile::tempdir;
     use uuid::Uuid;
 
@@ -2187,7 +3780,7 @@ mod tests {
         let path = std::env::temp_dir().join(format!("frogger-search-{}.sqlite3", Uuid::new_v4()));
         let conn = persistence::open_database(&path).expect("database should migrate");
 
-        insert_metadata_search_row(
+        insert_metadata_row(
             &conn,
             TestMetadataRow {
                 path: "/tmp/project",
@@ -2198,9 +3791,10 @@ mod tests {
                 is_dir: true,
                 size: None,
                 search_text: "project folder tmp",
+                ..Default::default()
             },
         );
-        insert_metadata_search_row(
+        insert_metadata_row(
             &conn,
             TestMetadataRow {
                 path: "/tmp/project-notes.md",
@@ -2211,9 +3805,10 @@ mod tests {
                 is_dir: false,
                 size: Some(42),
                 search_text: "project-notes markdown document tmp",
+                ..Default::default()
             },
         );
-        insert_metadata_search_row(
+        insert_metadata_row(
             &conn,
             TestMetadataRow {
                 path: "/tmp/other.txt",
@@ -2224,10 +3819,12 @@ mod tests {
                 is_dir: false,
                 size: Some(5),
                 search_text: "other text document tmp",
+                ..Default::default()
             },
         );
 
-        let results = search_metadata_impl(&conn, "project", Some(10)).expect("search should run");
+        let results = search_metadata_impl(&conn, &search_query("project"), Some(10))
+            .expect("search should run");
 
         assert_eq!(results.len(), 2);
         assert_eq!(results[0].path, "/tmp/project");
@@ -2244,7 +3841,7 @@ mod tests {
             std::env::temp_dir().join(format!("frogger-search-fuzzy-{}.sqlite3", Uuid::new_v4()));
         let conn = persistence::open_database(&path).expect("database should migrate");
 
-        insert_metadata_search_row(
+        insert_metadata_row(
             &conn,
             TestMetadataRow {
                 path: "/tmp/README.md",
@@ -2255,10 +3852,12 @@ mod tests {
                 is_dir: false,
                 size: Some(100),
                 search_text: "readme markdown document tmp",
+                ..Default::default()
             },
         );
 
-        let results = search_metadata_impl(&conn, "rdm", Some(10)).expect("search should run");
+        let results =
+            search_metadata_impl(&conn, &search_query("rdm"), Some(10)).expect("search should run");
 
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].path, "/tmp/README.md");
@@ -2267,34 +3866,610 @@ mod tests {
         std::fs::remove_file(path).ok();
     }
 
-    struct TestMetadataRow<'a> {
-        path: &'a str,
-        parent_path: &'a str,
-        name: &'a str,
-        display_name: &'a str,
-        kind: &'a str,
-        is_dir: bool,
-        size: Option<i64>,
-        search_text: &'a str,
+    #[test]
+    fn search_metadata_applies_filters_and_ranks_free_text_only() {
+        let path =
+            std::env::temp_dir().join(format!("frogger-search-filters-{}.sqlite3", Uuid::new_v4()));
+        let conn = persistence::open_database(&path).expect("database should migrate");
+
+        for (name, is_dir, size, modified_at, parent_path) in [
+            (
+                "report.pdf",
+                false,
+                20 * 1024 * 1024,
+                "2025-06-10T09:00:00+00:00",
+                "/Users/frog/Projects",
+            ),
+            (
+                "report-draft.pdf",
+                false,
+                30 * 1024 * 1024,
+                "2025-06-12T09:00:00+00:00",
+                "/Users/frog/Projects",
+            ),
+            (
+                "report.md",
+                false,
+                2048,
+                "2025-06-11T09:00:00+00:00",
+                "/Users/frog/Projects",
+            ),
+            (
+                "old-report.pdf",
+                false,
+                40 * 1024 * 1024,
+                "2024-01-01T09:00:00+00:00",
+                "/Users/frog/Archive",
+            ),
+        ] {
+            let file_path = format!("{parent_path}/{name}");
+            insert_metadata_row(
+                &conn,
+                TestMetadataRow {
+                    path: &file_path,
+                    parent_path,
+                    name,
+                    display_name: name,
+                    kind: if name.ends_with(".pdf") {
+                        "PDF Document"
+                    } else {
+                        "Markdown Document"
+                    },
+                    is_dir,
+                    size: Some(size),
+                    search_text: name,
+                    ..Default::default()
+                },
+            );
+            conn.execute(
+                "UPDATE metadata_index SET modified_at = ?1, extension = ?2 WHERE path = ?3",
+                params![modified_at, name.rsplit('.').next(), file_path],
+            )
+            .expect("metadata row should update");
+        }
+
+        let results = search_metadata_impl(
+            &conn,
+            &search_query("report kind:pdf size:>10mb in:~/Projects -draft"),
+            Some(10),
+        )
+        .expect("search should run");
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["report.pdf"]
+        );
+        assert_eq!(results[0].match_reason, SearchMatchReason::Prefix);
+
+        let results =
+            search_metadata_impl(&conn, &search_query("ext:md,pdf modified:2025"), Some(10))
+                .expect("filter-only search should run");
+        assert_eq!(results.len(), 3);
+        assert!(results
+            .iter()
+            .all(|result| result.match_reason == SearchMatchReason::Filter));
+
+        let results =
+            search_metadata_impl(&conn, &search_query("\"report.pdf\" -ext:md"), Some(10))
+                .expect("phrase search should run");
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["report.pdf", "old-report.pdf"]
+        );
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn search_metadata_full_text_candidates_rank_like_a_scan() {
+        let path =
+            std::env::temp_dir().join(format!("frogger-search-fts-{}.sqlite3", Uuid::new_v4()));
+        let mut conn = persistence::open_database(&path).expect("database should migrate");
+
+        let words = [
+            "report", "project", "notes", "invoice", "draft", "photo", "budget", "readme",
+            "summary", "archive",
+        ];
+        let extensions = ["md", "pdf", "txt", "rs", "png"];
+        let folders = [
+            "/Users/frog/Projects",
+            "/Users/frog/Documents",
+            "/tmp/scratch",
+        ];
+        let mut seed = 0x2545_f491_u64;
+        let tx = conn.transaction().expect("transaction should start");
+        for index in 0..6_000_i64 {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            let first = words[(seed >> 33) as usize % words.len()];
+            let second = words[(seed >> 41) as usize % words.len()];
+            let parent_path = folders[(seed >> 49) as usize % folders.len()];
+            let is_dir = index % 7 == 0;
+            let extension = extensions[(seed >> 53) as usize % extensions.len()];
+            let display_name = format!("{first}-{second}-{index}");
+            let name = if is_dir {
+                display_name.clone()
+            } else {
+                format!("{display_name}.{extension}")
+            };
+            let file_path = format!("{parent_path}/{name}");
+            let search_text = format!("{name} {display_name} {parent_path}").to_ascii_lowercase();
+            insert_metadata_row(
+                &tx,
+                TestMetadataRow {
+                    path: &file_path,
+                    parent_path,
+                    name: &name,
+                    display_name: &display_name,
+                    kind: if is_dir { "Folder" } else { "Document" },
+                    is_dir,
+                    size: (!is_dir).then_some(index),
+                    search_text: &search_text,
+                    ..Default::default()
+                },
+            );
+        }
+        tx.commit().expect("transaction should commit");
+
+        for (query, limit) in [
+            ("report", 50),
+            ("notes-draft", 20),
+            ("frog/projects/read", 200),
+            ("budget-summary-1", 10),
+            ("ARCHIVE.PDF", 50),
+            ("rdm", 50),
+            ("invoice ext:pdf -draft", 30),
+            ("nothing-matches-this", 50),
+            ("phto", 5),
+        ] {
+            let query = search_query(query);
+            let full_text =
+                search_metadata_from(&conn, &query, Some(limit), CandidateSource::FullText)
+                    .expect("full-text search should run");
+            let mut every_row =
+                rank_search_candidates(&conn, &query, CandidateSource::Scan, 1_000_000)
+                    .expect("unbounded scan should run");
+            sort_search_results(&mut every_row);
+            every_row.truncate(limit);
+            assert_eq!(full_text, every_row, "{query:?}");
+        }
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn search_metadata_folds_case_normalization_and_diacritics() {
+        let path =
+            std::env::temp_dir().join(format!("frogger-search-fold-{}.sqlite3", Uuid::new_v4()));
+        let mut conn = persistence::open_database(&path).expect("database should migrate");
+
+        for (name, display_name) in [
+            ("Re\u{301}sume\u{301}.pdf", "Re\u{301}sume\u{301}"),
+            ("ÄBC Notes.txt", "ÄBC Notes"),
+            ("resume-template.docx", "resume-template"),
+        ] {
+            insert_metadata_row(
+                &conn,
+                TestMetadataRow {
+                    path: &format!("/Users/frog/{name}"),
+                    parent_path: "/Users/frog",
+                    name,
+                    display_name,
+                    kind: "Document",
+                    is_dir: false,
+                    size: Some(1),
+                    search_text: name,
+                    ..Default::default()
+                },
+            );
+        }
+        indexing::refresh_search_text(&mut conn, SearchNormalization::default())
+            .expect("search text should refresh");
+
+        let results = search_metadata_impl(&conn, &search_query("RÉSUMÉ"), Some(10))
+            .expect("search should run");
+        assert_eq!(results[0].name, "Re\u{301}sume\u{301}.pdf");
+        assert_eq!(results[0].match_reason, SearchMatchReason::Exact);
+        assert_eq!(results[1].name, "resume-template.docx");
+
+        let results =
+            search_metadata_impl(&conn, &search_query("äbc"), Some(10)).expect("search should run");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].match_reason, SearchMatchReason::Prefix);
+
+        let strict = SearchNormalization {
+            ignore_diacritics: false,
+        };
+        let updated = indexing::set_search_normalization(&mut conn, strict)
+            .expect("normalization should update");
+        assert_eq!(updated, 2);
+        let query =
+            SearchQuery::parse("résumé", None, Local::now(), strict).expect("query should parse");
+        let results = search_metadata_impl(&conn, &query, Some(10)).expect("search should run");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "Re\u{301}sume\u{301}.pdf");
+        assert!(
+            !load_settings(&conn)
+                .expect("settings should load")
+                .search_ignore_diacritics
+        );
+
+        std::fs::remove_file(path).ok();
     }
 
-    fn insert_metadata_search_row(conn: &Connection, row: TestMetadataRow<'_>) {
+    #[test]
+    fn full_text_candidate_window_keeps_folded_exact_names() {
+        let path =
+            std::env::temp_dir().join(format!("frogger-search-window-{}.sqlite3", Uuid::new_v4()));
+        let mut conn = persistence::open_database(&path).expect("database should migrate");
+
+        for (name, display_name) in [("Écran.png", "Écran"), ("Straße", "Straße")] {
+            insert_metadata_row(
+                &conn,
+                TestMetadataRow {
+                    path: &format!("/Users/frog/{name}"),
+                    parent_path: "/Users/frog",
+                    name,
+                    display_name,
+                    kind: "Document",
+                    is_dir: false,
+                    size: Some(1),
+                    search_text: name,
+                    ..Default::default()
+                },
+            );
+        }
+        for index in 0..20 {
+            for stem in ["ecran", "strasse"] {
+                let name = format!("old-{stem}-{index}.txt");
+                insert_metadata_row(
+                    &conn,
+                    TestMetadataRow {
+                        path: &format!("/Users/frog/{name}"),
+                        parent_path: "/Users/frog",
+                        name: &name,
+                        display_name: &name,
+                        kind: "Document",
+                        is_dir: false,
+                        size: Some(1),
+                        search_text: &name,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
         conn.execute(
-            "INSERT INTO metadata_index (
-                path, parent_path, name, display_name, kind, is_dir, size, search_text
-             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![
-                row.path,
-                row.parent_path,
-                row.name,
-                row.display_name,
-                row.kind,
-                if row.is_dir { 1_i64 } else { 0_i64 },
-                row.size,
-                row.search_text,
-            ],
+            "UPDATE metadata_index SET recent_boost = 0.5 WHERE name LIKE 'old-%'",
+            [],
+        )
+        .expect("boosts should update");
+        indexing::refresh_search_text(&mut conn, SearchNormalization::default())
+            .expect("search text should refresh");
+
+        for (query, expected) in [("écran", "Écran.png"), ("STRASSE", "Straße")] {
+            let ranked =
+                rank_search_candidates(&conn, &search_query(query), CandidateSource::FullText, 3)
+                    .expect("full-text candidates should rank");
+            assert_eq!(ranked[0].name, expected, "{query:?}");
+            assert_eq!(ranked[0].match_reason, SearchMatchReason::Exact);
+        }
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn search_pattern_matches_names_and_paths() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        for (path, parent_path, name, is_dir) in [
+            ("/src/app.test.ts", "/src", "app.test.ts", false),
+            ("/src/app.ts", "/src", "app.ts", false),
+            ("/src/tests", "/src", "tests", true),
+            ("/photos/IMG_2024.jpg", "/photos", "IMG_2024.jpg", false),
+            ("/photos/IMG_24.jpg", "/photos", "IMG_24.jpg", false),
+        ] {
+            insert_metadata_row(
+                &conn,
+                TestMetadataRow {
+                    path,
+                    parent_path,
+                    name,
+                    display_name: name,
+                    kind: if is_dir { "Folder" } else { "Document" },
+                    is_dir,
+                    size: None,
+                    search_text: name,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let glob = NamePattern::compile("**/*.test.ts", SearchPatternMode::Glob)
+            .expect("glob should compile");
+        let results =
+            search_pattern_impl(&conn, &glob, None, || true).expect("pattern search should run");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "/src/app.test.ts");
+        assert_eq!(results[0].match_reason, SearchMatchReason::Pattern);
+
+        let regex = NamePattern::compile(r"^img_[0-9]{4}\.jpg$", SearchPatternMode::Regex)
+            .expect("regex should compile");
+        let results =
+            search_pattern_impl(&conn, &regex, None, || true).expect("pattern search should run");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "IMG_2024.jpg");
+
+        let broad =
+            NamePattern::compile("*", SearchPatternMode::Glob).expect("glob should compile");
+        let results = search_pattern_impl(&conn, &broad, Some(2), || true)
+            .expect("pattern search should run");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "tests");
+
+        let photos = NamePattern::compile("img_*.JPG", SearchPatternMode::Glob)
+            .expect("glob should compile");
+        let results =
+            search_pattern_impl(&conn, &photos, None, || true).expect("pattern search should run");
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn superseded_pattern_scans_stop_early() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        for index in 0..PATTERN_SCAN_CHECK_INTERVAL {
+            let path = format!("/tmp/log-{index}.txt");
+            insert_metadata_row(&conn, TestMetadataRow::file(&path, "Text Document"));
+        }
+        let regex = NamePattern::compile(r"log-\d+", SearchPatternMode::Regex)
+            .expect("regex should compile");
+
+        let results =
+            search_pattern_impl(&conn, &regex, None, || true).expect("pattern search should run");
+        assert_eq!(results.len(), 50);
+        let results =
+            search_pattern_impl(&conn, &regex, None, || false).expect("pattern search should run");
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn content_search_finds_document_text_with_snippets() {
+        let mut conn = persistence::open_memory_database().expect("database should migrate");
+        let temp = tempdir().expect("tempdir should exist");
+        std::fs::write(
+            temp.path().join("standup.md"),
+            "Notes: the launch checklist is due Friday.",
+        )
+        .expect("file should write");
+        std::fs::write(temp.path().join("launch.txt"), "See the launch checklist.")
+            .expect("file should write");
+        std::fs::write(temp.path().join("ideas.rs"), "// nothing relevant here")
+            .expect("file should write");
+        let rules = index_config::IndexRules::load(&conn).expect("rules should load");
+        indexing::snapshot_folder(
+            &mut conn,
+            temp.path(),
+            rules,
+            SearchNormalization::default(),
+            100,
+            Instant::now() + Duration::from_secs(5),
+        )
+        .expect("folder should index");
+
+        let query = search_query("launch checklist");
+        let before = search_metadata_impl(&conn, &query, Some(10)).expect("search should run");
+        assert!(before.iter().all(|result| result.snippet.is_none()));
+        assert!(!before.iter().any(|result| result.name == "standup.md"));
+
+        content_index::set_enabled(&mut conn, true).expect("setting should save");
+        content_index::sync(&mut conn, content_index::SYNC_BATCH_SIZE).expect("sync should run");
+        let results = search_metadata_impl(&conn, &query, Some(10)).expect("search should run");
+        let standup = results
+            .iter()
+            .find(|result| result.name == "standup.md")
+            .expect("document text should match");
+        assert_eq!(standup.match_reason, SearchMatchReason::Content);
+        let highlighted = standup
+            .snippet
+            .as_ref()
+            .expect("content hits should carry a snippet")
+            .segments
+            .iter()
+            .filter(|segment| segment.highlighted)
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(highlighted, vec!["launch", "checklist"]);
+        assert!(!results.iter().any(|result| result.name == "ideas.rs"));
+
+        let filtered = search_metadata_impl(&conn, &search_query("checklist ext:txt"), Some(10))
+            .expect("search should run");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].name, "launch.txt");
+        assert!(
+            load_settings(&conn)
+                .expect("settings should load")
+                .search_content_indexing
+        );
+    }
+
+    #[test]
+    fn search_in_folder_uses_index_for_covered_folders() {
+        let temp = tempdir().expect("tempdir should exist");
+        let root = temp.path().join("home");
+        std::fs::create_dir_all(root.join("projects/app")).expect("folders should exist");
+        std::fs::write(root.join("projects/app/notes.md"), "notes").expect("file should write");
+        std::fs::write(root.join("projects/notes-old.md"), "old").expect("file should write");
+        std::fs::write(root.join("notes-elsewhere.md"), "elsewhere").expect("file should write");
+        let database_path = temp.path().join("frogger.sqlite3");
+        let conn = persistence::open_database(&database_path).expect("database should migrate");
+        index_config::add_index_root(&conn, &root.to_string_lossy()).expect("root should add");
+        indexing::run_metadata_index(&database_path, vec![root.clone()], |_| {})
+            .expect("index should build");
+        std::fs::write(root.join("projects/notes-unindexed.md"), "new").expect("file should write");
+
+        let request = FolderSearchRequest {
+            path: root.join("projects").to_string_lossy().into_owned(),
+            query: "notes".to_string(),
+            cursor: None,
+            limit: Some(1),
+        };
+        let page = search_in_folder_impl(&conn, &request, None, Local::now())
+            .expect("folder search should run");
+        assert_eq!(page.source, FolderSearchSource::Index);
+        assert_eq!(page.total_count, 2);
+        assert_eq!(page.results[0].name, "notes-old.md");
+        assert_eq!(page.next_cursor.as_deref(), Some("1"));
+        assert!(!page.loading_complete);
+
+        let page = search_in_folder_impl(
+            &conn,
+            &FolderSearchRequest {
+                cursor: page.next_cursor,
+                ..request
+            },
+            None,
+            Local::now(),
+        )
+        .expect("second page should load");
+        assert_eq!(page.results[0].name, "notes.md");
+        assert!(page.loading_complete);
+    }
+
+    #[test]
+    fn search_in_folder_walks_folders_outside_the_index() {
+        let temp = tempdir().expect("tempdir should exist");
+        let volume = temp.path().join("usb");
+        std::fs::create_dir_all(volume.join("photos/2024")).expect("folders should exist");
+        std::fs::create_dir_all(volume.join("node_modules/pkg")).expect("folders should exist");
+        std::fs::write(volume.join("photos/2024/beach.jpg"), "jpg").expect("file should write");
+        std::fs::write(volume.join("photos/beach-notes.txt"), "txt").expect("file should write");
+        std::fs::write(volume.join("node_modules/pkg/beach.js"), "js").expect("file should write");
+        let database_path = temp.path().join("frogger.sqlite3");
+        let conn = persistence::open_database(&database_path).expect("database should migrate");
+
+        let page = search_in_folder_impl(
+            &conn,
+            &FolderSearchRequest {
+                path: volume.to_string_lossy().into_owned(),
+                query: "beach ext:jpg,txt".to_string(),
+                cursor: None,
+                limit: None,
+            },
+            None,
+            Local::now(),
+        )
+        .expect("folder search should run");
+        assert_eq!(page.source, FolderSearchSource::LiveWalk);
+        assert!(page.walk_complete);
+        assert_eq!(
+            page.results
+                .iter()
+                .map(|result| result.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["beach-notes.txt", "beach.jpg"]
+        );
+        assert!(page.loading_complete);
+    }
+
+    #[test]
+    fn search_sessions_emit_a_quick_batch_then_page_the_full_ranking() {
+        let path =
+            std::env::temp_dir().join(format!("frogger-search-session-{}.sqlite3", Uuid::new_v4()));
+        let conn = persistence::open_database(&path).expect("database should migrate");
+        for index in 0..30 {
+            let name = format!("invoice-{index:02}.pdf");
+            insert_metadata_row(
+                &conn,
+                TestMetadataRow {
+                    path: &format!("/Users/frog/{name}"),
+                    parent_path: "/Users/frog",
+                    name: &name,
+                    display_name: &name,
+                    kind: "PDF Document",
+                    is_dir: false,
+                    size: Some(index),
+                    search_text: &name,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let sessions = SearchSessions::default();
+        let id = sessions.begin(conn.get_interrupt_handle());
+        let mut batches = Vec::new();
+        run_search_session(
+            &conn,
+            &sessions,
+            &id,
+            "invoice",
+            &search_query("invoice"),
+            10,
+            |batch| batches.push(batch),
+        )
+        .expect("session should run");
+
+        assert_eq!(batches.len(), 2);
+        assert!(!batches[0].complete);
+        assert_eq!(batches[0].results.len(), 10);
+        assert!(batches[1].complete);
+        assert_eq!(batches[1].total_count, 30);
+        assert_eq!(batches[1].results, batches[0].results);
+
+        let page = sessions
+            .page(&id, Some("10"), Some(15))
+            .expect("finished session should page");
+        assert_eq!(page.results.len(), 15);
+        assert_eq!(page.results[0].name, "invoice-10.pdf");
+        assert_eq!(page.next_cursor.as_deref(), Some("25"));
+        assert!(!page.loading_complete);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn superseded_search_sessions_stop_without_emitting() {
+        let path =
+            std::env::temp_dir().join(format!("frogger-search-stale-{}.sqlite3", Uuid::new_v4()));
+        let conn = persistence::open_database(&path).expect("database should migrate");
+        let sessions = SearchSessions::default();
+        let stale = sessions.begin(conn.get_interrupt_handle());
+        let current = sessions.begin(conn.get_interrupt_handle());
+
+        let mut batches = Vec::new();
+        run_search_session(
+            &conn,
+            &sessions,
+            &stale,
+            "notes",
+            &search_query("notes"),
+            10,
+            |batch| batches.push(batch),
+        )
+        .expect("stale session should stop quietly");
+
+        assert!(batches.is_empty());
+        assert!(sessions.is_current(&current));
+        assert_eq!(
+            sessions
+                .page(&stale, None, None)
+                .expect_err("stale session should have no results")
+                .code,
+            "unavailable"
+        );
+
+        std::fs::remove_file(path).ok();
+    }
+
+    fn search_query(query: &str) -> SearchQuery {
+        SearchQuery::parse(
+            query,
+            Some("/Users/frog"),
+            Local::now(),
+            SearchNormalization::default(),
         )
-        .expect("metadata search row should insert");
+        .expect("query should parse")
     }
 
     #[test]
@@ -2314,6 +4489,11 @@ mod tests {
             restore_enabled: true,
             local_only_indexing: true,
             previews_enabled: true,
+            indexing_entries_per_second: None,
+            search_ignore_diacritics: true,
+            search_content_indexing: false,
+            search_photo_metadata_indexing: false,
+            thumbnail_cache_budget_bytes: thumbnail_cache::DEFAULT_CACHE_BUDGET_BYTES,
             list_column_visibility: BTreeMap::new(),
             list_column_widths: BTreeMap::new(),
             raw: BTreeMap::new(),
@@ -2797,6 +4977,91 @@ mod tests {
         std::fs::remove_file(database_path).ok();
     }
 
+    #[test]
+    fn smart_folders_list_saved_searches_with_sort_paging_and_hidden_files() {
+        let mut conn = persistence::open_memory_database().expect("database should migrate");
+        let temp = tempdir().expect("tempdir should exist");
+        std::fs::write(temp.path().join("beta-report.txt"), "beta").expect("file should write");
+        std::fs::write(temp.path().join("alpha-report.txt"), "alpha").expect("file should write");
+        std::fs::write(temp.path().join(".hidden-report.txt"), "hidden")
+            .expect("file should write");
+        std::fs::write(temp.path().join("notes.md"), "notes").expect("file should write");
+        let rules = index_config::IndexRules::load(&conn).expect("rules should load");
+        indexing::snapshot_folder(
+            &mut conn,
+            temp.path(),
+            rules,
+            SearchNormalization::default(),
+            100,
+            Instant::now() + Duration::from_secs(5),
+        )
+        .expect("folder should index");
+
+        assert!(matches!(
+            save_smart_folder_impl(&conn, None, "  ", "report", None),
+            Err(error) if error.code == "invalid_input"
+        ));
+        assert!(matches!(
+            save_smart_folder_impl(&conn, None, "Reports", "size:huge", None),
+            Err(error) if error.code == "invalid_input"
+        ));
+        let id = save_smart_folder_impl(&conn, None, "Reports", "report ext:txt", None)
+            .expect("smart folder should save");
+
+        let sidebar = load_sidebar_state(&conn, None).expect("sidebar should load");
+        assert_eq!(sidebar.smart_folders.len(), 1);
+        assert_eq!(sidebar.smart_folders[0].label, "Reports");
+        assert_eq!(sidebar.smart_folders[0].path, smart_folder_path(&id));
+        assert!(sidebar
+            .sections
+            .iter()
+            .any(|section| section.id == SidebarSectionId::SmartFolders));
+
+        let mut request = DirectoryListRequest {
+            path: format!("frogger://smart-folder/{id}"),
+            sort: SortState {
+                key: SortKey::Name,
+                direction: SortDirection::Desc,
+            },
+            folders_first: true,
+            hidden_files_visible: false,
+            file_extensions_visible: true,
+            cursor: None,
+            limit: Some(1),
+        };
+        let smart_folder_id =
+            smart_folder_id_from_path(&request.path).expect("virtual path should resolve");
+        let first_page =
+            list_smart_folder_directory_impl(&conn, smart_folder_id, &request, None, Local::now())
+                .expect("smart folder should list");
+        assert_eq!(first_page.path, smart_folder_path(&id));
+        assert_eq!(first_page.total_count, 2);
+        assert_eq!(first_page.entries[0].name, "beta-report.txt");
+        assert_eq!(first_page.next_cursor.as_deref(), Some("1"));
+
+        request.cursor = first_page.next_cursor;
+        let second_page =
+            list_smart_folder_directory_impl(&conn, smart_folder_id, &request, None, Local::now())
+                .expect("smart folder should page");
+        assert_eq!(second_page.entries[0].name, "alpha-report.txt");
+        assert!(second_page.loading_complete);
+
+        request.cursor = None;
+        request.limit = None;
+        request.hidden_files_visible = true;
+        let with_hidden =
+            list_smart_folder_directory_impl(&conn, smart_folder_id, &request, None, Local::now())
+                .expect("smart folder should list hidden files");
+        assert_eq!(with_hidden.total_count, 3);
+
+        conn.execute("DELETE FROM smart_folders WHERE id = ?1", [&id])
+            .expect("smart folder should delete");
+        assert!(matches!(
+            list_smart_folder_directory_impl(&conn, &id, &request, None, Local::now()),
+            Err(error) if error.code == "missing_path"
+        ));
+    }
+
     #[test]
     fn thumbnail_cache_reuses_and_invalidates_metadata() {
         let database_path =
@@ -2821,6 +5086,14 @@ mod tests {
         .expect("thumbnail should exist");
         assert!(!generated.cache_hit);
         assert!(Path::new(&generated.thumbnail_path).is_file());
+        let stats = thumbnail_cache::stats(&conn).expect("cache stats should load");
+        assert_eq!(stats.entry_count, 1);
+        assert_eq!(
+            stats.total_bytes,
+            std::fs::metadata(&generated.thumbnail_path)
+                .expect("thumbnail metadata should read")
+                .len()
+        );
 
         let cached = get_or_generate_thumbnail(
             &conn,
@@ -2848,6 +5121,141 @@ mod tests {
         std::fs::remove_file(database_path).ok();
     }
 
+    #[test]
+    fn thumbnails_and_previews_follow_exif_orientation() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        let temp = tempdir().expect("tempdir should exist");
+        let photo = image::RgbImage::from_fn(40, 20, |x, _| {
+            if x < 20 {
+                image::Rgb([230, 20, 20])
+            } else {
+                image::Rgb([20, 20, 230])
+            }
+        });
+        let mut jpeg = io::Cursor::new(Vec::new());
+        photo
+            .write_to(&mut jpeg, image::ImageFormat::Jpeg)
+            .expect("jpeg should encode");
+        let jpeg = jpeg.into_inner();
+        // A big-endian TIFF block holding only Orientation = 6, which means
+        // the camera was turned a quarter clockwise.
+        let exif: &[u8] =
+            b"Exif\0\0MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0";
+        let mut rotated = jpeg[..2].to_vec();
+        rotated.extend_from_slice(&[0xff, 0xe1]);
+        rotated.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+        rotated.extend_from_slice(exif);
+        rotated.extend_from_slice(&jpeg[2..]);
+        let path = temp.path().join("IMG_0042.jpg");
+        std::fs::write(&path, rotated).expect("photo should write");
+
+        let metadata = std::fs::metadata(&path).expect("photo metadata should read");
+        let thumbnail = get_or_generate_thumbnail(
+            &conn,
+            &temp.path().join("cache"),
+            &path,
+            metadata.len(),
+            None,
+        )
+        .expect("thumbnail generation should succeed")
+        .expect("thumbnail should exist");
+        assert_eq!((thumbnail.width, thumbnail.height), (160, 320));
+        let image = image::open(&thumbnail.thumbnail_path)
+            .expect("thumbnail should decode")
+            .to_rgb8();
+        let top = image.get_pixel(80, 40);
+        let bottom = image.get_pixel(80, 280);
+        assert!(
+            top[0] > 150 && top[2] < 100,
+            "expected red on top, got {top:?}"
+        );
+        assert!(
+            bottom[2] > 150 && bottom[0] < 100,
+            "expected blue below, got {bottom:?}"
+        );
+
+        let preview = build_preview_descriptor(&path, true).expect("photo should describe");
+        assert_eq!(
+            preview.metadata.get("width").map(String::as_str),
+            Some("20")
+        );
+        assert_eq!(
+            preview.metadata.get("height").map(String::as_str),
+            Some("40")
+        );
+        assert_eq!(
+            preview.metadata.get("hasGps").map(String::as_str),
+            Some("false")
+        );
+    }
+
+    #[test]
+    fn thumbnails_cover_gif_bmp_tiff_ico_and_svg() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        let temp = tempdir().expect("tempdir should exist");
+        let cache_dir = temp.path().join("cache");
+        let thumbnail_for = |path: &Path| {
+            assert!(is_supported_thumbnail_source(path));
+            let metadata = std::fs::metadata(path).expect("fixture metadata should read");
+            let descriptor =
+                get_or_generate_thumbnail(&conn, &cache_dir, path, metadata.len(), None)
+                    .expect("thumbnail generation should succeed")
+                    .expect("thumbnail should exist");
+            let image = image::open(&descriptor.thumbnail_path)
+                .expect("thumbnail should decode")
+                .to_rgba8();
+            (descriptor, image)
+        };
+
+        let blue = image::RgbaImage::from_pixel(48, 32, image::Rgba([20, 120, 220, 255]));
+        for (name, format) in [
+            ("fixture.bmp", image::ImageFormat::Bmp),
+            ("fixture.tiff", image::ImageFormat::Tiff),
+            ("fixture.ico", image::ImageFormat::Ico),
+        ] {
+            let path = temp.path().join(name);
+            blue.save_with_format(&path, format)
+                .expect("fixture should save");
+            let (descriptor, image) = thumbnail_for(&path);
+            assert_eq!((descriptor.width, descriptor.height), (320, 213));
+            assert_eq!(image.get_pixel(10, 10), &image::Rgba([20, 120, 220, 255]));
+        }
+
+        let gif_path = temp.path().join("animated.gif");
+        {
+            let file = std::fs::File::create(&gif_path).expect("fixture should create");
+            let mut encoder = image::codecs::gif::GifEncoder::new(file);
+            for color in [[200, 30, 30, 255], [30, 200, 30, 255]] {
+                let frame = image::RgbaImage::from_pixel(16, 16, image::Rgba(color));
+                encoder
+                    .encode_frame(image::Frame::new(frame))
+                    .expect("frame should encode");
+            }
+        }
+        let (_, first_frame) = thumbnail_for(&gif_path);
+        let pixel = first_frame.get_pixel(8, 8);
+        assert!(
+            pixel[0] > 150 && pixel[1] < 80,
+            "expected the red first frame, got {pixel:?}"
+        );
+
+        let svg_path = temp.path().join("badge.svg");
+        std::fs::write(
+            &svg_path,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20"><rect width="40" height="20" fill="red"/></svg>"#,
+        )
+        .expect("fixture should write");
+        let (descriptor, image) = thumbnail_for(&svg_path);
+        assert_eq!((descriptor.width, descriptor.height), (320, 160));
+        assert_eq!(image.get_pixel(160, 80), &image::Rgba([255, 0, 0, 255]));
+
+        let broken_svg = temp.path().join("broken.svg");
+        std::fs::write(&broken_svg, "<svg").expect("fixture should write");
+        let error = get_or_generate_thumbnail(&conn, &cache_dir, &broken_svg, 4, None)
+            .expect_err("broken svg should fail");
+        assert_eq!(error.code, "unavailable");
+    }
+
     #[test]
     fn thumbnail_cleanup_removes_orphaned_metadata() {
         let database_path = std::env::temp_dir().join(format!(
@@ -2881,6 +5289,92 @@ mod tests {
         std::fs::remove_file(database_path).ok();
     }
 
+    #[test]
+    fn preview_descriptors_sniff_contents_and_respect_settings() {
+        let temp = tempdir().expect("tempdir should exist");
+        let photo = temp.path().join("holiday.dat");
+        image::RgbImage::from_pixel(24, 12, image::Rgb([20, 120, 220]))
+            .save_with_format(&photo, image::ImageFormat::Png)
+            .expect("image should save");
+        let source = temp.path().join("main.rs");
+        std::fs::write(&source, "fn main() {}\n").expect("file should write");
+        let binary = temp.path().join("blob.bin");
+        std::fs::write(&binary, [0x7f, b'E', b'L', b'F', 0, 1, 2]).expect("file should write");
+
+        let image = build_preview_descriptor(&photo, true).expect("image should describe");
+        assert_eq!(image.renderer, PreviewRenderer::Image);
+        assert_eq!(
+            image.metadata.get("format").map(String::as_str),
+            Some("PNG")
+        );
+        assert_eq!(image.metadata.get("width").map(String::as_str), Some("24"));
+        assert_eq!(image.metadata.get("height").map(String::as_str), Some("12"));
+
+        let text = build_preview_descriptor(&source, true).expect("source should describe");
+        assert_eq!(text.renderer, PreviewRenderer::Text);
+        assert_eq!(text.kind, "Source Code");
+        assert_eq!(
+            text.metadata.get("language").map(String::as_str),
+            Some("Rust")
+        );
+
+        let other = build_preview_descriptor(&binary, true).expect("binary should describe");
+        assert_eq!(other.renderer, PreviewRenderer::FallbackMetadata);
+
+        let disabled = build_preview_descriptor(&photo, false).expect("image should describe");
+        assert_eq!(disabled.renderer, PreviewRenderer::FallbackMetadata);
+        assert!(!disabled.metadata.contains_key("format"));
+        assert_eq!(disabled.size, image.size);
+
+        let folder = build_preview_descriptor(temp.path(), true).expect("folder should describe");
+        assert_eq!(folder.kind, "Folder");
+        assert_eq!(
+            folder.metadata.get("itemCount").map(String::as_str),
+            Some("3")
+        );
+
+        let missing = build_preview_descriptor(&temp.path().join("gone.txt"), true)
+            .expect_err("missing file should fail");
+        assert_eq!(missing.code, "missing_path");
+        assert!(missing.recoverable);
+    }
+
+    #[test]
+    fn text_previews_truncate_and_highlight_source_files() {
+        let temp = tempdir().expect("tempdir should exist");
+        let log = temp.path().join("server.log");
+        std::fs::write(&log, "first line\nsecond line\nthird line\n").expect("file should write");
+        let source = temp.path().join("app.ts");
+        std::fs::write(&source, "export const answer = 42; // why\n").expect("file should write");
+
+        let head = read_text_preview(&log, 16).expect("log should preview");
+        assert_eq!(head.content, "first line\nsecon");
+        assert_eq!(head.line_count, 2);
+        assert!(head.truncated);
+        assert!(head.spans.is_empty());
+
+        let whole = read_text_preview(&log, 1024).expect("log should preview");
+        assert_eq!(whole.line_count, 3);
+        assert!(!whole.truncated);
+
+        let code = read_text_preview(&source, 1024).expect("source should preview");
+        assert_eq!(code.language.as_deref(), Some("TypeScript"));
+        assert_eq!(
+            code.spans.iter().map(|span| span.token).collect::<Vec<_>>(),
+            vec![
+                SyntaxToken::Keyword,
+                SyntaxToken::Keyword,
+                SyntaxToken::Number,
+                SyntaxToken::Comment
+            ]
+        );
+
+        assert!(matches!(
+            read_text_preview(temp.path(), 1024),
+            Err(error) if error.code == "invalid_input"
+        ));
+    }
+
     #[test]
     fn save_windows_round_trips_session_state() {
         let database_path =
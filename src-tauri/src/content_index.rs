@@ -0,0 +1,354 @@
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::models::{SearchSnippet, SnippetSegment};
+use crate::search_query::SearchQuery;
+
+pub const CONTENT_INDEXING_SETTING: &str = "search.contentIndexing";
+
+/// Files larger than this keep a metadata row but their text is not indexed.
+pub const MAX_CONTENT_FILE_BYTES: u64 = 1024 * 1024;
+/// How many files one [`sync`] call reads at most.
+pub const SYNC_BATCH_SIZE: usize = 64;
+/// A NUL byte this close to the start means the file is not really text.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// Markers FTS5 wraps around matched tokens in snippets. They come from the
+/// private use area, so they never collide with text in a document.
+pub(crate) const SNIPPET_MATCH_START: &str = "\u{e000}";
+pub(crate) const SNIPPET_MATCH_END: &str = "\u{e001}";
+pub(crate) const SNIPPET_ELLIPSIS: &str = "…";
+pub(crate) const SNIPPET_TOKENS: i64 = 12;
+
+/// Kinds whose text is indexed, besides CSV and TSV tables.
+const CONTENT_KINDS: &[&str] = &["Text Document", "Markdown Document", "Source Code"];
+
+pub fn is_enabled(conn: &Connection) -> Result<bool> {
+    let value = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [CONTENT_INDEXING_SETTING],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+    Ok(value.as_deref() == Some("true"))
+}
+
+/// Persists the opt-in. Turning content indexing off drops every stored
+/// document text; turning it on leaves the backfill to [`sync`].
+pub fn set_enabled(conn: &mut Connection, enabled: bool) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO settings (key, value, updated_at)
+         VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+         ON CONFLICT(key) DO UPDATE SET
+            value = excluded.value,
+            updated_at = excluded.updated_at",
+        params![CONTENT_INDEXING_SETTING, enabled.to_string()],
+    )?;
+    if !enabled {
+        tx.execute("DELETE FROM content_index", [])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Indexes the text of up to `limit` files whose content row is missing or
+/// older than their metadata row, and returns how many it visited.
+///
+/// Candidates come from `metadata_index`, so configured roots and exclusions
+/// apply without being checked again here. Files that turn out to be binary
+/// or unreadable get an empty row so they are not retried until they change.
+pub fn sync(conn: &mut Connection, limit: usize) -> Result<usize> {
+    if !is_enabled(conn)? {
+        return Ok(0);
+    }
+
+    let candidates = {
+        let kinds = CONTENT_KINDS
+            .iter()
+            .map(|kind| format!("'{kind}'"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT metadata_index.path, metadata_index.size, metadata_index.modified_at,
+                    metadata_index.extension
+             FROM metadata_index
+             LEFT JOIN content_index ON content_index.path = metadata_index.path
+             WHERE metadata_index.is_dir = 0
+               AND (metadata_index.kind IN ({kinds})
+                    OR lower(metadata_index.extension) IN ('csv', 'tsv'))
+               AND metadata_index.size <= ?1
+               AND (content_index.path IS NULL
+                    OR content_index.size IS NOT metadata_index.size
+                    OR content_index.modified_at IS NOT metadata_index.modified_at)
+             LIMIT ?2"
+        ))?;
+        let rows = stmt.query_map(
+            params![MAX_CONTENT_FILE_BYTES as i64, limit as i64],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<i64>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO content_index (path, size, modified_at, indexed_at, body)
+             VALUES (?1, ?2, ?3, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), ?4)
+             ON CONFLICT(path) DO UPDATE SET
+                size = excluded.size,
+                modified_at = excluded.modified_at,
+                indexed_at = excluded.indexed_at,
+                body = excluded.body",
+        )?;
+        for (path, size, modified_at, extension) in &candidates {
+            let body = match extract_text(Path::new(path), extension.as_deref()) {
+                Ok(body) => body.unwrap_or_default(),
+                Err(_error) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("[frogger] failed to read content of {path}: {_error:?}");
+                    String::new()
+                }
+            };
+            stmt.execute(params![path, size, modified_at, body])?;
+        }
+    }
+    tx.commit()?;
+    Ok(candidates.len())
+}
+
+/// Reads the searchable text of a file. Returns `None` for binary files.
+/// Table cells are separated so snippets read as rows rather than raw CSV.
+fn extract_text(path: &Path, extension: Option<&str>) -> Result<Option<String>> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)?
+        .take(MAX_CONTENT_FILE_BYTES)
+        .read_to_end(&mut bytes)?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return Ok(None);
+    }
+
+    let text = String::from_utf8_lossy(&bytes);
+    let text = match extension.map(str::to_ascii_lowercase).as_deref() {
+        Some("csv") => join_delimited_cells(&text, ','),
+        Some("tsv") => join_delimited_cells(&text, '\t'),
+        _ => text.into_owned(),
+    };
+    Ok(Some(text))
+}
+
+fn join_delimited_cells(text: &str, delimiter: char) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut cell = String::new();
+    let mut row = Vec::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(character) = chars.next() {
+        match character {
+            '"' if quoted && chars.next_if_eq(&'"').is_some() => cell.push('"'),
+            '"' => quoted = !quoted,
+            character if character == delimiter && !quoted => {
+                row.push(std::mem::take(&mut cell));
+            }
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell));
+                push_row(&mut output, &mut row);
+            }
+            '\r' if !quoted => {}
+            character => cell.push(character),
+        }
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        push_row(&mut output, &mut row);
+    }
+    output
+}
+
+fn push_row(output: &mut String, row: &mut Vec<String>) {
+    let cells = row
+        .drain(..)
+        .map(|cell| cell.trim().to_string())
+        .filter(|cell| !cell.is_empty())
+        .collect::<Vec<_>>();
+    if !cells.is_empty() {
+        output.push_str(&cells.join(" | "));
+        output.push('\n');
+    }
+}
+
+/// Builds the FTS5 expression for the free text and phrases of `query`. Every
+/// word must appear and the last one may be a prefix, so results keep up
+/// while the user is still typing.
+pub fn match_expression(query: &SearchQuery) -> Option<String> {
+    let quote = |value: &str| format!("\"{}\"", value.replace('"', "\"\""));
+    let mut terms = query.text.split_whitespace().map(quote).collect::<Vec<_>>();
+    if let Some(last) = terms.last_mut() {
+        last.push('*');
+    }
+    terms.extend(query.phrases.iter().map(|phrase| quote(phrase)));
+    (!terms.is_empty()).then(|| terms.join(" AND "))
+}
+
+/// Splits an FTS5 snippet marked with [`SNIPPET_MATCH_START`] and
+/// [`SNIPPET_MATCH_END`] into plain and highlighted segments.
+pub fn parse_snippet(raw: &str) -> SearchSnippet {
+    let mut segments = Vec::new();
+    let mut push = |text: &str, highlighted: bool| {
+        let text = text.replace(['\n', '\r', '\t'], " ");
+        if !text.is_empty() {
+            segments.push(SnippetSegment { text, highlighted });
+        }
+    };
+
+    let mut rest = raw;
+    while let Some(start) = rest.find(SNIPPET_MATCH_START) {
+        push(&rest[..start], false);
+        rest = &rest[start + SNIPPET_MATCH_START.len()..];
+        let end = rest.find(SNIPPET_MATCH_END).unwrap_or(rest.len());
+        push(&rest[..end], true);
+        rest = rest
+            .get(end + SNIPPET_MATCH_END.len()..)
+            .unwrap_or_default();
+    }
+    push(rest, false);
+
+    SearchSnippet { segments }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalization::SearchNormalization;
+    use crate::persistence::{
+        self,
+        test_support::{insert_metadata_row, TestMetadataRow},
+    };
+    use chrono::Local;
+
+    fn insert_file_row(conn: &Connection, path: &Path, kind: &str, extension: &str) {
+        let size = std::fs::metadata(path).expect("file should exist").len() as i64;
+        let path = path.to_string_lossy();
+        insert_metadata_row(
+            conn,
+            TestMetadataRow {
+                size: Some(size),
+                modified_at: Some("2025-01-01T00:00:00Z"),
+                extension: Some(extension),
+                ..TestMetadataRow::file(&path, kind)
+            },
+        );
+    }
+
+    fn content_hits(conn: &Connection, query: &str) -> Vec<String> {
+        let query = SearchQuery::parse(query, None, Local::now(), SearchNormalization::default())
+            .expect("query should parse");
+        let expression = match_expression(&query).expect("query should have text");
+        let mut stmt = conn
+            .prepare(
+                "SELECT content_index.path FROM content_search
+                 JOIN content_index ON content_index.rowid = content_search.rowid
+                 WHERE content_search MATCH ?1
+                 ORDER BY content_index.path",
+            )
+            .expect("content query should prepare");
+        stmt.query_map([expression], |row| row.get::<_, String>(0))
+            .expect("content query should run")
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .expect("content rows should read")
+    }
+
+    #[test]
+    fn sync_indexes_text_files_only_when_enabled() {
+        let mut conn = persistence::open_memory_database().expect("database should migrate");
+        let temp = tempfile::tempdir().expect("tempdir should exist");
+        let notes = temp.path().join("notes.md");
+        let table = temp.path().join("budget.csv");
+        let binary = temp.path().join("blob.txt");
+        let large = temp.path().join("large.txt");
+        std::fs::write(&notes, "Meeting about the Quarterly roadmap").expect("file should write");
+        std::fs::write(&table, "name,amount\n\"Widgets, large\",42\n").expect("file should write");
+        std::fs::write(&binary, b"roadmap\0\x01\x02").expect("file should write");
+        std::fs::write(
+            &large,
+            "roadmap ".repeat(MAX_CONTENT_FILE_BYTES as usize / 4),
+        )
+        .expect("file should write");
+        insert_file_row(&conn, &notes, "Markdown Document", "md");
+        insert_file_row(&conn, &table, "Spreadsheet", "csv");
+        insert_file_row(&conn, &binary, "Text Document", "txt");
+        insert_file_row(&conn, &large, "Text Document", "txt");
+
+        assert_eq!(
+            sync(&mut conn, SYNC_BATCH_SIZE).expect("sync should run"),
+            0
+        );
+
+        set_enabled(&mut conn, true).expect("setting should save");
+        assert_eq!(
+            sync(&mut conn, SYNC_BATCH_SIZE).expect("sync should run"),
+            3
+        );
+        assert_eq!(
+            sync(&mut conn, SYNC_BATCH_SIZE).expect("sync should run"),
+            0
+        );
+        assert_eq!(
+            content_hits(&conn, "roadmap"),
+            vec![notes.to_string_lossy().into_owned()]
+        );
+        assert_eq!(
+            content_hits(&conn, "widg"),
+            vec![table.to_string_lossy().into_owned()]
+        );
+
+        conn.execute(
+            "DELETE FROM metadata_index WHERE path = ?1",
+            [notes.to_string_lossy()],
+        )
+        .expect("metadata row should delete");
+        assert!(content_hits(&conn, "roadmap").is_empty());
+
+        set_enabled(&mut conn, false).expect("setting should save");
+        assert!(content_hits(&conn, "widgets").is_empty());
+    }
+
+    #[test]
+    fn delimited_cells_and_snippets_read_cleanly() {
+        assert_eq!(
+            join_delimited_cells("a,\"b, \"\"c\"\"\"\r\n\n1,2", ','),
+            "a | b, \"c\"\n1 | 2\n"
+        );
+
+        let snippet = parse_snippet("…the \u{e000}quarterly\u{e001}\nroadmap…");
+        assert_eq!(
+            snippet.segments,
+            vec![
+                SnippetSegment {
+                    text: "…the ".to_string(),
+                    highlighted: false
+                },
+                SnippetSegment {
+                    text: "quarterly".to_string(),
+                    highlighted: true
+                },
+                SnippetSegment {
+                    text: " roadmap…".to_string(),
+                    highlighted: false
+                },
+            ]
+        );
+    }
+}
@@ -39,6 +39,18 @@ impl CommandError {
     pub fn permission_denied(message: impl Into<String>, details: Option<String>) -> Self {
         Self::new("permission_denied", message, true, details)
     }
+
+    pub fn already_exists(message: impl Into<String>, details: Option<String>) -> Self {
+        Self::new("already_exists", message, true, details)
+    }
+
+    pub fn invalid_input(message: impl Into<String>, details: Option<String>) -> Self {
+        Self::new("invalid_input", message, true, details)
+    }
+
+    pub fn conflict(message: impl Into<String>, details: Option<String>) -> Self {
+        Self::new("conflict", message, true, details)
+    }
 }
 
 impl From<anyhow::Error> for CommandError {
@@ -0,0 +1,213 @@
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use uuid::Uuid;
+
+/// Days after which an open counts half as much as a fresh one.
+const RECENT_DECAY_DAYS: f64 = 14.0;
+/// Decayed open count at which the recent boost reaches one half. The boost
+/// approaches 1.0 but never gets there, so habits cannot outrank a better
+/// textual match tier.
+const RECENT_SATURATION: f64 = 3.0;
+/// Days after which an edit counts half as much as one made today.
+const MODIFIED_DECAY_DAYS: f64 = 7.0;
+/// Largest boost a fresh edit earns. Kept below the recent boost because
+/// opening something says more about intent than touching it.
+const MODIFIED_BOOST_WEIGHT: f64 = 0.25;
+
+/// SQL for the recent boost of the row at `path`, as of `now`.
+///
+/// Opens and picks from search results each count once, decayed
+/// hyperbolically by the age of the latest one. Paths that were never opened
+/// score 0.
+pub(crate) fn recent_boost_sql(path: &str, now: &str) -> String {
+    format!(
+        "coalesce((
+            SELECT score / (score + {RECENT_SATURATION:.1}) FROM (
+                SELECT (open_count + search_open_count)
+                    / (1.0 + max(julianday({now}) - julianday(opened_at), 0.0) / {RECENT_DECAY_DAYS:.1})
+                    AS score
+                FROM recents
+                WHERE recents.path = {path}
+            )
+        ), 0.0)"
+    )
+}
+
+/// SQL for the modified boost of a row last modified at `modified_at`, as of
+/// `now`. Rows without a modification time score 0.
+pub(crate) fn modified_boost_sql(modified_at: &str, now: &str) -> String {
+    format!(
+        "coalesce({MODIFIED_BOOST_WEIGHT:.2}
+            / (1.0 + max(julianday({now}) - julianday({modified_at}), 0.0) / {MODIFIED_DECAY_DAYS:.1}),
+        0.0)"
+    )
+}
+
+/// Records that `path` was opened and refreshes its recent boost in the
+/// metadata index, so the next search already ranks it higher.
+pub fn record_open(conn: &Connection, path: &str, now: DateTime<Utc>) -> Result<()> {
+    record(conn, path, now, 1, 0)
+}
+
+/// Records that `path` was picked from search results. The open itself is
+/// recorded separately, so a pick adds weight without counting as a visit.
+pub fn record_search_pick(conn: &Connection, path: &str, now: DateTime<Utc>) -> Result<()> {
+    record(conn, path, now, 0, 1)
+}
+
+fn record(
+    conn: &Connection,
+    path: &str,
+    now: DateTime<Utc>,
+    opens: i64,
+    search_opens: i64,
+) -> Result<()> {
+    let kind = if Path::new(path).is_dir() {
+        "folder"
+    } else {
+        "file"
+    };
+    let opened_at = now.to_rfc3339();
+
+    conn.execute(
+        "INSERT INTO recents (id, path, kind, opened_at, open_count, search_open_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(path) DO UPDATE SET
+            kind = excluded.kind,
+            opened_at = excluded.opened_at,
+            open_count = recents.open_count + excluded.open_count,
+            search_open_count = recents.search_open_count + excluded.search_open_count",
+        params![
+            format!("recent-{}", Uuid::new_v4()),
+            path,
+            kind,
+            opened_at,
+            opens,
+            search_opens
+        ],
+    )?;
+    conn.execute(
+        &format!(
+            "UPDATE metadata_index SET recent_boost = {} WHERE path = ?1",
+            recent_boost_sql("metadata_index.path", "?2")
+        ),
+        params![path, opened_at],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{
+        self,
+        test_support::{insert_metadata_row, TestMetadataRow},
+    };
+    use chrono::TimeZone;
+
+    fn recent_boost(conn: &Connection, path: &str) -> f64 {
+        conn.query_row(
+            "SELECT recent_boost FROM metadata_index WHERE path = ?1",
+            [path],
+            |row| row.get(0),
+        )
+        .expect("boost should be readable")
+    }
+
+    #[test]
+    fn opens_raise_recent_boost_and_search_picks_count_more() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        for path in [
+            "/tmp/browsed.txt",
+            "/tmp/searched.txt",
+            "/tmp/untouched.txt",
+        ] {
+            insert_metadata_row(&conn, TestMetadataRow::file(path, "Document"));
+        }
+
+        record_open(&conn, "/tmp/browsed.txt", now).expect("open should record");
+        record_open(&conn, "/tmp/searched.txt", now).expect("open should record");
+        record_search_pick(&conn, "/tmp/searched.txt", now).expect("pick should record");
+
+        let browsed = recent_boost(&conn, "/tmp/browsed.txt");
+        let searched = recent_boost(&conn, "/tmp/searched.txt");
+        assert!((browsed - 0.25).abs() < 1e-9);
+        assert!(searched > browsed);
+        assert_eq!(recent_boost(&conn, "/tmp/untouched.txt"), 0.0);
+
+        record_open(&conn, "/tmp/browsed.txt", now).expect("open should record");
+        assert!(recent_boost(&conn, "/tmp/browsed.txt") > browsed);
+    }
+
+    #[test]
+    fn boosts_decay_with_age() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        conn.execute(
+            "INSERT INTO recents (id, path, kind, opened_at, open_count)
+             VALUES ('recent-1', '/tmp/report.txt', 'file', '2025-01-01T00:00:00Z', 4)",
+            [],
+        )
+        .expect("recent should insert");
+
+        let boost_at = |now: &str| -> (f64, f64) {
+            conn.query_row(
+                &format!(
+                    "SELECT {}, {}",
+                    recent_boost_sql("'/tmp/report.txt'", "?1"),
+                    modified_boost_sql("'2025-01-01T00:00:00.123456789+00:00'", "?1")
+                ),
+                [now],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("boosts should compute")
+        };
+
+        let (fresh_recent, fresh_modified) = boost_at("2025-01-01T00:00:00Z");
+        let (old_recent, old_modified) = boost_at("2025-03-01T00:00:00Z");
+        assert!((fresh_recent - 4.0 / 7.0).abs() < 1e-9);
+        assert!((fresh_modified - MODIFIED_BOOST_WEIGHT).abs() < 1e-6);
+        assert!(old_recent < fresh_recent / 2.0);
+        assert!(old_modified < fresh_modified / 5.0);
+    }
+
+    #[test]
+    fn indexing_carries_boosts_for_recent_and_fresh_files() {
+        let mut conn = persistence::open_memory_database().expect("database should migrate");
+        let temp = tempfile::tempdir().expect("tempdir should exist");
+        let opened = temp.path().join("opened.txt");
+        let other = temp.path().join("other.txt");
+        std::fs::write(&opened, "opened").expect("file should write");
+        std::fs::write(&other, "other").expect("file should write");
+        record_open(&conn, opened.to_string_lossy().as_ref(), Utc::now())
+            .expect("open should record");
+
+        let rules = crate::index_config::IndexRules::load(&conn).expect("rules should load");
+        crate::indexing::snapshot_folder(
+            &mut conn,
+            temp.path(),
+            rules,
+            crate::normalization::SearchNormalization::default(),
+            100,
+            std::time::Instant::now() + std::time::Duration::from_secs(5),
+        )
+        .expect("folder should index");
+
+        let boosts = |path: &Path| -> (f64, f64) {
+            conn.query_row(
+                "SELECT recent_boost, modified_boost FROM metadata_index WHERE path = ?1",
+                [path.to_string_lossy()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("indexed row should exist")
+        };
+        let (opened_recent, opened_modified) = boosts(&opened);
+        let (other_recent, other_modified) = boosts(&other);
+        assert!(opened_recent > 0.2);
+        assert_eq!(other_recent, 0.0);
+        assert!(opened_modified > 0.2 && other_modified > 0.2);
+    }
+}
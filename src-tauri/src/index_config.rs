@@ -0,0 +1,405 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use rusqlite::{params, Connection};
+use uuid::Uuid;
+
+use crate::errors::CommandError;
+use crate::indexing;
+use crate::models::{IndexConfiguration, IndexExclusion, IndexExclusionKind, IndexRoot};
+
+/// Compiled exclusion rules used while walking and maintaining the index.
+///
+/// Glob rules without a `/` match an entry's name, other globs match its full
+/// path, and a trailing `/` limits a rule to folders. Globs ignore case, like
+/// the fixed list they replace. Path rules exclude exactly that path and
+/// everything below it.
+#[derive(Debug, Clone)]
+pub struct IndexRules {
+    name_globs: GlobSet,
+    name_globs_folders_only: Vec<bool>,
+    path_globs: GlobSet,
+    path_globs_folders_only: Vec<bool>,
+    paths: HashSet<PathBuf>,
+}
+
+impl IndexRules {
+    /// Loads the enabled exclusion rules. Rules that no longer compile are
+    /// skipped so one bad row cannot stop indexing.
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let exclusions = load_exclusions(conn)?;
+        Ok(Self::from_exclusions(&exclusions))
+    }
+
+    fn from_exclusions(exclusions: &[IndexExclusion]) -> Self {
+        let mut name_globs = GlobSetBuilder::new();
+        let mut name_globs_folders_only = Vec::new();
+        let mut path_globs = GlobSetBuilder::new();
+        let mut path_globs_folders_only = Vec::new();
+        let mut paths = HashSet::new();
+
+        for exclusion in exclusions {
+            match exclusion.kind {
+                IndexExclusionKind::Path => {
+                    paths.insert(PathBuf::from(&exclusion.pattern));
+                }
+                IndexExclusionKind::Glob => {
+                    let Ok(rule) = GlobRule::parse(&exclusion.pattern) else {
+                        continue;
+                    };
+                    if rule.matches_name {
+                        name_globs.add(rule.glob);
+                        name_globs_folders_only.push(rule.folders_only);
+                    } else {
+                        path_globs.add(rule.glob);
+                        path_globs_folders_only.push(rule.folders_only);
+                    }
+                }
+            }
+        }
+
+        Self {
+            name_globs: name_globs.build().unwrap_or_else(|_| GlobSet::empty()),
+            name_globs_folders_only,
+            path_globs: path_globs.build().unwrap_or_else(|_| GlobSet::empty()),
+            path_globs_folders_only,
+            paths,
+        }
+    }
+
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        if self.paths.contains(path) {
+            return true;
+        }
+
+        let applies = |folders_only: &[bool], index: usize| {
+            is_dir || !folders_only.get(index).copied().unwrap_or(false)
+        };
+        if let Some(name) = path.file_name() {
+            if self
+                .name_globs
+                .matches(name)
+                .into_iter()
+                .any(|index| applies(&self.name_globs_folders_only, index))
+            {
+                return true;
+            }
+        }
+        self.path_globs
+            .matches(path)
+            .into_iter()
+            .any(|index| applies(&self.path_globs_folders_only, index))
+    }
+}
+
+struct GlobRule {
+    glob: globset::Glob,
+    folders_only: bool,
+    matches_name: bool,
+}
+
+impl GlobRule {
+    fn parse(pattern: &str) -> Result<Self, globset::Error> {
+        let folders_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()?;
+        Ok(Self {
+            glob,
+            folders_only,
+            matches_name: !pattern.contains('/'),
+        })
+    }
+}
+
+/// Seeds the roots and home-relative exclusions that depend on the current
+/// user. Seeding never re-enables defaults the user removed.
+pub fn ensure_default_configuration(conn: &Connection, home_dir: &Path) -> Result<()> {
+    for root in indexing::default_index_roots(home_dir) {
+        conn.execute(
+            "INSERT OR IGNORE INTO index_roots (path, is_default) VALUES (?1, 1)",
+            [root.to_string_lossy()],
+        )?;
+    }
+
+    for (id, name) in [
+        ("default-home-library", "Library"),
+        ("default-home-applications", "Applications"),
+        ("default-home-trash", ".Trash"),
+    ] {
+        conn.execute(
+            "INSERT OR IGNORE INTO index_exclusions (id, kind, pattern, is_default)
+             VALUES (?1, 'path', ?2, 1)",
+            params![id, home_dir.join(name).to_string_lossy()],
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn load_configuration(conn: &Connection) -> Result<IndexConfiguration> {
+    let mut statement = conn.prepare(
+        "SELECT path, is_default FROM index_roots WHERE enabled = 1 ORDER BY is_default DESC, path",
+    )?;
+    let roots = statement
+        .query_map([], |row| {
+            Ok(IndexRoot {
+                path: row.get(0)?,
+                is_default: row.get::<_, i64>(1)? == 1,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(IndexConfiguration {
+        roots,
+        exclusions: load_exclusions(conn)?,
+    })
+}
+
+/// Enabled roots in the order they should be walked.
+pub fn load_index_roots(conn: &Connection) -> Result<Vec<PathBuf>> {
+    Ok(load_configuration(conn)?
+        .roots
+        .into_iter()
+        .map(|root| PathBuf::from(root.path))
+        .collect())
+}
+
+pub fn add_index_root(conn: &Connection, path: &str) -> Result<IndexConfiguration, CommandError> {
+    let root = Path::new(path);
+    if !root.is_absolute() {
+        return Err(CommandError::invalid_input(
+            "Index locations must be absolute paths.",
+            Some(path.to_string()),
+        ));
+    }
+    if !root.is_dir() {
+        return Err(CommandError::missing_path(
+            "Only existing folders can be indexed.",
+            Some(path.to_string()),
+        ));
+    }
+
+    conn.execute(
+        "INSERT INTO index_roots (path) VALUES (?1)
+         ON CONFLICT(path) DO UPDATE SET enabled = 1",
+        [path],
+    )
+    .map_err(anyhow::Error::from)
+    .map_err(CommandError::from)?;
+    indexing::configuration_changed(conn, false).map_err(CommandError::from)?;
+    load_configuration(conn).map_err(CommandError::from)
+}
+
+/// Removes a root. Default roots are only disabled so seeding does not bring
+/// them back.
+pub fn remove_index_root(
+    conn: &Connection,
+    path: &str,
+) -> Result<IndexConfiguration, CommandError> {
+    conn.execute(
+        "UPDATE index_roots SET enabled = 0 WHERE path = ?1 AND is_default = 1",
+        [path],
+    )
+    .and_then(|_| {
+        conn.execute(
+            "DELETE FROM index_roots WHERE path = ?1 AND is_default = 0",
+            [path],
+        )
+    })
+    .map_err(anyhow::Error::from)
+    .map_err(CommandError::from)?;
+    indexing::configuration_changed(conn, false).map_err(CommandError::from)?;
+    load_configuration(conn).map_err(CommandError::from)
+}
+
+pub fn add_index_exclusion(
+    conn: &Connection,
+    kind: IndexExclusionKind,
+    pattern: &str,
+) -> Result<IndexConfiguration, CommandError> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err(CommandError::invalid_input(
+            "Exclusion patterns cannot be empty.",
+            None,
+        ));
+    }
+    match kind {
+        IndexExclusionKind::Glob => {
+            GlobRule::parse(pattern).map_err(|error| {
+                CommandError::invalid_input(
+                    "That exclusion pattern is not a valid glob.",
+                    Some(error.to_string()),
+                )
+            })?;
+        }
+        IndexExclusionKind::Path => {
+            if !Path::new(pattern).is_absolute() {
+                return Err(CommandError::invalid_input(
+                    "Excluded paths must be absolute.",
+                    Some(pattern.to_string()),
+                ));
+            }
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO index_exclusions (id, kind, pattern) VALUES (?1, ?2, ?3)
+         ON CONFLICT(kind, pattern) DO UPDATE SET enabled = 1",
+        params![
+            format!("exclusion-{}", Uuid::new_v4()),
+            exclusion_kind_to_db(&kind),
+            pattern
+        ],
+    )
+    .map_err(anyhow::Error::from)
+    .map_err(CommandError::from)?;
+    indexing::configuration_changed(conn, false).map_err(CommandError::from)?;
+    load_configuration(conn).map_err(CommandError::from)
+}
+
+/// Removes an exclusion. Default exclusions are only disabled so seeding does
+/// not bring them back.
+pub fn remove_index_exclusion(
+    conn: &Connection,
+    id: &str,
+) -> Result<IndexConfiguration, CommandError> {
+    conn.execute(
+        "UPDATE index_exclusions SET enabled = 0 WHERE id = ?1 AND is_default = 1",
+        [id],
+    )
+    .and_then(|_| {
+        conn.execute(
+            "DELETE FROM index_exclusions WHERE id = ?1 AND is_default = 0",
+            [id],
+        )
+    })
+    .map_err(anyhow::Error::from)
+    .map_err(CommandError::from)?;
+    indexing::configuration_changed(conn, true).map_err(CommandError::from)?;
+    load_configuration(conn).map_err(CommandError::from)
+}
+
+fn load_exclusions(conn: &Connection) -> Result<Vec<IndexExclusion>> {
+    let mut statement = conn.prepare(
+        "SELECT id, kind, pattern, is_default FROM index_exclusions
+         WHERE enabled = 1
+         ORDER BY is_default DESC, kind, pattern",
+    )?;
+    let exclusions = statement
+        .query_map([], |row| {
+            Ok(IndexExclusion {
+                id: row.get(0)?,
+                kind: exclusion_kind_from_db(&row.get::<_, String>(1)?),
+                pattern: row.get(2)?,
+                is_default: row.get::<_, i64>(3)? == 1,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(exclusions)
+}
+
+fn exclusion_kind_to_db(kind: &IndexExclusionKind) -> &'static str {
+    match kind {
+        IndexExclusionKind::Glob => "glob",
+        IndexExclusionKind::Path => "path",
+    }
+}
+
+fn exclusion_kind_from_db(value: &str) -> IndexExclusionKind {
+    match value {
+        "path" => IndexExclusionKind::Path,
+        _ => IndexExclusionKind::Glob,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence;
+    use tempfile::tempdir;
+
+    fn test_database(temp: &Path) -> Connection {
+        persistence::open_database(
+            &temp.join(format!("frogger-index-config-{}.sqlite3", Uuid::new_v4())),
+        )
+        .expect("database should open")
+    }
+
+    #[test]
+    fn default_rules_exclude_noisy_folders_but_not_files() {
+        let temp = tempdir().expect("tempdir should exist");
+        let home = temp.path().join("home");
+        std::fs::create_dir_all(&home).expect("home should exist");
+        let conn = test_database(temp.path());
+        ensure_default_configuration(&conn, &home).expect("defaults should seed");
+        let rules = IndexRules::load(&conn).expect("rules should load");
+
+        assert!(rules.is_excluded(&home.join("node_modules"), true));
+        assert!(rules.is_excluded(&home.join("src/Cache"), true));
+        assert!(rules.is_excluded(&home.join(".git"), true));
+        assert!(rules.is_excluded(&home.join("Library"), true));
+        assert!(!rules.is_excluded(&home.join("build"), false));
+        assert!(!rules.is_excluded(&home.join("Documents"), true));
+        assert_eq!(
+            load_index_roots(&conn).expect("roots should load"),
+            vec![home.clone()]
+        );
+    }
+
+    #[test]
+    fn user_rules_add_and_remove_roots_and_exclusions() {
+        let temp = tempdir().expect("tempdir should exist");
+        let home = temp.path().join("home");
+        let drive = temp.path().join("drive");
+        std::fs::create_dir_all(&home).expect("home should exist");
+        std::fs::create_dir_all(&drive).expect("drive should exist");
+        let conn = test_database(temp.path());
+        ensure_default_configuration(&conn, &home).expect("defaults should seed");
+
+        let configuration =
+            add_index_root(&conn, &drive.to_string_lossy()).expect("drive should be added");
+        assert_eq!(configuration.roots.len(), 2);
+        remove_index_root(&conn, &home.to_string_lossy()).expect("home should be removed");
+        ensure_default_configuration(&conn, &home).expect("defaults should seed again");
+        assert_eq!(
+            load_index_roots(&conn).expect("roots should load"),
+            vec![drive.clone()]
+        );
+
+        let missing = add_index_root(&conn, &temp.path().join("missing").to_string_lossy())
+            .expect_err("missing root should be refused");
+        assert_eq!(missing.code, "missing_path");
+        let invalid = add_index_exclusion(&conn, IndexExclusionKind::Glob, "[")
+            .expect_err("broken glob should be refused");
+        assert_eq!(invalid.code, "invalid_input");
+
+        let configuration = add_index_exclusion(&conn, IndexExclusionKind::Glob, "*.iso")
+            .expect("glob should be added");
+        let build = configuration
+            .exclusions
+            .iter()
+            .find(|exclusion| exclusion.pattern == "build/")
+            .expect("build should be excluded by default")
+            .id
+            .clone();
+        remove_index_exclusion(&conn, &build).expect("build exclusion should be removed");
+        add_index_exclusion(
+            &conn,
+            IndexExclusionKind::Path,
+            &drive.join("private").to_string_lossy(),
+        )
+        .expect("path should be added");
+
+        let rules = IndexRules::load(&conn).expect("rules should load");
+        assert!(!rules.is_excluded(&drive.join("build"), true));
+        assert!(rules.is_excluded(&drive.join("images/backup.ISO"), false));
+        assert!(rules.is_excluded(&drive.join("private"), true));
+        assert!(!rules.is_excluded(&drive.join("private-notes"), true));
+    }
+}
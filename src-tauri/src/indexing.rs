@@ -1,24 +1,225 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::{Mutex, OnceLock};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TrySendError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, SecondsFormat, Utc};
 use ignore::WalkBuilder;
-use rusqlite::{params, Connection};
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tauri::{AppHandle, Emitter};
 
+use crate::content_index;
+use crate::errors::CommandError;
+use crate::frecency;
+use crate::index_config::{self, IndexRules};
 use crate::models::{EventNames, IndexingState, IndexingStatus};
+use crate::normalization::{self, SearchNormalization};
 use crate::persistence;
+use crate::photo_metadata;
 
 const METADATA_INDEX_ID: &str = "metadata";
 const DEFAULT_BATCH_SIZE: usize = 1_000;
 const PROGRESS_INTERVAL: Duration = Duration::from_millis(750);
+const CHANGE_DEBOUNCE: Duration = Duration::from_millis(500);
+/// Upper bound on how long a steady stream of changes can hold back a batch.
+const MAX_CHANGE_DELAY: Duration = Duration::from_secs(5);
+/// Pause before retrying a batch that failed, such as while another
+/// connection holds the database. It doubles with every failure in a row, up
+/// to [`MAX_CHANGE_RETRY_DELAY`], and maintenance gives up after
+/// [`MAX_CHANGE_RETRIES`] failures.
+const CHANGE_RETRY_DELAY: Duration = Duration::from_secs(2);
+const MAX_CHANGE_RETRY_DELAY: Duration = Duration::from_secs(60);
+const MAX_CHANGE_RETRIES: u32 = 6;
+/// How many watcher events can wait for maintenance. Past that, the roots the
+/// dropped events fell under are walked again instead.
+const WATCH_EVENT_CAPACITY: usize = 16_384;
+/// Whether the platform watcher needs one watch per folder (inotify, kqueue)
+/// rather than one recursive watch per root (FSEvents, ReadDirectoryChangesW).
+const WATCH_PER_FOLDER: bool = !cfg!(any(target_os = "macos", target_os = "windows"));
+
+const CONTROL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const ENTRIES_PER_SECOND_SETTING: &str = "indexing.entriesPerSecond";
 
 static SCHEDULED_DATABASES: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+static INDEX_CONTROL: OnceLock<IndexControl> = OnceLock::new();
+
+/// Lets commands pause, resume, cancel and throttle the indexer thread.
+///
+/// The thread checks the control between walk entries and between batches of
+/// watcher changes, and reports pauses through the regular indexing state.
+#[derive(Debug, Default)]
+pub struct IndexControl {
+    state: Mutex<IndexControlState>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct IndexControlState {
+    active: bool,
+    paused: bool,
+    cancelled: bool,
+    /// Roots or exclusions changed, so the running indexer should walk and
+    /// watch the new configuration.
+    reconfigured: bool,
+    /// Resume was asked for while a cancelled run was still winding down, so
+    /// the indexer thread starts over instead of exiting.
+    restart_requested: bool,
+    entries_per_second: Option<u32>,
+}
+
+impl IndexControl {
+    pub fn pause(&self) -> Result<(), CommandError> {
+        self.update(|state| state.paused = true)
+    }
+
+    /// Unpauses the indexer. Resuming a cancelled run that has not stopped
+    /// yet queues a new run for when it has.
+    pub fn resume(&self) -> Result<(), CommandError> {
+        self.update(|state| {
+            if state.cancelled {
+                state.restart_requested = true;
+            } else {
+                state.paused = false;
+            }
+        })
+    }
+
+    pub fn cancel(&self) -> Result<(), CommandError> {
+        self.update(|state| {
+            state.cancelled = true;
+            state.restart_requested = false;
+        })
+    }
+
+    /// Asks a running indexer to pick up changed roots and exclusions once its
+    /// current walk is done. Without a running indexer the next start reads
+    /// the configuration anyway.
+    pub fn reconfigure(&self) {
+        self.lock().reconfigured = true;
+        self.changed.notify_all();
+    }
+
+    /// Limits how many entries the indexer processes per second. `None` or
+    /// zero removes the limit.
+    pub fn set_entries_per_second(&self, entries_per_second: Option<u32>) {
+        self.lock().entries_per_second = entries_per_second.filter(|limit| *limit > 0);
+    }
+
+    fn begin(&self) {
+        let mut state = self.lock();
+        state.active = true;
+        state.paused = false;
+        state.cancelled = false;
+        state.reconfigured = false;
+        state.restart_requested = false;
+    }
+
+    /// Clears and returns a pending reconfiguration, unless the indexer was
+    /// cancelled, which takes precedence.
+    fn take_reconfigured(&self) -> bool {
+        let mut state = self.lock();
+        let reconfigured = state.reconfigured && !state.cancelled;
+        state.reconfigured = false;
+        reconfigured
+    }
+
+    /// Ends the run. Returns true instead when a restart was queued, leaving
+    /// the control active and reset for the next run.
+    fn finish(&self) -> bool {
+        let mut state = self.lock();
+        state.paused = false;
+        if state.restart_requested {
+            state.restart_requested = false;
+            state.cancelled = false;
+            return true;
+        }
+        state.active = false;
+        false
+    }
+
+    fn snapshot(&self) -> IndexControlState {
+        *self.lock()
+    }
+
+    /// Blocks while the indexer is paused. Returns false once it is cancelled.
+    fn wait_while_paused(&self) -> bool {
+        let mut state = self.lock();
+        while state.paused && !state.cancelled {
+            state = self
+                .changed
+                .wait(state)
+                .expect("index control should not be poisoned");
+        }
+        !state.cancelled
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut IndexControlState)) -> Result<(), CommandError> {
+        let mut state = self.lock();
+        if !state.active {
+            return Err(CommandError::unavailable(
+                "The metadata index is not running.",
+                None,
+            ));
+        }
+        apply(&mut state);
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IndexControlState> {
+        self.state
+            .lock()
+            .expect("index control should not be poisoned")
+    }
+}
+
+/// Spaces out walk entries so the indexer stays under its entries-per-second
+/// limit.
+struct Throttle {
+    started: Instant,
+    processed: u64,
+    limit: Option<u32>,
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            processed: 0,
+            limit: None,
+        }
+    }
+
+    fn pace(&mut self, limit: Option<u32>) {
+        let Some(limit) = limit else {
+            self.limit = None;
+            return;
+        };
+        if self.limit != Some(limit) {
+            *self = Self {
+                limit: Some(limit),
+                ..Self::new()
+            };
+        }
+
+        self.processed += 1;
+        let due = Duration::from_secs_f64(self.processed as f64 / f64::from(limit));
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            thread::sleep(wait);
+        }
+    }
+}
+
+pub fn shared_index_control() -> &'static IndexControl {
+    INDEX_CONTROL.get_or_init(IndexControl::default)
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IndexingSummary {
@@ -28,6 +229,7 @@ pub struct IndexingSummary {
     pub metadata_errors: u64,
     pub pruned_item_count: u64,
     pub elapsed_ms: u128,
+    pub cancelled: bool,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -39,6 +241,34 @@ struct IndexingCounters {
     pruned_item_count: u64,
 }
 
+/// Where an interrupted initial build picks up again. A folder is recorded
+/// once the walk has left it, and replaces the completed folders inside it so
+/// the list stays short.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResumePoint {
+    run_started_at: String,
+    current_root: Option<String>,
+    completed_dirs: BTreeSet<String>,
+}
+
+impl ResumePoint {
+    fn complete_dir(&mut self, dir: &Path) {
+        let dir = dir.to_string_lossy().into_owned();
+        let prefix = child_prefix(&dir);
+        let nested = self
+            .completed_dirs
+            .range(prefix.clone()..)
+            .take_while(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in nested {
+            self.completed_dirs.remove(&path);
+        }
+        self.completed_dirs.insert(dir);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct MetadataIndexRow {
     path: String,
@@ -91,18 +321,25 @@ fn push_existing_dir(roots: &mut Vec<PathBuf>, path: PathBuf) {
 /// Starts one metadata indexing/reconciliation run for this app process.
 ///
 /// Multiple webviews may call bootstrap; this guard prevents duplicate
-/// filesystem crawls competing with each other. Failed runs are removed from the
-/// guard so a later bootstrap can retry.
+/// filesystem crawls competing with each other. Runs that fail, are cancelled
+/// or stop are removed from the guard so a later bootstrap or resume can start
+/// again. An initial build that was interrupted by a crash, quit or cancel
+/// continues from its checkpoint instead of starting over.
 pub fn ensure_metadata_index_started(
     app: &AppHandle,
     database_path: PathBuf,
     home_dir: PathBuf,
 ) -> Result<bool> {
-    let roots = default_index_roots(&home_dir);
+    let roots = {
+        let conn = persistence::open_database(&database_path)?;
+        index_config::ensure_default_configuration(&conn, &home_dir)?;
+        index_config::load_index_roots(&conn)?
+    };
     if roots.is_empty() {
         return Ok(false);
     }
 
+    let control = shared_index_control();
     let scheduled = SCHEDULED_DATABASES.get_or_init(|| Mutex::new(HashSet::new()));
     {
         let mut scheduled = scheduled
@@ -118,39 +355,18 @@ pub fn ensure_metadata_index_started(
     let mut conn = persistence::open_database(&database_path)?;
     let initial_state = mark_run_started(&mut conn, &roots)?;
     emit_indexing_state(app, &initial_state);
+    control.set_entries_per_second(load_entries_per_second(&conn)?);
+    control.begin();
     drop(conn);
 
     let app_handle = app.clone();
     let thread_database_path = database_path.clone();
-    let thread_roots = roots.clone();
     let spawn_result = thread::Builder::new()
         .name("frogger-metadata-index".to_string())
-        .spawn(move || {
-            let result = run_metadata_index(&thread_database_path, thread_roots, |state| {
-                emit_indexing_state(&app_handle, &state);
-            });
-
-            if let Err(error) = result {
-                if let Ok(mut conn) = persistence::open_database(&thread_database_path) {
-                    let had_initial_index = has_initial_index(&conn).unwrap_or(false);
-                    let state = mark_run_failed(&mut conn, had_initial_index, &error.to_string())
-                        .unwrap_or_else(|_| IndexingState {
-                            status: IndexingStatus::Failed,
-                            has_initial_index: had_initial_index,
-                            indexed_item_count: 0,
-                            message: Some(error.to_string()),
-                        });
-                    emit_indexing_state(&app_handle, &state);
-                }
-
-                let scheduled = SCHEDULED_DATABASES.get_or_init(|| Mutex::new(HashSet::new()));
-                if let Ok(mut scheduled) = scheduled.lock() {
-                    scheduled.remove(&thread_database_path);
-                }
-            }
-        });
+        .spawn(move || index_thread(&app_handle, &thread_database_path, roots, control));
 
     if let Err(error) = spawn_result {
+        control.finish();
         let scheduled = SCHEDULED_DATABASES.get_or_init(|| Mutex::new(HashSet::new()));
         if let Ok(mut scheduled) = scheduled.lock() {
             scheduled.remove(&database_path);
@@ -165,9 +381,142 @@ pub fn ensure_metadata_index_started(
     Ok(true)
 }
 
+/// Body of the indexer thread: a full run followed by watcher maintenance.
+/// A configuration change ends maintenance and starts over with the new roots
+/// and exclusions.
+fn index_thread(
+    app: &AppHandle,
+    database_path: &Path,
+    mut roots: Vec<PathBuf>,
+    control: &'static IndexControl,
+) {
+    loop {
+        if let Err(error) = index_session(app, database_path, &mut roots, control) {
+            if let Ok(mut conn) = persistence::open_database(database_path) {
+                let had_initial_index = has_initial_index(&conn).unwrap_or(false);
+                let state = mark_run_failed(&mut conn, had_initial_index, &error.to_string())
+                    .unwrap_or_else(|_| IndexingState {
+                        status: IndexingStatus::Failed,
+                        has_initial_index: had_initial_index,
+                        indexed_item_count: 0,
+                        message: Some(error.to_string()),
+                    });
+                emit_indexing_state(app, &state);
+            }
+        }
+
+        // Leave the guard while going inactive, so whoever sees the indexer
+        // stopped can always schedule a new run.
+        let scheduled = SCHEDULED_DATABASES.get_or_init(|| Mutex::new(HashSet::new()));
+        let mut scheduled = scheduled
+            .lock()
+            .expect("metadata index scheduled-database guard should not be poisoned");
+        if !control.finish() {
+            scheduled.remove(database_path);
+            return;
+        }
+        drop(scheduled);
+
+        // A resume arrived while the cancelled run was stopping; start over
+        // from its checkpoint with the current configuration.
+        if let Ok(reloaded) = persistence::open_database(database_path)
+            .and_then(|conn| index_config::load_index_roots(&conn))
+        {
+            if !reloaded.is_empty() {
+                roots = reloaded;
+            }
+        }
+    }
+}
+
+/// One run followed by watcher maintenance, repeated for every configuration
+/// change. Returns once the indexer is cancelled or fails.
+fn index_session(
+    app: &AppHandle,
+    database_path: &Path,
+    roots: &mut Vec<PathBuf>,
+    control: &IndexControl,
+) -> Result<()> {
+    loop {
+        // Watch before the walk starts so changes made while it runs are
+        // applied once it finishes. Per-folder watches are added as the walk
+        // reaches each folder.
+        let mut watch = persistence::open_database(database_path)
+            .and_then(|conn| IndexRules::load(&conn))
+            .map_err(|error| notify::Error::generic(&error.to_string()))
+            .and_then(|rules| IndexWatcher::start(roots, rules, WATCH_EVENT_CAPACITY));
+        let result = run_metadata_index_watched(
+            database_path,
+            roots.clone(),
+            control,
+            watch.as_mut().ok().map(|(watcher, _)| watcher),
+            |state| emit_indexing_state(app, &state),
+        );
+
+        let result = match (result, watch) {
+            (Ok(summary), _) if summary.cancelled => Ok(()),
+            (Ok(_), Ok((mut watcher, events))) => maintain_metadata_index(
+                database_path,
+                roots,
+                &mut watcher,
+                events,
+                control,
+                |state| emit_indexing_state(app, &state),
+            ),
+            (Ok(_), Err(_error)) => {
+                #[cfg(debug_assertions)]
+                eprintln!("[frogger] failed to watch index roots: {_error:?}");
+                Ok(())
+            }
+            (Err(error), _) => Err(error),
+        };
+
+        if result.is_ok() && control.take_reconfigured() {
+            let reloaded = persistence::open_database(database_path)
+                .and_then(|conn| index_config::load_index_roots(&conn))?;
+            if !reloaded.is_empty() {
+                *roots = reloaded;
+                continue;
+            }
+        }
+        return result;
+    }
+}
+
 pub fn run_metadata_index<F>(
     database_path: &Path,
     roots: Vec<PathBuf>,
+    on_progress: F,
+) -> Result<IndexingSummary>
+where
+    F: FnMut(IndexingState),
+{
+    run_metadata_index_with_control(database_path, roots, &IndexControl::default(), on_progress)
+}
+
+/// Runs the metadata index while honoring `control`. A paused run flushes
+/// its batch and reports [`IndexingStatus::Paused`] until it is resumed. A
+/// cancelled run stops without pruning, keeping its resume point.
+pub fn run_metadata_index_with_control<F>(
+    database_path: &Path,
+    roots: Vec<PathBuf>,
+    control: &IndexControl,
+    on_progress: F,
+) -> Result<IndexingSummary>
+where
+    F: FnMut(IndexingState),
+{
+    run_metadata_index_watched(database_path, roots, control, None, on_progress)
+}
+
+/// The full run behind [`run_metadata_index_with_control`]. With a per-folder
+/// `watcher`, every folder is watched as the walk reaches it, so the tree is
+/// only walked once.
+fn run_metadata_index_watched<F>(
+    database_path: &Path,
+    roots: Vec<PathBuf>,
+    control: &IndexControl,
+    mut watcher: Option<&mut IndexWatcher>,
     mut on_progress: F,
 ) -> Result<IndexingSummary>
 where
@@ -175,42 +524,54 @@ where
 {
     let started = Instant::now();
     let mut conn = persistence::open_database(database_path)?;
-    let run_started_at = now_rfc3339();
     let mut counters = IndexingCounters::default();
     let mut batch = Vec::with_capacity(DEFAULT_BATCH_SIZE);
     let mut last_progress = Instant::now();
+    let mut throttle = Throttle::new();
+
+    let rules = IndexRules::load(&conn)?;
+    let normalization = SearchNormalization::load(&conn)?;
 
     let started_state = mark_run_started(&mut conn, &roots)?;
-    let initial_build = !started_state.has_initial_index;
+    let initial_build = started_state.status == IndexingStatus::InitialBuild;
+    let run_status = started_state.status.clone();
     on_progress(started_state);
 
+    // An initial build records where it is so a later launch can resume it.
+    // Rows keep the original run's timestamp so pruning still sees them as
+    // fresh.
+    let mut resume = load_resume_point(&conn)?;
+    let run_started_at = resume
+        .as_ref()
+        .map(|resume| resume.run_started_at.clone())
+        .unwrap_or_else(now_rfc3339);
+    let completed_dirs = resume
+        .as_ref()
+        .map(|resume| resume.completed_dirs.clone())
+        .unwrap_or_default();
+
     for root in roots.iter().filter(|root| root.is_dir()) {
-        let mut builder = WalkBuilder::new(root);
-        builder
-            .follow_links(false)
-            .hidden(false)
-            .ignore(false)
-            .git_ignore(false)
-            .git_global(false)
-            .git_exclude(false)
-            .parents(false);
-
-        let home_for_exclusions = roots.first().cloned();
-        let walk = builder
-            .filter_entry(move |entry| {
-                entry.depth() == 0
-                    || !is_default_excluded_path(
-                        entry.path(),
-                        entry
-                            .file_type()
-                            .map(|file_type| file_type.is_dir())
-                            .unwrap_or(false),
-                        home_for_exclusions.as_deref(),
-                    )
-            })
-            .build();
+        if let Some(watcher) = watcher.as_mut() {
+            // The walk skips folders an interrupted build already finished,
+            // so those are watched on their own.
+            for dir in completed_dirs.iter().map(PathBuf::from) {
+                if dir.starts_with(root) {
+                    watcher.watch_tree(&dir);
+                }
+            }
+        }
+        if let Some(resume) = resume.as_mut() {
+            if resume
+                .completed_dirs
+                .contains(root.to_string_lossy().as_ref())
+            {
+                continue;
+            }
+            resume.current_root = Some(root.to_string_lossy().into_owned());
+        }
 
-        for entry_result in walk {
+        let mut open_dirs: Vec<(usize, PathBuf)> = Vec::new();
+        for entry_result in metadata_walk(root, rules.clone(), completed_dirs.clone()) {
             let entry = match entry_result {
                 Ok(entry) => entry,
                 Err(_error) => {
@@ -219,17 +580,85 @@ where
                 }
             };
 
+            let control_state = control.snapshot();
+            if control_state.paused || control_state.cancelled {
+                flush_batch(&mut conn, &mut batch, &run_started_at)?;
+                counters.indexed_item_count = count_indexed_items_from_table(&conn)?;
+                if control_state.paused {
+                    on_progress(mark_run_progress(
+                        &mut conn,
+                        IndexingStatus::Paused,
+                        true,
+                        &roots,
+                        &counters,
+                        resume.as_ref(),
+                        "Indexing paused",
+                    )?);
+                }
+                if !control.wait_while_paused() {
+                    on_progress(mark_run_progress(
+                        &mut conn,
+                        IndexingStatus::Cancelled,
+                        true,
+                        &roots,
+                        &counters,
+                        resume.as_ref(),
+                        "Indexing cancelled",
+                    )?);
+                    return Ok(IndexingSummary {
+                        indexed_item_count: counters.indexed_item_count,
+                        dirs_visited: counters.dirs_visited,
+                        files_visited: counters.files_visited,
+                        metadata_errors: counters.metadata_errors,
+                        pruned_item_count: 0,
+                        elapsed_ms: started.elapsed().as_millis(),
+                        cancelled: true,
+                    });
+                }
+                on_progress(mark_run_progress(
+                    &mut conn,
+                    run_status.clone(),
+                    true,
+                    &roots,
+                    &counters,
+                    resume.as_ref(),
+                    "Indexing…",
+                )?);
+                throttle = Throttle::new();
+                last_progress = Instant::now();
+            }
+            throttle.pace(control_state.entries_per_second);
+
             let is_dir = entry
                 .file_type()
                 .map(|file_type| file_type.is_dir())
                 .unwrap_or(false);
             if is_dir {
                 counters.dirs_visited += 1;
+                if let Some(watcher) = watcher.as_mut() {
+                    watcher.watch_folder(entry.path());
+                }
             } else {
                 counters.files_visited += 1;
             }
 
-            match metadata_row_from_path(entry.path()) {
+            // The walk is depth-first, so reaching an entry no deeper than an
+            // open folder means that folder has been fully visited.
+            if let Some(resume) = resume.as_mut() {
+                while open_dirs
+                    .last()
+                    .is_some_and(|(depth, _)| *depth >= entry.depth())
+                {
+                    if let Some((_, dir)) = open_dirs.pop() {
+                        resume.complete_dir(&dir);
+                    }
+                }
+                if is_dir {
+                    open_dirs.push((entry.depth(), entry.path().to_path_buf()));
+                }
+            }
+
+            match metadata_row_from_path(entry.path(), normalization) {
                 Ok(Some(row)) => batch.push(row),
                 Ok(None) => {}
                 Err(_error) => counters.metadata_errors += 1,
@@ -243,8 +672,15 @@ where
                 } else {
                     IndexingStatus::Reconciling
                 };
-                let state =
-                    mark_run_progress(&mut conn, status, true, &roots, &counters, "Indexing…")?;
+                let state = mark_run_progress(
+                    &mut conn,
+                    status,
+                    true,
+                    &roots,
+                    &counters,
+                    resume.as_ref(),
+                    "Indexing…",
+                )?;
                 on_progress(state);
                 last_progress = Instant::now();
             } else if last_progress.elapsed() >= PROGRESS_INTERVAL {
@@ -265,6 +701,12 @@ where
                 last_progress = Instant::now();
             }
         }
+
+        if let Some(resume) = resume.as_mut() {
+            while let Some((_, dir)) = open_dirs.pop() {
+                resume.complete_dir(&dir);
+            }
+        }
     }
 
     if !batch.is_empty() {
@@ -273,7 +715,19 @@ where
 
     counters.pruned_item_count = prune_stale_rows(&conn, &roots, &run_started_at)?;
     counters.indexed_item_count = count_indexed_items_from_table(&conn)?;
-    let completed_state = mark_run_completed(&mut conn, &roots, &counters)?;
+    // A configuration change during the walk may have widened what should be
+    // indexed, so the run only vouches for roots it already covered.
+    let covered_roots = if control.snapshot().reconfigured {
+        let previous = load_covered_roots(&conn)?;
+        roots
+            .iter()
+            .filter(|root| previous.contains(root))
+            .cloned()
+            .collect()
+    } else {
+        roots.clone()
+    };
+    let completed_state = mark_run_completed(&mut conn, &roots, &covered_roots, &counters)?;
     on_progress(completed_state);
 
     Ok(IndexingSummary {
@@ -283,56 +737,646 @@ where
         metadata_errors: counters.metadata_errors,
         pruned_item_count: counters.pruned_item_count,
         elapsed_ms: started.elapsed().as_millis(),
+        cancelled: false,
     })
 }
 
-pub(crate) fn is_default_excluded_path(path: &Path, is_dir: bool, home_dir: Option<&Path>) -> bool {
-    if !is_dir {
-        return false;
+/// Filesystem changes reported under the index roots since the last update.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IndexChanges {
+    /// Paths whose own row should be refreshed, or removed with its
+    /// descendants when the path no longer exists.
+    pub paths: BTreeSet<PathBuf>,
+    /// Subtrees that must be walked again because their events were lost.
+    pub rescans: BTreeSet<PathBuf>,
+    /// Paths that were created or renamed into place, which may be folders
+    /// that need their own watch.
+    pub created: BTreeSet<PathBuf>,
+}
+
+impl IndexChanges {
+    /// Adds one watcher event. An event flagged for rescan means the
+    /// platform queue overflowed, so its paths, or every root when it names
+    /// none, are walked again instead of being patched row by row.
+    pub fn record(&mut self, event: &Event, roots: &[PathBuf]) {
+        if event.need_rescan() {
+            if event.paths.is_empty() {
+                self.rescans.extend(roots.iter().cloned());
+            } else {
+                self.rescans.extend(event.paths.iter().cloned());
+            }
+            return;
+        }
+
+        match event.kind {
+            EventKind::Access(_) => return,
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)) => {
+                self.created.extend(event.paths.iter().cloned());
+            }
+            _ => {}
+        }
+        self.paths.extend(event.paths.iter().cloned());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.rescans.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IndexUpdateSummary {
+    pub upserted_item_count: u64,
+    pub removed_item_count: u64,
+}
+
+/// Applies watcher changes to `metadata_index` without walking the roots.
+///
+/// Existing paths are upserted and missing paths are deleted together with
+/// their descendants. Folders that appear with unindexed contents, such as a
+/// folder moved in from outside the roots, and every requested rescan are
+/// walked and reconciled like a full run limited to that subtree.
+pub fn apply_index_changes(
+    conn: &mut Connection,
+    roots: &[PathBuf],
+    changes: &IndexChanges,
+) -> Result<IndexUpdateSummary> {
+    let rules = IndexRules::load(conn)?;
+    let normalization = SearchNormalization::load(conn)?;
+    let mut summary = IndexUpdateSummary::default();
+    let mut rescans = changes
+        .rescans
+        .iter()
+        .filter(|path| is_indexed_path(path, roots, &rules))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut batch = Vec::new();
+    let mut removed = Vec::new();
+
+    for path in &changes.paths {
+        if rescans.iter().any(|rescan| path.starts_with(rescan))
+            || !is_indexed_path(path, roots, &rules)
+        {
+            continue;
+        }
+
+        match metadata_row_from_path(path, normalization) {
+            Ok(Some(row)) if row.is_dir && !is_indexed_dir(conn, &row.path)? => {
+                rescans.push(path.clone());
+            }
+            Ok(Some(row)) => batch.push(row),
+            Ok(None) => {}
+            Err(_error) if std::fs::symlink_metadata(path).is_err() => removed.push(path.clone()),
+            Err(_error) => {
+                #[cfg(debug_assertions)]
+                eprintln!("[frogger] failed to refresh index row: {_error:?}");
+            }
+        }
+    }
+
+    summary.upserted_item_count += batch.len() as u64;
+    flush_batch(conn, &mut batch, &now_rfc3339())?;
+    for path in &removed {
+        summary.removed_item_count += remove_indexed_subtree(conn, path)?;
+    }
+
+    rescans.sort();
+    rescans.dedup();
+    let mut rescanned: Vec<PathBuf> = Vec::new();
+    for subtree in rescans {
+        if rescanned.iter().any(|done| subtree.starts_with(done)) {
+            continue;
+        }
+
+        let rescan_started_at = now_rfc3339();
+        if subtree.is_dir() {
+            for entry in metadata_walk(&subtree, rules.clone(), BTreeSet::new()).flatten() {
+                if let Ok(Some(row)) = metadata_row_from_path(entry.path(), normalization) {
+                    batch.push(row);
+                }
+                if batch.len() >= DEFAULT_BATCH_SIZE {
+                    summary.upserted_item_count += batch.len() as u64;
+                    flush_batch(conn, &mut batch, &rescan_started_at)?;
+                }
+            }
+        } else if let Ok(Some(row)) = metadata_row_from_path(&subtree, normalization) {
+            batch.push(row);
+        }
+        summary.upserted_item_count += batch.len() as u64;
+        flush_batch(conn, &mut batch, &rescan_started_at)?;
+        summary.removed_item_count += prune_stale_subtree(conn, &subtree, &rescan_started_at)?;
+        rescanned.push(subtree);
+    }
+
+    Ok(summary)
+}
+
+/// Whether searching the index sees everything below `folder`: the folder is
+/// inside a root a completed run has walked, and is not excluded.
+pub fn covers_folder(conn: &Connection, folder: &Path) -> Result<bool> {
+    if !has_initial_index(conn)? {
+        return Ok(false);
+    }
+    let covered = load_covered_roots(conn)?;
+    let roots = index_config::load_index_roots(conn)?
+        .into_iter()
+        .filter(|root| covered.contains(root))
+        .collect::<Vec<_>>();
+    let rules = IndexRules::load(conn)?;
+    Ok(is_indexed_path(folder, &roots, &rules))
+}
+
+/// Records a change to the index roots or exclusions and asks the running
+/// indexer to reconcile. `widened` means previously excluded items may now
+/// belong in the index, so no root counts as covered until the next run
+/// completes.
+pub fn configuration_changed(conn: &Connection, widened: bool) -> Result<()> {
+    if widened {
+        conn.execute(
+            "UPDATE index_state SET covered_roots_json = '[]' WHERE id = ?1",
+            [METADATA_INDEX_ID],
+        )?;
+    }
+    shared_index_control().reconfigure();
+    Ok(())
+}
+
+/// Roots the last completed run walked under the current exclusions.
+fn load_covered_roots(conn: &Connection) -> Result<Vec<PathBuf>> {
+    let covered_roots_json: String = conn.query_row(
+        "SELECT covered_roots_json FROM index_state WHERE id = ?1",
+        [METADATA_INDEX_ID],
+        |row| row.get(0),
+    )?;
+    Ok(serde_json::from_str(&covered_roots_json).unwrap_or_default())
+}
+
+/// Walks `root` into `conn`'s metadata table the way the indexer would, for
+/// folders the index does not cover. Stops after `max_entries` entries or at
+/// `deadline` and returns whether the whole subtree was visited.
+pub fn snapshot_folder(
+    conn: &mut Connection,
+    root: &Path,
+    rules: IndexRules,
+    normalization: SearchNormalization,
+    max_entries: usize,
+    deadline: Instant,
+) -> Result<bool> {
+    let indexed_at = now_rfc3339();
+    let mut batch = Vec::with_capacity(DEFAULT_BATCH_SIZE);
+    let mut visited = 0_usize;
+    for entry in metadata_walk(root, rules, BTreeSet::new()).flatten() {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if visited >= max_entries || Instant::now() >= deadline {
+            flush_batch(conn, &mut batch, &indexed_at)?;
+            return Ok(false);
+        }
+        visited += 1;
+
+        if let Ok(Some(row)) = metadata_row_from_path(entry.path(), normalization) {
+            batch.push(row);
+        }
+        if batch.len() >= DEFAULT_BATCH_SIZE {
+            flush_batch(conn, &mut batch, &indexed_at)?;
+        }
+    }
+    flush_batch(conn, &mut batch, &indexed_at)?;
+    Ok(true)
+}
+
+/// Keeps `metadata_index` current from filesystem events until the watcher
+/// is dropped, `control` is cancelled or the index configuration changes. Events are applied in debounced
+/// batches; `on_update` receives the index state after every batch that
+/// changed rows and whenever updates are paused or resumed.
+///
+/// A pass that fails, for example because another connection holds the
+/// database, is reported and retried with its changes kept, backing off
+/// between attempts. Only [`MAX_CHANGE_RETRIES`] failures in a row, which
+/// point at a lasting problem such as a damaged database, end maintenance.
+fn maintain_metadata_index<F>(
+    database_path: &Path,
+    roots: &[PathBuf],
+    watcher: &mut IndexWatcher,
+    events: Receiver<notify::Result<Event>>,
+    control: &IndexControl,
+    on_update: F,
+) -> Result<()>
+where
+    F: FnMut(IndexingState),
+{
+    let mut maintenance = Maintenance {
+        conn: persistence::open_database(database_path)?,
+        roots,
+        watcher,
+        events,
+        control,
+        on_update,
+        pending: IndexChanges::default(),
+        content_enabled: false,
+        content_pending: false,
+        photos_enabled: false,
+        photos_pending: false,
+    };
+    let mut failures = 0_u32;
+    loop {
+        match maintenance.pass() {
+            Ok(true) => failures = 0,
+            Ok(false) => return Ok(()),
+            Err(error) if failures + 1 >= MAX_CHANGE_RETRIES => return Err(error),
+            Err(error) => {
+                let delay = CHANGE_RETRY_DELAY
+                    .saturating_mul(1 << failures)
+                    .min(MAX_CHANGE_RETRY_DELAY);
+                failures += 1;
+                #[cfg(debug_assertions)]
+                eprintln!("[frogger] metadata index update failed, retrying: {error:?}");
+                let count = count_indexed_items_from_table(&maintenance.conn).unwrap_or(0);
+                (maintenance.on_update)(progress_state(
+                    IndexingStatus::Failed,
+                    true,
+                    count,
+                    Some(format!(
+                        "Index update failed, retrying in {} seconds: {error}",
+                        delay.as_secs()
+                    )),
+                ));
+                let retry_at = Instant::now() + delay;
+                while Instant::now() < retry_at && !control.snapshot().cancelled {
+                    thread::sleep(CONTROL_POLL_INTERVAL);
+                }
+                if let Ok(conn) = persistence::open_database(database_path) {
+                    maintenance.conn = conn;
+                }
+            }
+        }
+    }
+}
+
+/// State of [`maintain_metadata_index`] that survives a failed pass.
+struct Maintenance<'a, F> {
+    conn: Connection,
+    roots: &'a [PathBuf],
+    watcher: &'a mut IndexWatcher,
+    events: Receiver<notify::Result<Event>>,
+    control: &'a IndexControl,
+    on_update: F,
+    /// Changes received but not applied yet, kept across failed passes.
+    pending: IndexChanges,
+    content_enabled: bool,
+    content_pending: bool,
+    photos_enabled: bool,
+    photos_pending: bool,
+}
+
+impl<F> Maintenance<'_, F>
+where
+    F: FnMut(IndexingState),
+{
+    /// Runs one round of control checks, backfills and event batching.
+    /// Returns false once maintenance should stop.
+    fn pass(&mut self) -> Result<bool> {
+        let control_state = self.control.snapshot();
+        if control_state.paused && !control_state.cancelled {
+            // Events keep queueing while paused and are applied on resume.
+            let count = count_indexed_items_from_table(&self.conn)?;
+            (self.on_update)(mark_index_status(
+                &mut self.conn,
+                IndexingStatus::Paused,
+                count,
+                "Index updates paused",
+            )?);
+            if self.control.wait_while_paused() {
+                (self.on_update)(mark_index_status(
+                    &mut self.conn,
+                    IndexingStatus::Ready,
+                    count,
+                    "Index ready",
+                )?);
+            }
+        }
+        if self.control.snapshot().cancelled {
+            let count = count_indexed_items_from_table(&self.conn)?;
+            (self.on_update)(mark_index_status(
+                &mut self.conn,
+                IndexingStatus::Cancelled,
+                count,
+                "Index updates stopped",
+            )?);
+            return Ok(false);
+        }
+        if self.control.snapshot().reconfigured {
+            // The indexer thread walks the new configuration and comes back.
+            return Ok(false);
+        }
+
+        // Document text and photo EXIF are read in small batches between
+        // event batches, so turning either on backfills without holding up
+        // updates.
+        let enabled = content_index::is_enabled(&self.conn)?;
+        self.content_pending |= enabled && !self.content_enabled;
+        self.content_enabled = enabled;
+        if self.content_pending {
+            let visited = content_index::sync(&mut self.conn, content_index::SYNC_BATCH_SIZE)?;
+            self.content_pending = visited == content_index::SYNC_BATCH_SIZE;
+        }
+        let enabled = photo_metadata::is_enabled(&self.conn)?;
+        self.photos_pending |= enabled && !self.photos_enabled;
+        self.photos_enabled = enabled;
+        if self.photos_pending {
+            let visited = photo_metadata::sync(&mut self.conn, photo_metadata::SYNC_BATCH_SIZE)?;
+            self.photos_pending = visited == photo_metadata::SYNC_BATCH_SIZE;
+        }
+
+        let connected = self.collect_changes();
+        if self.pending.is_empty() {
+            return Ok(connected);
+        }
+
+        self.watcher.watch_new_folders(&self.pending);
+        let summary = apply_index_changes(&mut self.conn, self.roots, &self.pending)?;
+        self.pending = IndexChanges::default();
+        if summary != IndexUpdateSummary::default() {
+            self.content_pending = self.content_enabled;
+            self.photos_pending = self.photos_enabled;
+            let count = count_indexed_items_from_table(&self.conn)?;
+            (self.on_update)(mark_index_status(
+                &mut self.conn,
+                IndexingStatus::Ready,
+                count,
+                "Index ready",
+            )?);
+        }
+        Ok(connected)
+    }
+
+    /// Adds the next debounced burst of events to `pending`, plus a rescan of
+    /// every root whose events overflowed the queue. A steady stream is cut
+    /// off after [`MAX_CHANGE_DELAY`] so it cannot hold back updates. Returns
+    /// false once the watcher has gone away.
+    fn collect_changes(&mut self) -> bool {
+        self.pending.rescans.extend(self.watcher.take_overflowed());
+        let wait = if self.content_pending || self.photos_pending || !self.pending.is_empty() {
+            Duration::ZERO
+        } else {
+            CONTROL_POLL_INTERVAL
+        };
+        let mut next = match self.events.recv_timeout(wait) {
+            Ok(first) => Some(first),
+            Err(RecvTimeoutError::Timeout) => return true,
+            Err(RecvTimeoutError::Disconnected) => return false,
+        };
+
+        let started = Instant::now();
+        while let Some(result) = next.take() {
+            match result {
+                Ok(event) => self.pending.record(&event, self.roots),
+                // The watcher could not tell what changed, so nothing it
+                // reported since the last batch can be trusted.
+                Err(_error) => self.pending.rescans.extend(self.roots.iter().cloned()),
+            }
+            if started.elapsed() >= MAX_CHANGE_DELAY {
+                break;
+            }
+            next = match self.events.recv_timeout(CHANGE_DEBOUNCE) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => return false,
+            };
+        }
+        true
+    }
+}
+
+/// Watches the index roots for changes.
+///
+/// FSEvents and ReadDirectoryChangesW cover a whole tree with one watch, so
+/// there each root is watched recursively. inotify and kqueue need a watch per
+/// folder, so elsewhere every indexed folder is registered on its own and
+/// excluded subtrees such as `node_modules` are never watched. Either way,
+/// events that only touch excluded paths are dropped before they are queued.
+///
+/// At most `capacity` events wait in the queue. Events that do not fit are
+/// dropped and the roots they fell under are handed out by
+/// [`IndexWatcher::take_overflowed`] to be walked again.
+struct IndexWatcher {
+    watcher: RecommendedWatcher,
+    roots: Vec<PathBuf>,
+    rules: IndexRules,
+    overflowed: Arc<Mutex<BTreeSet<PathBuf>>>,
+    /// Set once the platform refused more watches; further folders are left
+    /// to the next full run.
+    out_of_watches: bool,
+}
+
+impl IndexWatcher {
+    /// Starts watching `roots`. With per-folder watches nothing is watched
+    /// yet: the full run registers each folder as its walk reaches it. Roots
+    /// that cannot be watched are left to the next full run.
+    fn start(
+        roots: &[PathBuf],
+        rules: IndexRules,
+        capacity: usize,
+    ) -> notify::Result<(Self, Receiver<notify::Result<Event>>)> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let overflowed = Arc::new(Mutex::new(BTreeSet::new()));
+        let filter_overflowed = Arc::clone(&overflowed);
+        let filter_roots = roots.to_vec();
+        let filter_rules = rules.clone();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            if let Ok(event) = &result {
+                if !event.need_rescan()
+                    && !event
+                        .paths
+                        .iter()
+                        .any(|path| is_indexed_path(path, &filter_roots, &filter_rules))
+                {
+                    return;
+                }
+            }
+            let Err(TrySendError::Full(result)) = sender.try_send(result) else {
+                return;
+            };
+            let mut hit = filter_roots
+                .iter()
+                .filter(|root| {
+                    result
+                        .as_ref()
+                        .is_ok_and(|event| event.paths.iter().any(|path| path.starts_with(root)))
+                })
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            if hit.is_empty() {
+                hit.extend(filter_roots.iter().cloned());
+            }
+            if let Ok(mut overflowed) = filter_overflowed.lock() {
+                overflowed.extend(hit);
+            }
+        })?;
+
+        let mut index_watcher = Self {
+            watcher,
+            roots: roots.to_vec(),
+            rules,
+            overflowed,
+            out_of_watches: false,
+        };
+        if !WATCH_PER_FOLDER {
+            for root in roots {
+                if let Err(_error) = index_watcher.watcher.watch(root, RecursiveMode::Recursive) {
+                    #[cfg(debug_assertions)]
+                    eprintln!(
+                        "[frogger] failed to watch index root {}: {_error:?}",
+                        root.display()
+                    );
+                }
+            }
+        }
+        Ok((index_watcher, receiver))
+    }
+
+    /// Roots whose events were dropped since the last call.
+    fn take_overflowed(&self) -> BTreeSet<PathBuf> {
+        std::mem::take(
+            &mut *self
+                .overflowed
+                .lock()
+                .expect("index watcher overflow should not be poisoned"),
+        )
+    }
+
+    /// Registers folders that appeared in `changes`. Recursive watches pick
+    /// them up on their own.
+    fn watch_new_folders(&mut self, changes: &IndexChanges) {
+        if !WATCH_PER_FOLDER {
+            return;
+        }
+        for path in changes.created.iter().chain(&changes.rescans) {
+            if path.is_dir() && is_indexed_path(path, &self.roots, &self.rules) {
+                self.watch_tree(path);
+            }
+        }
+    }
+
+    /// Watches every indexed folder in the tree at `path`.
+    fn watch_tree(&mut self, path: &Path) {
+        if !WATCH_PER_FOLDER {
+            return;
+        }
+        let folders = metadata_walk(path, self.rules.clone(), BTreeSet::new())
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_dir()));
+        for folder in folders {
+            if self.out_of_watches {
+                return;
+            }
+            self.watch_folder(folder.path());
+        }
     }
 
-    if home_dir
-        .map(|home| {
-            path == home.join("Library")
-                || path == home.join("Applications")
-                || path == home.join(".Trash")
-        })
-        .unwrap_or(false)
-    {
-        return true;
+    /// Watches one folder, without its subfolders. Recursive watches already
+    /// cover it.
+    fn watch_folder(&mut self, path: &Path) {
+        if !WATCH_PER_FOLDER || self.out_of_watches {
+            return;
+        }
+        match self.watcher.watch(path, RecursiveMode::NonRecursive) {
+            Ok(()) => {}
+            // Out of watches: the rest is left to the next full run.
+            Err(_error) if matches!(_error.kind, notify::ErrorKind::MaxFilesWatch) => {
+                #[cfg(debug_assertions)]
+                eprintln!(
+                    "[frogger] ran out of watches at {}: {_error:?}",
+                    path.display()
+                );
+                self.out_of_watches = true;
+            }
+            // The folder vanished or cannot be read; skip just this one.
+            Err(_error) => {}
+        }
     }
+}
 
-    let Some(name) = path.file_name().and_then(|value| value.to_str()) else {
+/// True when `path` lies under an index root and neither it nor any folder
+/// between it and that root is excluded.
+fn is_indexed_path(path: &Path, roots: &[PathBuf], rules: &IndexRules) -> bool {
+    let Some(root) = roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.as_os_str().len())
+    else {
+        return false;
+    };
+    let Ok(relative) = path.strip_prefix(root) else {
         return false;
     };
-    let lower = name.to_ascii_lowercase();
-
-    if lower.starts_with('.') {
-        return true;
-    }
-
-    matches!(
-        lower.as_str(),
-        "node_modules"
-            | "target"
-            | "dist"
-            | "build"
-            | "coverage"
-            | "__pycache__"
-            | "vendor"
-            | "tmp"
-            | "temp"
-            | "cache"
-            | "caches"
-            | "appdata"
-            | "system volume information"
-            | "$recycle.bin"
-            | "venv"
-            | "env"
+
+    let mut current = root.clone();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        current.push(component);
+        let is_dir = components.peek().is_some()
+            || std::fs::symlink_metadata(&current)
+                .map(|metadata| metadata.is_dir())
+                .unwrap_or(false);
+        if rules.is_excluded(&current, is_dir) {
+            return false;
+        }
+    }
+    true
+}
+
+fn is_indexed_dir(conn: &Connection, path: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM metadata_index WHERE path = ?1 AND is_dir = 1)",
+        [path],
+        |row| row.get(0),
     )
+    .context("failed to look up indexed folder")
+}
+
+fn remove_indexed_subtree(conn: &Connection, path: &Path) -> Result<u64> {
+    let path_string = path.to_string_lossy().into_owned();
+    let affected = conn.execute(
+        "DELETE FROM metadata_index WHERE path = ?1 OR path LIKE ?2 ESCAPE '\\'",
+        params![path_string, child_like_pattern(&path_string)],
+    )?;
+    Ok(affected as u64)
+}
+
+/// Walks `root` the way the metadata indexer does: without following links or
+/// honoring ignore files, and skipping excluded entries and `completed_dirs`
+/// below it.
+fn metadata_walk(root: &Path, rules: IndexRules, completed_dirs: BTreeSet<String>) -> ignore::Walk {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .follow_links(false)
+        .hidden(false)
+        .ignore(false)
+        .git_ignore(false)
+        .git_global(false)
+        .git_exclude(false)
+        .parents(false);
+
+    builder
+        .filter_entry(move |entry| {
+            entry.depth() == 0
+                || (!completed_dirs.contains(entry.path().to_string_lossy().as_ref())
+                    && !rules.is_excluded(
+                        entry.path(),
+                        entry
+                            .file_type()
+                            .map(|file_type| file_type.is_dir())
+                            .unwrap_or(false),
+                    ))
+        })
+        .build()
 }
 
-fn metadata_row_from_path(path: &Path) -> Result<Option<MetadataIndexRow>> {
+fn metadata_row_from_path(
+    path: &Path,
+    normalization: SearchNormalization,
+) -> Result<Option<MetadataIndexRow>> {
     let name = path
         .file_name()
         .and_then(|value| value.to_str())
@@ -366,15 +1410,14 @@ fn metadata_row_from_path(path: &Path) -> Result<Option<MetadataIndexRow>> {
     };
     let display_name = name.clone();
     let path_string = path.to_string_lossy().into_owned();
-    let search_text = format!(
-        "{} {} {} {} {}",
-        name,
-        display_name,
-        parent_path,
-        kind,
-        extension.as_deref().unwrap_or_default()
-    )
-    .to_ascii_lowercase();
+    let search_text = metadata_search_text(
+        &name,
+        &display_name,
+        &parent_path,
+        &kind,
+        extension.as_deref(),
+        normalization,
+    );
 
     Ok(Some(MetadataIndexRow {
         path: path_string,
@@ -392,6 +1435,87 @@ fn metadata_row_from_path(path: &Path) -> Result<Option<MetadataIndexRow>> {
     }))
 }
 
+/// Builds the folded text that search candidates are matched against. Queries
+/// are folded with the same [`SearchNormalization`].
+fn metadata_search_text(
+    name: &str,
+    display_name: &str,
+    parent_path: &str,
+    kind: &str,
+    extension: Option<&str>,
+    normalization: SearchNormalization,
+) -> String {
+    normalization.fold(&format!(
+        "{} {} {} {} {}",
+        name,
+        display_name,
+        parent_path,
+        kind,
+        extension.unwrap_or_default()
+    ))
+}
+
+/// Refolds `search_text` for every indexed row, for when the normalization
+/// options change. Works through the table in path order so memory stays
+/// flat on large indexes. Returns how many rows changed.
+pub fn refresh_search_text(
+    conn: &mut Connection,
+    normalization: SearchNormalization,
+) -> Result<u64> {
+    let mut after = String::new();
+    let mut updated = 0_u64;
+    loop {
+        let rows = {
+            let mut stmt = conn.prepare(
+                "SELECT path, name, display_name, parent_path, kind, extension, search_text
+                 FROM metadata_index
+                 WHERE path > ?1
+                 ORDER BY path
+                 LIMIT ?2",
+            )?;
+            let rows = stmt
+                .query_map(params![after, DEFAULT_BATCH_SIZE as i64], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                        row.get::<_, String>(6)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+        let Some((last_path, ..)) = rows.last() else {
+            return Ok(updated);
+        };
+        after = last_path.clone();
+
+        let tx = conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("UPDATE metadata_index SET search_text = ?1 WHERE path = ?2")?;
+            for (path, name, display_name, parent_path, kind, extension, search_text) in &rows {
+                let refreshed = metadata_search_text(
+                    name,
+                    display_name,
+                    parent_path,
+                    kind,
+                    extension.as_deref(),
+                    normalization,
+                );
+                if refreshed != *search_text {
+                    stmt.execute(params![refreshed, path])?;
+                    updated += 1;
+                }
+            }
+        }
+        tx.commit()?;
+    }
+}
+
 fn flush_batch(
     conn: &mut Connection,
     batch: &mut Vec<MetadataIndexRow>,
@@ -403,11 +1527,14 @@ fn flush_batch(
 
     let tx = conn.transaction()?;
     {
-        let mut stmt = tx.prepare(
+        // Boosts are recomputed on every write so the ranking keeps up with
+        // recents and with time passing between runs.
+        let mut stmt = tx.prepare(&format!(
             "INSERT INTO metadata_index (
                 path, parent_path, name, display_name, kind, is_dir, size,
-                modified_at, created_at, indexed_at, hidden, extension, search_text
-             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+                modified_at, created_at, indexed_at, hidden, extension, search_text,
+                recent_boost, modified_boost
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, {}, {})
              ON CONFLICT(path) DO UPDATE SET
                 parent_path = excluded.parent_path,
                 name = excluded.name,
@@ -420,9 +1547,14 @@ fn flush_batch(
                 indexed_at = excluded.indexed_at,
                 hidden = excluded.hidden,
                 extension = excluded.extension,
-                search_text = excluded.search_text",
-        )?;
-
+                search_text = excluded.search_text,
+                recent_boost = excluded.recent_boost,
+                modified_boost = excluded.modified_boost",
+            frecency::recent_boost_sql("?1", "?14"),
+            frecency::modified_boost_sql("?8", "?14"),
+        ))?;
+
+        let now = now_rfc3339();
         for row in batch.iter() {
             stmt.execute(params![
                 &row.path,
@@ -438,6 +1570,7 @@ fn flush_batch(
                 bool_to_i64(row.hidden),
                 row.extension.as_deref(),
                 &row.search_text,
+                &now,
             ])?;
         }
     }
@@ -448,11 +1581,25 @@ fn flush_batch(
 
 fn mark_run_started(conn: &mut Connection, roots: &[PathBuf]) -> Result<IndexingState> {
     let has_initial = has_initial_index(conn)?;
-    let status = if has_initial {
+    let now = now_rfc3339();
+    let interrupted = load_resume_point(conn)?;
+    let resuming = interrupted.is_some();
+    let status = if has_initial && !resuming {
         IndexingStatus::Reconciling
     } else {
         IndexingStatus::InitialBuild
     };
+    let resume = interrupted.or_else(|| {
+        (status == IndexingStatus::InitialBuild).then(|| ResumePoint {
+            run_started_at: now.clone(),
+            ..ResumePoint::default()
+        })
+    });
+    let message = if resuming {
+        "Resuming index…"
+    } else {
+        "Indexing…"
+    };
     let count = count_indexed_items_from_checkpoint_or_table(conn)?;
     let checkpoint = checkpoint_json(
         roots,
@@ -460,9 +1607,9 @@ fn mark_run_started(conn: &mut Connection, roots: &[PathBuf]) -> Result<Indexing
             indexed_item_count: count,
             ..IndexingCounters::default()
         },
-        "Indexing…",
+        resume.as_ref(),
+        message,
     );
-    let now = now_rfc3339();
 
     conn.execute(
         "UPDATE index_state SET
@@ -487,7 +1634,7 @@ fn mark_run_started(conn: &mut Connection, roots: &[PathBuf]) -> Result<Indexing
         status,
         has_initial,
         count,
-        Some("Indexing…".to_string()),
+        Some(message.to_string()),
     ))
 }
 
@@ -497,9 +1644,10 @@ fn mark_run_progress(
     has_initial: bool,
     roots: &[PathBuf],
     counters: &IndexingCounters,
+    resume: Option<&ResumePoint>,
     message: &str,
 ) -> Result<IndexingState> {
-    let checkpoint = checkpoint_json(roots, counters, message);
+    let checkpoint = checkpoint_json(roots, counters, resume, message);
     let now = now_rfc3339();
     conn.execute(
         "UPDATE index_state SET
@@ -528,9 +1676,10 @@ fn mark_run_progress(
 fn mark_run_completed(
     conn: &mut Connection,
     roots: &[PathBuf],
+    covered_roots: &[PathBuf],
     counters: &IndexingCounters,
 ) -> Result<IndexingState> {
-    let checkpoint = checkpoint_json(roots, counters, "Index ready");
+    let checkpoint = checkpoint_json(roots, counters, None, "Index ready");
     let now = now_rfc3339();
     conn.execute(
         "UPDATE index_state SET
@@ -538,10 +1687,16 @@ fn mark_run_completed(
             has_initial_index = 1,
             completed_at = ?1,
             checkpoint_json = ?2,
+            covered_roots_json = ?3,
             error_json = NULL,
             updated_at = ?1
-         WHERE id = ?3",
-        params![now, checkpoint, METADATA_INDEX_ID],
+         WHERE id = ?4",
+        params![
+            now,
+            checkpoint,
+            serde_json::to_string(covered_roots)?,
+            METADATA_INDEX_ID
+        ],
     )?;
 
     Ok(progress_state(
@@ -552,6 +1707,41 @@ fn mark_run_completed(
     ))
 }
 
+/// Records the state of a finished index between full runs, while watcher
+/// changes keep it current.
+fn mark_index_status(
+    conn: &mut Connection,
+    status: IndexingStatus,
+    indexed_item_count: u64,
+    message: &str,
+) -> Result<IndexingState> {
+    conn.execute(
+        "UPDATE index_state SET
+            status = ?1,
+            checkpoint_json = json_set(
+                checkpoint_json,
+                '$.indexedItemCount', ?2,
+                '$.message', ?3
+            ),
+            updated_at = ?4
+         WHERE id = ?5",
+        params![
+            status_to_db(&status),
+            indexed_item_count as i64,
+            message,
+            now_rfc3339(),
+            METADATA_INDEX_ID
+        ],
+    )?;
+
+    Ok(progress_state(
+        status,
+        true,
+        indexed_item_count,
+        Some(message.to_string()),
+    ))
+}
+
 fn mark_run_failed(
     conn: &mut Connection,
     had_initial_index: bool,
@@ -583,7 +1773,12 @@ fn mark_run_failed(
     ))
 }
 
-fn checkpoint_json(roots: &[PathBuf], counters: &IndexingCounters, message: &str) -> String {
+fn checkpoint_json(
+    roots: &[PathBuf],
+    counters: &IndexingCounters,
+    resume: Option<&ResumePoint>,
+    message: &str,
+) -> String {
     json!({
         "indexedItemCount": counters.indexed_item_count,
         "dirsVisited": counters.dirs_visited,
@@ -591,27 +1786,72 @@ fn checkpoint_json(roots: &[PathBuf], counters: &IndexingCounters, message: &str
         "metadataErrors": counters.metadata_errors,
         "prunedItemCount": counters.pruned_item_count,
         "roots": roots.iter().map(|root| root.to_string_lossy().into_owned()).collect::<Vec<_>>(),
+        "resume": resume,
         "message": message,
     })
     .to_string()
 }
 
+/// Reads the resume point of an initial build that stopped before finishing,
+/// whether it was interrupted, cancelled or failed. Completed and reconciling
+/// runs leave nothing to resume.
+fn load_resume_point(conn: &Connection) -> Result<Option<ResumePoint>> {
+    let checkpoint_json: String = conn.query_row(
+        "SELECT checkpoint_json FROM index_state WHERE id = ?1",
+        [METADATA_INDEX_ID],
+        |row| row.get(0),
+    )?;
+
+    Ok(serde_json::from_str::<serde_json::Value>(&checkpoint_json)
+        .ok()
+        .and_then(|value| value.get("resume").cloned())
+        .and_then(|resume| serde_json::from_value(resume).ok()))
+}
+
+/// Removes rows a full run did not refresh: entries that disappeared or became
+/// excluded under a root, and everything outside the configured roots.
 fn prune_stale_rows(conn: &Connection, roots: &[PathBuf], run_started_at: &str) -> Result<u64> {
     let mut pruned = 0_u64;
+    for root in roots {
+        pruned += prune_stale_subtree(conn, root, run_started_at)?;
+    }
+
+    let mut conditions = Vec::with_capacity(roots.len());
+    let mut values = Vec::with_capacity(roots.len() * 2);
     for root in roots {
         let root_string = root.to_string_lossy().into_owned();
-        let pattern = child_like_pattern(&root_string);
-        let affected = conn.execute(
-            "DELETE FROM metadata_index
-             WHERE (path = ?1 OR path LIKE ?2 ESCAPE '\\') AND indexed_at < ?3",
-            params![root_string, pattern, run_started_at],
-        )?;
-        pruned += affected as u64;
+        conditions.push(format!(
+            "path LIKE ?{} ESCAPE '\\' OR path = ?{}",
+            values.len() + 1,
+            values.len() + 2
+        ));
+        values.push(child_like_pattern(&root_string));
+        values.push(root_string);
     }
+    let sql = if conditions.is_empty() {
+        "DELETE FROM metadata_index".to_string()
+    } else {
+        format!(
+            "DELETE FROM metadata_index WHERE NOT ({})",
+            conditions.join(" OR ")
+        )
+    };
+    pruned += conn.execute(&sql, rusqlite::params_from_iter(values))? as u64;
 
     Ok(pruned)
 }
 
+fn prune_stale_subtree(conn: &Connection, root: &Path, run_started_at: &str) -> Result<u64> {
+    let root_string = root.to_string_lossy().into_owned();
+    let pattern = child_like_pattern(&root_string);
+    let affected = conn.execute(
+        "DELETE FROM metadata_index
+         WHERE (path = ?1 OR path LIKE ?2 ESCAPE '\\') AND indexed_at < ?3",
+        params![root_string, pattern, run_started_at],
+    )?;
+    Ok(affected as u64)
+}
+
 fn child_like_pattern(root: &str) -> String {
     let separator = std::path::MAIN_SEPARATOR;
     let mut pattern = escape_like(root);
@@ -622,6 +1862,15 @@ fn child_like_pattern(root: &str) -> String {
     pattern
 }
 
+fn child_prefix(path: &str) -> String {
+    let separator = std::path::MAIN_SEPARATOR;
+    let mut prefix = path.to_string();
+    if !prefix.ends_with(separator) {
+        prefix.push(separator);
+    }
+    prefix
+}
+
 fn escape_like(value: &str) -> String {
     value
         .chars()
@@ -633,6 +1882,60 @@ fn escape_like(value: &str) -> String {
         .collect()
 }
 
+fn load_entries_per_second(conn: &Connection) -> Result<Option<u32>> {
+    let value = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [ENTRIES_PER_SECOND_SETTING],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+    Ok(value
+        .and_then(|value| value.parse::<u32>().ok())
+        .filter(|limit| *limit > 0))
+}
+
+/// Persists the entries-per-second limit and applies it to a running index.
+pub fn set_entries_per_second(conn: &Connection, entries_per_second: Option<u32>) -> Result<()> {
+    let limit = entries_per_second.filter(|limit| *limit > 0);
+    match limit {
+        Some(limit) => conn.execute(
+            "INSERT INTO settings (key, value, updated_at)
+             VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+             ON CONFLICT(key) DO UPDATE SET
+                value = excluded.value,
+                updated_at = excluded.updated_at",
+            params![ENTRIES_PER_SECOND_SETTING, limit.to_string()],
+        )?,
+        None => conn.execute(
+            "DELETE FROM settings WHERE key = ?1",
+            [ENTRIES_PER_SECOND_SETTING],
+        )?,
+    };
+    shared_index_control().set_entries_per_second(limit);
+    Ok(())
+}
+
+/// Persists the search normalization options and refolds indexed text to
+/// match, so queries folded the new way keep finding existing rows.
+pub fn set_search_normalization(
+    conn: &mut Connection,
+    normalization: SearchNormalization,
+) -> Result<u64> {
+    conn.execute(
+        "INSERT INTO settings (key, value, updated_at)
+         VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+         ON CONFLICT(key) DO UPDATE SET
+            value = excluded.value,
+            updated_at = excluded.updated_at",
+        params![
+            normalization::IGNORE_DIACRITICS_SETTING,
+            normalization.ignore_diacritics.to_string()
+        ],
+    )?;
+    refresh_search_text(conn, normalization)
+}
+
 fn has_initial_index(conn: &Connection) -> Result<bool> {
     let has_initial_index: i64 = conn.query_row(
         "SELECT has_initial_index FROM index_state WHERE id = ?1",
@@ -691,6 +1994,8 @@ fn status_to_db(status: &IndexingStatus) -> &'static str {
         IndexingStatus::InitialBuild => "initial_build",
         IndexingStatus::Reconciling => "reconciling",
         IndexingStatus::Ready => "ready",
+        IndexingStatus::Paused => "paused",
+        IndexingStatus::Cancelled => "cancelled",
         IndexingStatus::Failed => "failed",
     }
 }
@@ -729,7 +2034,10 @@ enum FileCategory {
 
 fn file_category(extension: Option<&str>) -> FileCategory {
     match extension.map(|value| value.to_ascii_lowercase()).as_deref() {
-        Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "heic" | "svg") => FileCategory::Image,
+        Some(
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "heic" | "svg" | "bmp" | "tif" | "tiff"
+            | "ico",
+        ) => FileCategory::Image,
         Some("mov" | "mp4" | "m4v" | "avi" | "mkv" | "webm") => FileCategory::Video,
         Some("mp3" | "wav" | "aac" | "flac" | "m4a" | "ogg") => FileCategory::Audio,
         Some("pdf") => FileCategory::Pdf,
@@ -779,32 +2087,16 @@ mod tests {
     fn default_exclusions_skip_noisy_indexing_dirs() {
         let temp = tempdir().expect("tempdir should exist");
         let home = temp.path();
-
-        assert!(is_default_excluded_path(
-            &home.join("node_modules"),
-            true,
-            Some(home)
-        ));
-        assert!(is_default_excluded_path(
-            &home.join(".git"),
-            true,
-            Some(home)
-        ));
-        assert!(is_default_excluded_path(
-            &home.join("Library"),
-            true,
-            Some(home)
-        ));
-        assert!(!is_default_excluded_path(
-            &home.join("Documents"),
-            true,
-            Some(home)
-        ));
-        assert!(!is_default_excluded_path(
-            &home.join("Cargo.lock"),
-            false,
-            Some(home)
-        ));
+        let database_path = home.join(format!("frogger-rules-{}.sqlite3", Uuid::new_v4()));
+        let conn = persistence::open_database(&database_path).expect("database should open");
+        index_config::ensure_default_configuration(&conn, home).expect("defaults should seed");
+        let rules = IndexRules::load(&conn).expect("rules should load");
+
+        assert!(rules.is_excluded(&home.join("node_modules"), true));
+        assert!(rules.is_excluded(&home.join(".git"), true));
+        assert!(rules.is_excluded(&home.join("Library"), true));
+        assert!(!rules.is_excluded(&home.join("Documents"), true));
+        assert!(!rules.is_excluded(&home.join("Cargo.lock"), false));
     }
 
     #[test]
@@ -878,6 +2170,504 @@ mod tests {
         assert!(indexed_paths.contains(&fresh.to_string_lossy().into_owned()));
     }
 
+    #[test]
+    fn configured_roots_and_exclusions_apply_to_runs_and_pruning() {
+        let temp = tempdir().expect("tempdir should exist");
+        let home = temp.path().join("home");
+        let drive = temp.path().join("drive");
+        std::fs::create_dir_all(home.join("build")).expect("build dir should be created");
+        std::fs::create_dir_all(&drive).expect("drive should be created");
+        std::fs::write(home.join("build/output.txt"), "").expect("output should write");
+        std::fs::write(home.join("server.log"), "").expect("log should write");
+        std::fs::write(drive.join("movie.mkv"), "").expect("movie should write");
+        let database_path = temp
+            .path()
+            .join(format!("frogger-config-{}.sqlite3", Uuid::new_v4()));
+        let conn = persistence::open_database(&database_path).expect("database should open");
+        index_config::ensure_default_configuration(&conn, &home).expect("defaults should seed");
+        index_config::add_index_root(&conn, &drive.to_string_lossy()).expect("drive should add");
+        let roots = index_config::load_index_roots(&conn).expect("roots should load");
+        run_metadata_index(&database_path, roots, |_| {}).expect("first index should run");
+
+        let paths = indexed_paths(&conn);
+        assert!(paths.contains(&drive.join("movie.mkv").to_string_lossy().into_owned()));
+        assert!(paths.contains(&home.join("server.log").to_string_lossy().into_owned()));
+        assert!(!paths.iter().any(|path| path.contains("build")));
+
+        let configuration = index_config::add_index_exclusion(
+            &conn,
+            crate::models::IndexExclusionKind::Glob,
+            "*.log",
+        )
+        .expect("log exclusion should add");
+        let build = configuration
+            .exclusions
+            .iter()
+            .find(|exclusion| exclusion.pattern == "build/")
+            .expect("build should be excluded by default");
+        index_config::remove_index_exclusion(&conn, &build.id).expect("build should be allowed");
+        index_config::remove_index_root(&conn, &drive.to_string_lossy())
+            .expect("drive should be removed");
+        let roots = index_config::load_index_roots(&conn).expect("roots should load");
+        let summary =
+            run_metadata_index(&database_path, roots, |_| {}).expect("second index should run");
+
+        assert!(summary.pruned_item_count >= 3);
+        let paths = indexed_paths(&conn);
+        assert!(paths.contains(&home.join("build/output.txt").to_string_lossy().into_owned()));
+        assert!(!paths.iter().any(|path| path.ends_with(".log")));
+        assert!(!paths
+            .iter()
+            .any(|path| path.starts_with(&*drive.to_string_lossy())));
+    }
+
+    #[test]
+    fn new_roots_and_removed_exclusions_are_covered_only_after_a_run() {
+        let temp = tempdir().expect("tempdir should exist");
+        let home = temp.path().join("home");
+        let drive = temp.path().join("drive");
+        std::fs::create_dir_all(home.join("build")).expect("build dir should be created");
+        std::fs::create_dir_all(&drive).expect("drive should be created");
+        let database_path = temp
+            .path()
+            .join(format!("frogger-coverage-{}.sqlite3", Uuid::new_v4()));
+        let conn = persistence::open_database(&database_path).expect("database should open");
+        index_config::ensure_default_configuration(&conn, &home).expect("defaults should seed");
+        let roots = index_config::load_index_roots(&conn).expect("roots should load");
+        run_metadata_index(&database_path, roots, |_| {}).expect("first index should run");
+        assert!(covers_folder(&conn, &home).expect("coverage should load"));
+
+        index_config::add_index_root(&conn, &drive.to_string_lossy()).expect("drive should add");
+        assert!(!covers_folder(&conn, &drive).expect("coverage should load"));
+        assert!(covers_folder(&conn, &home).expect("coverage should load"));
+
+        let build = index_config::load_configuration(&conn)
+            .expect("configuration should load")
+            .exclusions
+            .into_iter()
+            .find(|exclusion| exclusion.pattern == "build/")
+            .expect("build should be excluded by default");
+        index_config::remove_index_exclusion(&conn, &build.id).expect("build should be allowed");
+        assert!(!covers_folder(&conn, &home.join("build")).expect("coverage should load"));
+
+        let roots = index_config::load_index_roots(&conn).expect("roots should load");
+        run_metadata_index(&database_path, roots, |_| {}).expect("second index should run");
+        assert!(covers_folder(&conn, &drive).expect("coverage should load"));
+        assert!(covers_folder(&conn, &home.join("build")).expect("coverage should load"));
+    }
+
+    #[test]
+    fn resume_point_keeps_only_outermost_completed_dirs() {
+        let root = Path::new("/home");
+        let mut resume = ResumePoint::default();
+
+        resume.complete_dir(&root.join("a/b"));
+        resume.complete_dir(&root.join("a/c"));
+        resume.complete_dir(&root.join("ab"));
+        resume.complete_dir(&root.join("a"));
+
+        assert_eq!(
+            resume.completed_dirs,
+            BTreeSet::from([
+                root.join("a").to_string_lossy().into_owned(),
+                root.join("ab").to_string_lossy().into_owned(),
+            ])
+        );
+    }
+
+    #[test]
+    fn interrupted_initial_build_resumes_after_completed_dirs() {
+        let temp = tempdir().expect("tempdir should exist");
+        let root = temp.path().join("home");
+        std::fs::create_dir_all(root.join("done")).expect("done dir should be created");
+        std::fs::create_dir_all(root.join("todo")).expect("todo dir should be created");
+        std::fs::write(root.join("done/old.txt"), "old").expect("old file should write");
+        let database_path = temp
+            .path()
+            .join(format!("frogger-resume-{}.sqlite3", Uuid::new_v4()));
+        let mut conn = persistence::open_database(&database_path).expect("database should open");
+
+        // Simulate a build that wrote `done` and then stopped.
+        let started_state =
+            mark_run_started(&mut conn, std::slice::from_ref(&root)).expect("run should start");
+        assert_eq!(started_state.status, IndexingStatus::InitialBuild);
+        let mut resume = load_resume_point(&conn)
+            .expect("resume point should load")
+            .expect("initial build should record a resume point");
+        let mut batch = vec![
+            metadata_row_from_path(&root.join("done"), SearchNormalization::default())
+                .expect("row should build")
+                .expect("row should exist"),
+            metadata_row_from_path(&root.join("done/old.txt"), SearchNormalization::default())
+                .expect("row should build")
+                .expect("row should exist"),
+        ];
+        flush_batch(&mut conn, &mut batch, &resume.run_started_at).expect("batch should flush");
+        resume.current_root = Some(root.to_string_lossy().into_owned());
+        resume.complete_dir(&root.join("done"));
+        mark_run_progress(
+            &mut conn,
+            IndexingStatus::InitialBuild,
+            true,
+            std::slice::from_ref(&root),
+            &IndexingCounters::default(),
+            Some(&resume),
+            "Indexing…",
+        )
+        .expect("progress should save");
+
+        std::fs::write(root.join("done/late.txt"), "late").expect("late file should write");
+        std::fs::write(root.join("todo/next.txt"), "next").expect("next file should write");
+        let mut states = Vec::new();
+        let summary = run_metadata_index(&database_path, vec![root.clone()], |state| {
+            states.push(state)
+        })
+        .expect("resumed index should run");
+
+        assert_eq!(states[0].status, IndexingStatus::InitialBuild);
+        assert_eq!(states[0].message.as_deref(), Some("Resuming index…"));
+        assert_eq!(summary.dirs_visited, 2);
+        let paths = indexed_paths(&conn);
+        assert!(paths.contains(&root.join("done/old.txt").to_string_lossy().into_owned()));
+        assert!(paths.contains(&root.join("todo/next.txt").to_string_lossy().into_owned()));
+        assert!(!paths.contains(&root.join("done/late.txt").to_string_lossy().into_owned()));
+        assert!(load_resume_point(&conn)
+            .expect("resume point should load")
+            .is_none());
+
+        // A finished index reconciles from scratch on the next launch.
+        run_metadata_index(&database_path, vec![root.clone()], |_| {})
+            .expect("reconcile should run");
+        assert!(indexed_paths(&conn)
+            .contains(&root.join("done/late.txt").to_string_lossy().into_owned()));
+    }
+
+    #[test]
+    fn resume_after_cancel_restarts_once_the_cancelled_run_stops() {
+        let control = IndexControl::default();
+        assert!(control.resume().is_err());
+
+        control.begin();
+        control.pause().expect("running index should pause");
+        control.cancel().expect("running index should cancel");
+        control
+            .resume()
+            .expect("resume should be queued while the run winds down");
+        assert!(!control.wait_while_paused());
+
+        assert!(control.finish());
+        let state = control.snapshot();
+        assert!(state.active);
+        assert!(!state.cancelled);
+        assert!(!state.paused);
+
+        control.cancel().expect("restarted index should cancel");
+        assert!(!control.finish());
+        assert!(!control.snapshot().active);
+        assert!(control.resume().is_err());
+    }
+
+    #[test]
+    fn paused_index_reports_paused_state_until_resumed() {
+        let temp = tempdir().expect("tempdir should exist");
+        let root = temp.path().join("home");
+        std::fs::create_dir_all(&root).expect("root should be created");
+        for index in 0..5 {
+            std::fs::write(root.join(format!("file-{index}.txt")), "").expect("file should write");
+        }
+        let database_path = temp
+            .path()
+            .join(format!("frogger-pause-{}.sqlite3", Uuid::new_v4()));
+        let control = IndexControl::default();
+        assert_eq!(
+            control
+                .pause()
+                .expect_err("idle index should not pause")
+                .code,
+            "unavailable"
+        );
+        control.begin();
+        control.pause().expect("running index should pause");
+
+        let (sender, states) = mpsc::channel();
+        let summary = thread::scope(|scope| {
+            let (database_path, control) = (&database_path, &control);
+            let run = scope.spawn(move || {
+                run_metadata_index_with_control(database_path, vec![root], control, |state| {
+                    sender.send(state).ok();
+                })
+            });
+            wait_for_status(&states, IndexingStatus::Paused);
+            let conn = persistence::open_database(database_path).expect("database should open");
+            let stored: String = conn
+                .query_row(
+                    "SELECT status FROM index_state WHERE id = 'metadata'",
+                    [],
+                    |row| row.get(0),
+                )
+                .expect("state should load");
+            assert_eq!(stored, "paused");
+
+            control.resume().expect("paused index should resume");
+            run.join().expect("index thread should finish")
+        })
+        .expect("index should finish");
+
+        assert!(!summary.cancelled);
+        wait_for_status(&states, IndexingStatus::Ready);
+    }
+
+    #[test]
+    fn cancelled_index_stops_without_pruning_and_keeps_resume_point() {
+        let temp = tempdir().expect("tempdir should exist");
+        let root = temp.path().join("home");
+        std::fs::create_dir_all(&root).expect("root should be created");
+        std::fs::write(root.join("file.txt"), "").expect("file should write");
+        let database_path = temp
+            .path()
+            .join(format!("frogger-cancel-{}.sqlite3", Uuid::new_v4()));
+        let control = IndexControl::default();
+        control.begin();
+        control.cancel().expect("running index should cancel");
+
+        let mut states = Vec::new();
+        let summary = run_metadata_index_with_control(
+            &database_path,
+            vec![root.clone()],
+            &control,
+            |state| states.push(state),
+        )
+        .expect("cancelled index should return");
+
+        assert!(summary.cancelled);
+        assert_eq!(
+            states.last().map(|state| state.status.clone()),
+            Some(IndexingStatus::Cancelled)
+        );
+        let conn = persistence::open_database(&database_path).expect("database should open");
+        assert!(load_resume_point(&conn)
+            .expect("resume point should load")
+            .is_some());
+    }
+
+    #[test]
+    fn throttle_limits_entries_per_second() {
+        let mut throttle = Throttle::new();
+        let started = Instant::now();
+        for _ in 0..5 {
+            throttle.pace(Some(50));
+        }
+        assert!(started.elapsed() >= Duration::from_millis(90));
+
+        let unthrottled = Instant::now();
+        for _ in 0..1_000 {
+            throttle.pace(None);
+        }
+        assert!(unthrottled.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn index_changes_upsert_and_remove_only_affected_rows() {
+        let temp = tempdir().expect("tempdir should exist");
+        let root = temp.path().join("home");
+        std::fs::create_dir_all(root.join("docs")).expect("docs should be created");
+        std::fs::write(root.join("docs/old.txt"), "old").expect("old file should write");
+        std::fs::write(root.join("keep.txt"), "keep").expect("kept file should write");
+        let database_path = temp
+            .path()
+            .join(format!("frogger-changes-{}.sqlite3", Uuid::new_v4()));
+        let roots = vec![root.clone()];
+        run_metadata_index(&database_path, roots.clone(), |_| {}).expect("index should run");
+
+        std::fs::remove_dir_all(root.join("docs")).expect("docs should be removed");
+        std::fs::write(root.join("new.md"), "new").expect("new file should write");
+        std::fs::create_dir_all(root.join("node_modules")).expect("excluded dir should exist");
+        std::fs::write(root.join("node_modules/skip.js"), "").expect("skip file should write");
+        let mut changes = IndexChanges::default();
+        for path in ["docs", "new.md", "node_modules/skip.js"] {
+            changes.paths.insert(root.join(path));
+        }
+        changes.paths.insert(temp.path().join("outside.txt"));
+
+        let mut conn = persistence::open_database(&database_path).expect("database should open");
+        let summary =
+            apply_index_changes(&mut conn, &roots, &changes).expect("changes should apply");
+
+        assert_eq!(summary.upserted_item_count, 1);
+        assert_eq!(summary.removed_item_count, 2);
+        let indexed_paths = indexed_paths(&conn);
+        assert!(indexed_paths.contains(&root.join("new.md").to_string_lossy().into_owned()));
+        assert!(indexed_paths.contains(&root.join("keep.txt").to_string_lossy().into_owned()));
+        assert!(!indexed_paths.iter().any(|path| path.contains("docs")));
+        assert!(!indexed_paths
+            .iter()
+            .any(|path| path.contains("node_modules")));
+    }
+
+    #[test]
+    fn index_changes_rescan_new_folders_and_overflowed_subtrees() {
+        let temp = tempdir().expect("tempdir should exist");
+        let root = temp.path().join("home");
+        std::fs::create_dir_all(root.join("projects")).expect("projects should be created");
+        std::fs::write(root.join("projects/stale.rs"), "").expect("stale file should write");
+        std::fs::write(root.join("outside-rescan.txt"), "").expect("file should write");
+        let database_path = temp
+            .path()
+            .join(format!("frogger-rescan-{}.sqlite3", Uuid::new_v4()));
+        let roots = vec![root.clone()];
+        run_metadata_index(&database_path, roots.clone(), |_| {}).expect("index should run");
+
+        // A folder moved in reports one event for itself, not its contents.
+        std::fs::create_dir_all(root.join("moved/inner")).expect("moved tree should exist");
+        std::fs::write(root.join("moved/inner/file.txt"), "").expect("moved file should write");
+        std::fs::remove_file(root.join("projects/stale.rs")).expect("stale file should go");
+        std::fs::write(root.join("projects/fresh.rs"), "").expect("fresh file should write");
+        std::fs::remove_file(root.join("outside-rescan.txt")).expect("file should be removed");
+
+        let mut changes = IndexChanges::default();
+        changes.paths.insert(root.join("moved"));
+        changes.record(
+            &Event::new(EventKind::Other)
+                .add_path(root.join("projects"))
+                .set_flag(notify::event::Flag::Rescan),
+            &roots,
+        );
+        assert_eq!(changes.rescans, BTreeSet::from([root.join("projects")]));
+
+        let mut conn = persistence::open_database(&database_path).expect("database should open");
+        apply_index_changes(&mut conn, &roots, &changes).expect("changes should apply");
+
+        let paths = indexed_paths(&conn);
+        for expected in [
+            "moved",
+            "moved/inner",
+            "moved/inner/file.txt",
+            "projects/fresh.rs",
+            "outside-rescan.txt",
+        ] {
+            assert!(
+                paths.contains(&root.join(expected).to_string_lossy().into_owned()),
+                "{expected} should be indexed"
+            );
+        }
+        assert!(!paths.contains(
+            &root
+                .join("projects/stale.rs")
+                .to_string_lossy()
+                .into_owned()
+        ));
+
+        let mut overflow = IndexChanges::default();
+        overflow.record(
+            &Event::new(EventKind::Other).set_flag(notify::event::Flag::Rescan),
+            &roots,
+        );
+        assert_eq!(overflow.rescans, BTreeSet::from([root.clone()]));
+        apply_index_changes(&mut conn, &roots, &overflow).expect("root rescan should apply");
+        assert!(!indexed_paths(&conn).contains(
+            &root
+                .join("outside-rescan.txt")
+                .to_string_lossy()
+                .into_owned()
+        ));
+    }
+
+    #[test]
+    fn index_watcher_skips_excluded_folders_and_follows_new_ones() {
+        let temp = tempdir().expect("tempdir should exist");
+        let root = std::fs::canonicalize(temp.path())
+            .expect("tempdir should resolve")
+            .join("home");
+        std::fs::create_dir_all(root.join("src")).expect("src dir should be created");
+        std::fs::create_dir_all(root.join("node_modules/pkg"))
+            .expect("node_modules should be created");
+        let roots = vec![root.clone()];
+        let database_path = temp.path().join("index.sqlite3");
+        let conn = persistence::open_database(&database_path).expect("database should migrate");
+        let rules = IndexRules::load(&conn).expect("rules should load");
+        let (mut watcher, events) =
+            IndexWatcher::start(&roots, rules, WATCH_EVENT_CAPACITY).expect("watcher should start");
+        // The full run registers the per-folder watches as it walks.
+        run_metadata_index_watched(
+            &database_path,
+            roots.clone(),
+            &IndexControl::default(),
+            Some(&mut watcher),
+            |_| {},
+        )
+        .expect("index should build");
+        collect_events(&events, &roots);
+
+        std::fs::write(root.join("node_modules/pkg/index.js"), "skip")
+            .expect("dependency file should write");
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").expect("source should write");
+        let changes = collect_events(&events, &roots);
+        assert!(changes.paths.contains(&root.join("src/main.rs")));
+        assert!(!changes
+            .paths
+            .iter()
+            .any(|path| path.starts_with(root.join("node_modules"))));
+
+        std::fs::create_dir(root.join("docs")).expect("docs dir should be created");
+        let changes = collect_events(&events, &roots);
+        assert!(changes.created.contains(&root.join("docs")));
+        watcher.watch_new_folders(&changes);
+        std::fs::write(root.join("docs/notes.md"), "notes").expect("notes should write");
+        assert!(collect_events(&events, &roots)
+            .paths
+            .contains(&root.join("docs/notes.md")));
+    }
+
+    #[test]
+    fn index_watcher_rescans_roots_whose_events_overflowed() {
+        let temp = tempdir().expect("tempdir should exist");
+        let root = std::fs::canonicalize(temp.path())
+            .expect("tempdir should resolve")
+            .join("home");
+        std::fs::create_dir_all(&root).expect("root should be created");
+        let roots = vec![root.clone()];
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        let rules = IndexRules::load(&conn).expect("rules should load");
+        let (mut watcher, events) =
+            IndexWatcher::start(&roots, rules, 1).expect("watcher should start");
+        watcher.watch_tree(&root);
+
+        for index in 0..20 {
+            std::fs::write(root.join(format!("file-{index}.txt")), "x").expect("file should write");
+        }
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut overflowed = BTreeSet::new();
+        while overflowed.is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+            overflowed = watcher.take_overflowed();
+        }
+        assert_eq!(overflowed, BTreeSet::from([root.clone()]));
+        assert!(events.try_recv().is_ok());
+        assert!(watcher.take_overflowed().is_empty());
+    }
+
+    fn collect_events(
+        events: &mpsc::Receiver<notify::Result<Event>>,
+        roots: &[PathBuf],
+    ) -> IndexChanges {
+        let mut changes = IndexChanges::default();
+        while let Ok(Ok(event)) = events.recv_timeout(Duration::from_millis(500)) {
+            changes.record(&event, roots);
+        }
+        changes
+    }
+
+    fn wait_for_status(states: &mpsc::Receiver<IndexingState>, status: IndexingStatus) {
+        loop {
+            let state = states
+                .recv_timeout(Duration::from_secs(10))
+                .expect("index should report its state");
+            if state.status == status {
+                return;
+            }
+        }
+    }
+
     fn indexed_paths(conn: &Connection) -> Vec<String> {
         let mut stmt = conn
             .prepare("SELECT path FROM metadata_index ORDER BY path")
@@ -0,0 +1,909 @@
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::errors::CommandError;
+use crate::models::{FileOperationKind, OperationActivity};
+use crate::operations;
+use crate::trash_bin;
+
+/// How many completed operations are kept for undo.
+const JOURNAL_LIMIT: i64 = 100;
+
+/// One item touched by a journaled operation.
+///
+/// `source` is where the item was before the operation and `target` is where
+/// it ended up. New folders have no source and trashed items have no target.
+/// `replaced` is where the item that used to be at `target` is held when the
+/// operation replaced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalStep {
+    pub source: Option<PathBuf>,
+    pub target: Option<PathBuf>,
+    pub replaced: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct StoredStep {
+    source: Option<PathBuf>,
+    target: Option<PathBuf>,
+    /// Fingerprint of the item where it currently lives, used to detect
+    /// changes made outside Frogger before the step is replayed.
+    fingerprint: Option<String>,
+    /// Set once an interrupted replay has applied this step, so retrying
+    /// picks up with the steps that are left.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    replayed: bool,
+    /// Holding location of the item this step replaced at `target`. Undo
+    /// moves it back and redo sets it aside again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    replaced: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+struct JournalEntry {
+    id: i64,
+    operation: FileOperationKind,
+    steps: Vec<StoredStep>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+/// Records a completed operation so it can be undone.
+///
+/// Steps that left an item where it was are dropped, and recording a new
+/// operation discards anything that could still have been redone.
+pub fn record(
+    conn: &Connection,
+    operation: FileOperationKind,
+    steps: &[JournalStep],
+) -> Result<()> {
+    let operation_value = operation_to_db(&operation)
+        .ok_or_else(|| anyhow!("{operation:?} operations cannot be journaled"))?;
+    let stored = steps
+        .iter()
+        .filter(|step| step.source != step.target)
+        .map(|step| StoredStep {
+            source: step.source.clone(),
+            target: step.target.clone(),
+            fingerprint: step.target.as_deref().and_then(fingerprint),
+            replayed: false,
+            replaced: step.replaced.clone(),
+        })
+        .collect::<Vec<_>>();
+    if stored.is_empty() {
+        return Ok(());
+    }
+
+    forget_entries(conn, "state = 'undone'", [])?;
+    conn.execute(
+        "INSERT INTO operation_journal (operation, state, steps_json) VALUES (?1, 'done', ?2)",
+        params![operation_value, serde_json::to_string(&stored)?],
+    )?;
+    forget_entries(
+        conn,
+        "id <= (SELECT MAX(id) FROM operation_journal) - ?1",
+        [JOURNAL_LIMIT],
+    )?;
+    Ok(())
+}
+
+/// Where a replaced item is held so undo can put it back: a folder next to
+/// the journal database, which keeps it out of the user's folders.
+pub(crate) fn holding_path(database_path: &Path, name: &std::ffi::OsStr) -> PathBuf {
+    database_path
+        .with_extension("replaced")
+        .join(Uuid::new_v4().simple().to_string())
+        .join(name)
+}
+
+/// Deletes the journal entries matching `condition`, along with the replaced
+/// items they were holding. Undone entries already put theirs back.
+fn forget_entries(conn: &Connection, condition: &str, params: impl rusqlite::Params) -> Result<()> {
+    let held = {
+        let mut statement = conn.prepare(&format!(
+            "DELETE FROM operation_journal WHERE {condition} RETURNING steps_json"
+        ))?;
+        let rows = statement.query_map(params, |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    for steps_json in held {
+        let steps = serde_json::from_str::<Vec<StoredStep>>(&steps_json).unwrap_or_default();
+        for replaced in steps.iter().filter_map(|step| step.replaced.as_deref()) {
+            release_held(replaced);
+        }
+    }
+    Ok(())
+}
+
+/// Deletes a held item, if it is still there, and its holding folder.
+fn release_held(replaced: &Path) {
+    if replaced.symlink_metadata().is_ok() {
+        operations::remove_recursively(replaced).ok();
+    }
+    if let Some(holder) = replaced.parent() {
+        std::fs::remove_dir(holder).ok();
+    }
+}
+
+/// Reverses the most recent operation that has not been undone yet.
+pub fn undo(conn: &Connection) -> Result<OperationActivity, CommandError> {
+    let entry = load_entry(
+        conn,
+        "SELECT id, operation, steps_json FROM operation_journal
+         WHERE state = 'done' ORDER BY id DESC LIMIT 1",
+    )?
+    .ok_or_else(|| CommandError::unavailable("There is nothing to undo.", None))?;
+    replay(conn, entry, Direction::Undo)
+}
+
+/// Repeats the operation that was undone most recently.
+pub fn redo(conn: &Connection) -> Result<OperationActivity, CommandError> {
+    let entry = load_entry(
+        conn,
+        "SELECT id, operation, steps_json FROM operation_journal
+         WHERE state = 'undone' ORDER BY id ASC LIMIT 1",
+    )?
+    .ok_or_else(|| CommandError::unavailable("There is nothing to redo.", None))?;
+    replay(conn, entry, Direction::Redo)
+}
+
+fn load_entry(conn: &Connection, sql: &str) -> Result<Option<JournalEntry>, CommandError> {
+    let row = conn
+        .query_row(sql, [], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .optional()
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)?;
+    let Some((id, operation, steps_json)) = row else {
+        return Ok(None);
+    };
+
+    let operation = operation_from_db(&operation).ok_or_else(|| {
+        CommandError::internal(format!("unknown journaled operation {operation}"))
+    })?;
+    let steps = serde_json::from_str(&steps_json)
+        .map_err(|error| CommandError::internal(error.to_string()))?;
+    Ok(Some(JournalEntry {
+        id,
+        operation,
+        steps,
+    }))
+}
+
+/// Checks every step before touching anything, then replays the steps. Undo
+/// runs the steps backwards so nested items are restored before their parents.
+///
+/// If a step fails, the steps applied so far are marked in the journal and the
+/// entry keeps its state, so retrying continues where the replay stopped.
+fn replay(
+    conn: &Connection,
+    mut entry: JournalEntry,
+    direction: Direction,
+) -> Result<OperationActivity, CommandError> {
+    for step in entry.steps.iter().filter(|step| !step.replayed) {
+        check_step(&entry.operation, step, direction)?;
+    }
+
+    let order = match direction {
+        Direction::Undo => (0..entry.steps.len()).rev().collect::<Vec<_>>(),
+        Direction::Redo => (0..entry.steps.len()).collect(),
+    };
+    let current_state = match direction {
+        Direction::Undo => "done",
+        Direction::Redo => "undone",
+    };
+    for index in order {
+        let step = &mut entry.steps[index];
+        if step.replayed {
+            continue;
+        }
+        if let Err(error) = apply_step(conn, &entry.operation, step, direction) {
+            save_entry(conn, &entry, current_state)?;
+            return Err(error);
+        }
+        step.fingerprint =
+            current_location(&entry.operation, step, direction).and_then(fingerprint);
+        step.replayed = true;
+    }
+
+    for step in &mut entry.steps {
+        step.replayed = false;
+    }
+    let state = match direction {
+        Direction::Undo => "undone",
+        Direction::Redo => "done",
+    };
+    save_entry(conn, &entry, state)?;
+
+    Ok(replay_activity(&entry, direction))
+}
+
+fn save_entry(conn: &Connection, entry: &JournalEntry, state: &str) -> Result<(), CommandError> {
+    let steps_json = serde_json::to_string(&entry.steps)
+        .map_err(|error| CommandError::internal(error.to_string()))?;
+    conn.execute(
+        "UPDATE operation_journal
+         SET state = ?1,
+             steps_json = ?2,
+             updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+         WHERE id = ?3",
+        params![state, steps_json, entry.id],
+    )
+    .map(|_| ())
+    .map_err(anyhow::Error::from)
+    .map_err(CommandError::from)
+}
+
+fn check_step(
+    operation: &FileOperationKind,
+    step: &StoredStep,
+    direction: Direction,
+) -> Result<(), CommandError> {
+    let source = step.source.as_deref();
+    let target = step.target.as_deref();
+    if let Some(replaced) = step.replaced.as_deref() {
+        let target = required_path(target)?;
+        match direction {
+            Direction::Undo => ensure_exists(replaced, operation, direction)?,
+            Direction::Redo => {
+                ensure_exists(target, operation, direction)?;
+                ensure_free(replaced, None, operation, direction)?;
+            }
+        }
+    }
+    match (direction, operation) {
+        (Direction::Undo, FileOperationKind::MoveToTrash) => {
+            ensure_free(required_path(source)?, None, operation, direction)
+        }
+        (Direction::Undo, FileOperationKind::Rename | FileOperationKind::Move) => {
+            let target = required_path(target)?;
+            ensure_unchanged(target, step, operation, direction)?;
+            ensure_free(required_path(source)?, Some(target), operation, direction)
+        }
+        (Direction::Undo, _) => {
+            ensure_unchanged(required_path(target)?, step, operation, direction)
+        }
+        (Direction::Redo, FileOperationKind::MoveToTrash) => {
+            ensure_unchanged(required_path(source)?, step, operation, direction)
+        }
+        (Direction::Redo, FileOperationKind::Rename | FileOperationKind::Move) => {
+            let source = required_path(source)?;
+            ensure_unchanged(source, step, operation, direction)?;
+            if step.replaced.is_some() {
+                // The replaced item is set aside again before the move.
+                return Ok(());
+            }
+            ensure_free(required_path(target)?, Some(source), operation, direction)
+        }
+        (Direction::Redo, FileOperationKind::Copy) => {
+            let source = required_path(source)?;
+            std::fs::symlink_metadata(source)
+                .map_err(|error| operations::operation_error(source, error))?;
+            if step.replaced.is_some() {
+                return Ok(());
+            }
+            ensure_free(required_path(target)?, None, operation, direction)
+        }
+        (Direction::Redo, _) => ensure_free(required_path(target)?, None, operation, direction),
+    }
+}
+
+fn apply_step(
+    conn: &Connection,
+    operation: &FileOperationKind,
+    step: &StoredStep,
+    direction: Direction,
+) -> Result<(), CommandError> {
+    if *operation == FileOperationKind::MoveToTrash {
+        let source = required_path(step.source.as_deref())?.to_path_buf();
+        return match direction {
+            Direction::Undo => trash_bin::put_back_paths(conn, &[source]).map(|_| ()),
+            Direction::Redo => trash_bin::move_to_trash(conn, &[source]).map(|_| ()),
+        };
+    }
+
+    let target = required_path(step.target.as_deref())?;
+    if let (Direction::Redo, Some(replaced)) = (direction, step.replaced.as_deref()) {
+        set_aside(target, replaced).map_err(|error| operations::operation_error(target, error))?;
+    }
+    let result = match (direction, operation) {
+        (Direction::Undo, FileOperationKind::NewFolder) => std::fs::remove_dir(target),
+        (Direction::Undo, FileOperationKind::Copy) => operations::remove_recursively(target),
+        (Direction::Undo, _) => {
+            let source = required_path(step.source.as_deref())?;
+            // A merged move removes emptied source folders, so recreate them.
+            match source.parent() {
+                Some(parent) => std::fs::create_dir_all(parent),
+                None => Ok(()),
+            }
+            .and_then(|_| operations::relocate_item(target, source))
+        }
+        (Direction::Redo, FileOperationKind::NewFolder) => std::fs::create_dir(target),
+        (Direction::Redo, FileOperationKind::Copy) => {
+            operations::duplicate_item(required_path(step.source.as_deref())?, target)
+        }
+        (Direction::Redo, _) => {
+            operations::relocate_item(required_path(step.source.as_deref())?, target)
+        }
+    };
+    if let Err(error) = result {
+        if let (Direction::Redo, Some(replaced)) = (direction, step.replaced.as_deref()) {
+            // Put the replaced item back so the step can be retried as a whole.
+            operations::relocate_item(replaced, target).ok();
+        }
+        return Err(operations::operation_error(target, error));
+    }
+
+    if let (Direction::Undo, Some(replaced)) = (direction, step.replaced.as_deref()) {
+        operations::relocate_item(replaced, target)
+            .map_err(|error| operations::operation_error(target, error))?;
+        release_held(replaced);
+    }
+    Ok(())
+}
+
+/// Moves the item at `target` into its holding location.
+fn set_aside(target: &Path, replaced: &Path) -> std::io::Result<()> {
+    if let Some(holder) = replaced.parent() {
+        std::fs::create_dir_all(holder)?;
+    }
+    operations::relocate_item(target, replaced)
+}
+
+/// Where the step's item lives once it has been replayed in `direction`.
+fn current_location<'a>(
+    operation: &FileOperationKind,
+    step: &'a StoredStep,
+    direction: Direction,
+) -> Option<&'a Path> {
+    match (direction, operation) {
+        (
+            Direction::Undo,
+            FileOperationKind::Rename | FileOperationKind::Move | FileOperationKind::MoveToTrash,
+        ) => step.source.as_deref(),
+        (Direction::Undo, _) => None,
+        (Direction::Redo, _) => step.target.as_deref(),
+    }
+}
+
+fn ensure_exists(
+    path: &Path,
+    operation: &FileOperationKind,
+    direction: Direction,
+) -> Result<(), CommandError> {
+    if path.symlink_metadata().is_ok() {
+        return Ok(());
+    }
+    Err(CommandError::missing_path(
+        format!(
+            "{} no longer exists, so Frogger can't {} this {}.",
+            operations::item_label(path),
+            direction.verb(),
+            operation_noun(operation)
+        ),
+        Some(path.to_string_lossy().into_owned()),
+    ))
+}
+
+fn ensure_unchanged(
+    path: &Path,
+    step: &StoredStep,
+    operation: &FileOperationKind,
+    direction: Direction,
+) -> Result<(), CommandError> {
+    let current = fingerprint(path);
+    if current.is_none() {
+        return Err(CommandError::missing_path(
+            format!(
+                "{} no longer exists, so Frogger can't {} this {}.",
+                operations::item_label(path),
+                direction.verb(),
+                operation_noun(operation)
+            ),
+            Some(path.to_string_lossy().into_owned()),
+        ));
+    }
+    if current != step.fingerprint {
+        return Err(CommandError::conflict(
+            format!(
+                "{} has changed since then, so Frogger can't {} this {}.",
+                operations::item_label(path),
+                direction.verb(),
+                operation_noun(operation)
+            ),
+            Some(path.to_string_lossy().into_owned()),
+        ));
+    }
+    Ok(())
+}
+
+/// Refuses to replay a step onto a path that is now taken. `moving_from` is
+/// the item about to be renamed into `path`, so a case-only rename does not
+/// count as a clash with itself.
+fn ensure_free(
+    path: &Path,
+    moving_from: Option<&Path>,
+    operation: &FileOperationKind,
+    direction: Direction,
+) -> Result<(), CommandError> {
+    let case_only_rename = moving_from
+        .map(|from| from.to_string_lossy().to_lowercase() == path.to_string_lossy().to_lowercase())
+        .unwrap_or(false);
+    if case_only_rename || path.symlink_metadata().is_err() {
+        return Ok(());
+    }
+
+    Err(CommandError::conflict(
+        format!(
+            "An item named {} is in the way, so Frogger can't {} this {}.",
+            operations::item_label(path),
+            direction.verb(),
+            operation_noun(operation)
+        ),
+        Some(path.to_string_lossy().into_owned()),
+    ))
+}
+
+fn required_path(path: Option<&Path>) -> Result<&Path, CommandError> {
+    path.ok_or_else(|| CommandError::internal("journaled step is missing a path"))
+}
+
+/// Cheap staleness check for an item: size and modification time for files
+/// and the link target for symlinks. File contents are never read, so an edit
+/// that keeps the size and restores the modification time goes unnoticed.
+/// Folders condense the relative path, kind, size and modification time of
+/// everything beneath them into one SHA-256, so an edit anywhere inside
+/// changes the fingerprint. The hash is stored, so it must stay stable across
+/// builds.
+fn fingerprint(path: &Path) -> Option<String> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    if !metadata.is_dir() {
+        return entry_fingerprint(path, &metadata);
+    }
+
+    let mut hasher = Sha256::new();
+    let mut count = 0_u64;
+    for entry in WalkDir::new(path)
+        .min_depth(1)
+        .follow_links(false)
+        .sort_by_file_name()
+    {
+        let entry = entry.ok()?;
+        let metadata = entry.metadata().ok()?;
+        let relative = entry.path().strip_prefix(path).ok()?;
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(entry_fingerprint(entry.path(), &metadata)?.as_bytes());
+        hasher.update([0]);
+        count += 1;
+    }
+    let digest = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    Some(format!("tree:{count}:{digest}"))
+}
+
+fn entry_fingerprint(path: &Path, metadata: &std::fs::Metadata) -> Option<String> {
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        let link_target = std::fs::read_link(path).ok()?;
+        return Some(format!("symlink:{}", link_target.to_string_lossy()));
+    }
+    if file_type.is_dir() {
+        return Some("dir".to_string());
+    }
+
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|value| value.duration_since(UNIX_EPOCH).ok())
+        .map(|value| value.as_nanos())
+        .unwrap_or_default();
+    Some(format!("file:{}:{modified}", metadata.len()))
+}
+
+fn replay_activity(entry: &JournalEntry, direction: Direction) -> OperationActivity {
+    let sources = entry
+        .steps
+        .iter()
+        .filter_map(|step| step.source.clone())
+        .collect::<Vec<_>>();
+    let targets = entry
+        .steps
+        .iter()
+        .filter_map(|step| step.target.clone())
+        .collect::<Vec<_>>();
+    let target_folder = targets
+        .first()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let (primary_path, message) = match (direction, &entry.operation) {
+        (Direction::Undo, FileOperationKind::Rename) => (
+            sources.first().cloned().unwrap_or_default(),
+            format!(
+                "Renamed {} back to {}.",
+                operations::items_label(&targets),
+                operations::items_label(&sources)
+            ),
+        ),
+        (Direction::Undo, FileOperationKind::Move) => (
+            sources.first().cloned().unwrap_or_default(),
+            format!("Moved {} back.", operations::items_label(&sources)),
+        ),
+        (Direction::Undo, FileOperationKind::MoveToTrash) => (
+            sources.first().cloned().unwrap_or_default(),
+            format!("Put back {}.", operations::items_label(&sources)),
+        ),
+        (Direction::Undo, _) => (
+            target_folder,
+            format!("Removed {}.", operations::items_label(&targets)),
+        ),
+        (Direction::Redo, FileOperationKind::NewFolder) => (
+            targets.first().cloned().unwrap_or_default(),
+            format!("Created {}.", operations::items_label(&targets)),
+        ),
+        (Direction::Redo, FileOperationKind::MoveToTrash) => (
+            sources.first().cloned().unwrap_or_default(),
+            format!("Moved {} to the Trash.", operations::items_label(&sources)),
+        ),
+        (Direction::Redo, FileOperationKind::Rename) => (
+            targets.first().cloned().unwrap_or_default(),
+            format!(
+                "Renamed {} to {}.",
+                operations::items_label(&sources),
+                operations::items_label(&targets)
+            ),
+        ),
+        (Direction::Redo, _) => (
+            target_folder.clone(),
+            format!(
+                "{} {} to {}.",
+                if entry.operation == FileOperationKind::Copy {
+                    "Copied"
+                } else {
+                    "Moved"
+                },
+                operations::items_label(&sources),
+                operations::item_label(&target_folder)
+            ),
+        ),
+    };
+
+    operations::succeeded_activity(
+        direction.operation(),
+        &primary_path,
+        message,
+        entry.steps.len() as u64,
+    )
+}
+
+impl Direction {
+    fn verb(self) -> &'static str {
+        match self {
+            Direction::Undo => "undo",
+            Direction::Redo => "redo",
+        }
+    }
+
+    fn operation(self) -> FileOperationKind {
+        match self {
+            Direction::Undo => FileOperationKind::Undo,
+            Direction::Redo => FileOperationKind::Redo,
+        }
+    }
+}
+
+fn operation_noun(operation: &FileOperationKind) -> &'static str {
+    match operation {
+        FileOperationKind::NewFolder => "new folder",
+        FileOperationKind::Rename => "rename",
+        FileOperationKind::MoveToTrash => "move to the Trash",
+        FileOperationKind::Copy => "copy",
+        _ => "move",
+    }
+}
+
+fn operation_to_db(operation: &FileOperationKind) -> Option<&'static str> {
+    match operation {
+        FileOperationKind::NewFolder => Some("new_folder"),
+        FileOperationKind::Rename => Some("rename"),
+        FileOperationKind::MoveToTrash => Some("move_to_trash"),
+        FileOperationKind::Copy => Some("copy"),
+        FileOperationKind::Move => Some("move"),
+        _ => None,
+    }
+}
+
+fn operation_from_db(value: &str) -> Option<FileOperationKind> {
+    match value {
+        "new_folder" => Some(FileOperationKind::NewFolder),
+        "rename" => Some(FileOperationKind::Rename),
+        "move_to_trash" => Some(FileOperationKind::MoveToTrash),
+        "copy" => Some(FileOperationKind::Copy),
+        "move" => Some(FileOperationKind::Move),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence;
+    use tempfile::tempdir;
+
+    fn test_database() -> (Connection, PathBuf) {
+        let path = std::env::temp_dir().join(format!("frogger-journal-{}.sqlite3", Uuid::new_v4()));
+        let conn = persistence::open_database(&path).expect("database should migrate");
+        (conn, path)
+    }
+
+    fn step(source: Option<&Path>, target: Option<&Path>) -> JournalStep {
+        JournalStep {
+            source: source.map(Path::to_path_buf),
+            target: target.map(Path::to_path_buf),
+            replaced: None,
+        }
+    }
+
+    #[test]
+    fn rename_round_trips_through_undo_and_redo() {
+        let temp = tempdir().expect("tempdir should exist");
+        let (conn, database_path) = test_database();
+        let original = temp.path().join("draft.txt");
+        std::fs::write(&original, "draft").expect("file should write");
+        let activity = operations::rename_item(&original, "final.txt").expect("rename should work");
+        let renamed = temp.path().join("final.txt");
+        record(
+            &conn,
+            activity.operation,
+            &[step(Some(&original), Some(&renamed))],
+        )
+        .expect("rename should be journaled");
+
+        let undone = undo(&conn).expect("undo should work");
+        assert_eq!(undone.operation, FileOperationKind::Undo);
+        assert!(original.is_file());
+        assert!(!renamed.exists());
+
+        let redone = redo(&conn).expect("redo should work");
+        assert_eq!(redone.operation, FileOperationKind::Redo);
+        assert!(renamed.is_file());
+        assert!(!original.exists());
+
+        assert!(undo(&conn).is_ok());
+        let nothing = redo(&conn)
+            .and_then(|_| redo(&conn))
+            .expect_err("redo stack should end");
+        assert_eq!(nothing.code, "unavailable");
+
+        std::fs::remove_file(database_path).ok();
+    }
+
+    #[test]
+    fn undo_refuses_when_the_item_changed_or_its_old_location_is_taken() {
+        let temp = tempdir().expect("tempdir should exist");
+        let (conn, database_path) = test_database();
+        let folder = temp.path().join("folder");
+        std::fs::create_dir(&folder).expect("folder should be created");
+        let source = temp.path().join("notes.txt");
+        let target = folder.join("notes.txt");
+        std::fs::write(&target, "notes").expect("file should write");
+        record(
+            &conn,
+            FileOperationKind::Move,
+            &[step(Some(&source), Some(&target))],
+        )
+        .expect("move should be journaled");
+
+        std::fs::write(&source, "someone else").expect("blocking file should write");
+        let blocked = undo(&conn).expect_err("occupied source should block undo");
+        assert_eq!(blocked.code, "conflict");
+        assert_eq!(
+            std::fs::read_to_string(&source).expect("blocking file should read"),
+            "someone else"
+        );
+
+        std::fs::remove_file(&source).expect("blocking file should be removed");
+        std::fs::write(&target, "notes, edited later").expect("file should be edited");
+        let changed = undo(&conn).expect_err("edited item should block undo");
+        assert_eq!(changed.code, "conflict");
+        assert!(target.is_file());
+        assert!(!source.exists());
+
+        std::fs::remove_file(database_path).ok();
+    }
+
+    #[test]
+    fn undo_removes_unchanged_copies_and_new_folders() {
+        let temp = tempdir().expect("tempdir should exist");
+        let (conn, database_path) = test_database();
+        let source = temp.path().join("photo.jpg");
+        let backup = temp.path().join("backup");
+        std::fs::write(&source, "jpeg").expect("file should write");
+        operations::create_folder(temp.path(), Some("backup")).expect("folder should create");
+        record(
+            &conn,
+            FileOperationKind::NewFolder,
+            &[step(None, Some(&backup))],
+        )
+        .expect("new folder should be journaled");
+        let copy = backup.join("photo.jpg");
+        operations::duplicate_item(&source, &copy).expect("copy should work");
+        record(
+            &conn,
+            FileOperationKind::Copy,
+            &[step(Some(&source), Some(&copy))],
+        )
+        .expect("copy should be journaled");
+
+        undo(&conn).expect("copy undo should work");
+        assert!(!copy.exists());
+        assert!(source.is_file());
+
+        std::fs::write(backup.join("kept.txt"), "kept").expect("file should write");
+        let not_empty = undo(&conn).expect_err("non-empty folder should not be removed");
+        assert_eq!(not_empty.code, "conflict");
+        assert!(backup.join("kept.txt").is_file());
+
+        std::fs::remove_file(backup.join("kept.txt")).expect("file should be removed");
+        undo(&conn).expect("new folder undo should work");
+        assert!(!backup.exists());
+
+        redo(&conn).expect("new folder redo should work");
+        redo(&conn).expect("copy redo should work");
+        assert_eq!(
+            std::fs::read_to_string(&copy).expect("copy should read"),
+            "jpeg"
+        );
+
+        std::fs::remove_file(database_path).ok();
+    }
+
+    #[test]
+    fn recording_clears_redo_and_skips_no_op_steps() {
+        let temp = tempdir().expect("tempdir should exist");
+        let (conn, database_path) = test_database();
+        let first = temp.path().join("first");
+        let second = temp.path().join("second");
+        std::fs::create_dir(&first).expect("folder should be created");
+        record(
+            &conn,
+            FileOperationKind::NewFolder,
+            &[step(None, Some(&first))],
+        )
+        .expect("new folder should be journaled");
+        undo(&conn).expect("undo should work");
+
+        std::fs::create_dir(&second).expect("folder should be created");
+        record(
+            &conn,
+            FileOperationKind::NewFolder,
+            &[step(None, Some(&second))],
+        )
+        .expect("new folder should be journaled");
+        record(
+            &conn,
+            FileOperationKind::Move,
+            &[step(Some(&second), Some(&second))],
+        )
+        .expect("in-place move should be ignored");
+
+        let error = redo(&conn).expect_err("new work should clear redo");
+        assert_eq!(error.code, "unavailable");
+        undo(&conn).expect("undo should reverse the second folder");
+        assert!(!second.exists());
+        assert_eq!(
+            undo(&conn).expect_err("journal should be empty").code,
+            "unavailable"
+        );
+
+        std::fs::remove_file(database_path).ok();
+    }
+
+    #[test]
+    fn trash_undo_refuses_when_the_original_location_is_taken() {
+        let temp = tempdir().expect("tempdir should exist");
+        let (conn, database_path) = test_database();
+        let trashed = temp.path().join("old.txt");
+        record(
+            &conn,
+            FileOperationKind::MoveToTrash,
+            &[step(Some(&trashed), None)],
+        )
+        .expect("trash should be journaled");
+
+        std::fs::write(&trashed, "replacement").expect("replacement should write");
+        let error = undo(&conn).expect_err("occupied location should block put back");
+        assert_eq!(error.code, "conflict");
+        assert_eq!(
+            std::fs::read_to_string(&trashed).expect("replacement should read"),
+            "replacement"
+        );
+
+        std::fs::remove_file(database_path).ok();
+    }
+
+    #[test]
+    fn fingerprint_tracks_file_contents_and_folder_children() {
+        let temp = tempdir().expect("tempdir should exist");
+        let file = temp.path().join("a.txt");
+        std::fs::write(&file, "a").expect("file should write");
+        let folder_before = fingerprint(temp.path());
+        let file_before = fingerprint(&file);
+
+        std::fs::write(&file, "ab").expect("file should write");
+        std::fs::write(temp.path().join("b.txt"), "b").expect("file should write");
+
+        assert_ne!(fingerprint(&file), file_before);
+        assert_ne!(fingerprint(temp.path()), folder_before);
+        assert_eq!(fingerprint(&temp.path().join("missing")), None);
+
+        let nested = temp.path().join("nested");
+        std::fs::create_dir(&nested).expect("folder should be created");
+        std::fs::write(nested.join("deep.txt"), "deep").expect("file should write");
+        let tree_before = fingerprint(temp.path());
+        assert_eq!(fingerprint(temp.path()), tree_before);
+        std::fs::write(nested.join("deep.txt"), "deeper").expect("file should write");
+        assert_ne!(fingerprint(temp.path()), tree_before);
+    }
+
+    #[test]
+    fn interrupted_undo_resumes_with_the_remaining_steps() {
+        let temp = tempdir().expect("tempdir should exist");
+        let (conn, database_path) = test_database();
+        let moved_from = temp.path().join("a.txt");
+        let moved_to = temp.path().join("a-moved.txt");
+        std::fs::write(&moved_to, "a").expect("file should write");
+        // Undoing this step needs a folder where a file is sitting.
+        let blocker = temp.path().join("blocker");
+        std::fs::write(&blocker, "in the way").expect("blocker should write");
+        let stuck_from = blocker.join("b.txt");
+        let stuck_to = temp.path().join("b-moved.txt");
+        std::fs::write(&stuck_to, "b").expect("file should write");
+        record(
+            &conn,
+            FileOperationKind::Move,
+            &[
+                step(Some(&stuck_from), Some(&stuck_to)),
+                step(Some(&moved_from), Some(&moved_to)),
+            ],
+        )
+        .expect("move should be journaled");
+
+        undo(&conn).expect_err("blocked step should fail");
+        assert!(moved_from.is_file());
+        assert!(stuck_to.is_file());
+
+        std::fs::remove_file(&blocker).expect("blocker should be removed");
+        undo(&conn).expect("retry should finish the undo");
+        assert!(stuck_from.is_file());
+        assert!(moved_from.is_file());
+
+        redo(&conn).expect("redo should replay every step");
+        assert!(moved_to.is_file());
+        assert!(stuck_to.is_file());
+
+        std::fs::remove_file(database_path).ok();
+    }
+}
@@ -1,8 +1,24 @@
 pub mod commands;
+pub mod content_index;
 pub mod errors;
+pub mod frecency;
+pub mod index_config;
 pub mod indexing;
+pub mod journal;
 pub mod models;
+pub mod normalization;
+pub mod operations;
+pub mod pattern_search;
 pub mod persistence;
+pub mod photo_metadata;
+pub mod preview;
+pub mod search_history;
+pub mod search_query;
+pub mod search_session;
+pub mod syntax;
+pub mod thumbnail_cache;
+pub mod trash_bin;
+pub mod watcher;
 
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
 
@@ -49,21 +65,63 @@ pub fn run() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
+            commands::add_index_exclusion,
+            commands::add_index_root,
             commands::bootstrap_app,
+            commands::cancel_file_operation,
+            commands::cancel_indexing,
+            commands::cancel_search,
             commands::cleanup_thumbnail_cache,
+            commands::clear_search_history,
+            commands::copy_items,
             commands::create_file_manager_window,
+            commands::create_folder,
+            commands::delete_search_history_entry,
+            commands::empty_trash,
             commands::get_folder_view_state,
+            commands::get_index_configuration,
+            commands::get_preview,
+            commands::get_search_page,
+            commands::get_search_suggestions,
             commands::get_sidebar_state,
+            commands::get_text_preview,
             commands::get_thumbnail,
+            commands::get_thumbnail_cache_stats,
             commands::list_directory,
+            commands::list_file_operations,
+            commands::list_search_history,
+            commands::list_trash,
+            commands::move_items,
+            commands::move_items_to_trash,
             commands::open_file_with_default_app,
+            commands::pause_indexing,
             commands::pin_sidebar_folder,
+            commands::put_back_items,
             commands::record_recent_item,
+            commands::record_search_history,
+            commands::record_search_result_open,
+            commands::redo_file_operation,
+            commands::remove_index_exclusion,
+            commands::remove_index_root,
+            commands::remove_smart_folder,
+            commands::rename_item,
+            commands::resolve_file_operation_conflict,
+            commands::resume_indexing,
             commands::save_folder_view_state,
             commands::save_session_state,
+            commands::save_smart_folder,
+            commands::search_in_folder,
             commands::search_metadata,
+            commands::search_pattern,
             commands::set_browser_display_setting,
+            commands::set_content_indexing,
+            commands::set_indexing_throttle,
+            commands::set_photo_metadata_indexing,
+            commands::set_search_ignore_diacritics,
             commands::set_sidebar_section_visibility,
+            commands::set_thumbnail_cache_budget,
+            commands::start_search,
+            commands::undo_file_operation,
             commands::unpin_sidebar_folder
         ])
         .run(tauri::generate_context!())
@@ -51,6 +51,11 @@ pub struct AppSettings {
     pub restore_enabled: bool,
     pub local_only_indexing: bool,
     pub previews_enabled: bool,
+    pub indexing_entries_per_second: Option<u32>,
+    pub search_ignore_diacritics: bool,
+    pub search_content_indexing: bool,
+    pub search_photo_metadata_indexing: bool,
+    pub thumbnail_cache_budget_bytes: u64,
     pub list_column_visibility: BTreeMap<String, bool>,
     pub list_column_widths: BTreeMap<String, f64>,
     pub raw: BTreeMap<String, String>,
@@ -150,6 +155,7 @@ pub struct SidebarState {
     pub recent_items: Vec<SidebarItem>,
     pub favorites: Vec<SidebarItem>,
     pub locations: Vec<SidebarItem>,
+    pub smart_folders: Vec<SidebarItem>,
     pub recents_virtual_folder_id: String,
 }
 
@@ -168,6 +174,7 @@ pub enum SidebarSectionId {
     Recents,
     Favorites,
     Locations,
+    SmartFolders,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -187,6 +194,7 @@ pub enum SidebarItemType {
     Drive,
     CloudFolder,
     Home,
+    SmartFolder,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -270,6 +278,14 @@ pub struct ThumbnailDescriptor {
     pub cache_hit: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailCacheStats {
+    pub entry_count: u64,
+    pub total_bytes: u64,
+    pub budget_bytes: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResult {
@@ -283,6 +299,7 @@ pub struct SearchResult {
     pub modified_at: Option<String>,
     pub rank: i64,
     pub match_reason: SearchMatchReason,
+    pub snippet: Option<SearchSnippet>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -292,6 +309,121 @@ pub enum SearchMatchReason {
     Prefix,
     Substring,
     Fuzzy,
+    Filter,
+    Content,
+    Pattern,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHistoryEntry {
+    pub id: String,
+    pub query: String,
+    pub last_used_at: String,
+    pub use_count: i64,
+}
+
+/// A completion for the search field. `text` replaces the whole query;
+/// `label` is what the suggestion list shows.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSuggestion {
+    pub text: String,
+    pub label: String,
+    pub source: SearchSuggestionSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchSuggestionSource {
+    History,
+    Favorite,
+    Kind,
+    Extension,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchPatternMode {
+    Regex,
+    Glob,
+}
+
+/// Document text around a content match, split so the matched words can be
+/// highlighted without the frontend parsing markers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSnippet {
+    pub segments: Vec<SnippetSegment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetSegment {
+    pub text: String,
+    pub highlighted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSession {
+    pub id: String,
+    pub query: String,
+}
+
+/// Ranked results of a search session starting at `offset`. A session sends a
+/// quick first batch and then, once ranking finishes, a `complete` batch that
+/// replaces it; later pages come from `get_search_page`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResultBatch {
+    pub session_id: String,
+    pub query: String,
+    pub results: Vec<SearchResult>,
+    pub offset: usize,
+    pub total_count: usize,
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResultPage {
+    pub session_id: String,
+    pub results: Vec<SearchResult>,
+    pub total_count: usize,
+    pub next_cursor: Option<String>,
+    pub loading_complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderSearchRequest {
+    pub path: String,
+    pub query: String,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderSearchPage {
+    pub path: String,
+    pub query: String,
+    pub results: Vec<SearchResult>,
+    pub total_count: usize,
+    pub next_cursor: Option<String>,
+    pub loading_complete: bool,
+    pub source: FolderSearchSource,
+    /// False when a live walk stopped at its entry or time budget, so results
+    /// may be missing matches deeper in the folder.
+    pub walk_complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FolderSearchSource {
+    Index,
+    LiveWalk,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -310,9 +442,41 @@ pub enum IndexingStatus {
     InitialBuild,
     Reconciling,
     Ready,
+    Paused,
+    Cancelled,
     Failed,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexConfiguration {
+    pub roots: Vec<IndexRoot>,
+    pub exclusions: Vec<IndexExclusion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexRoot {
+    pub path: String,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexExclusion {
+    pub id: String,
+    pub kind: IndexExclusionKind,
+    pub pattern: String,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IndexExclusionKind {
+    Glob,
+    Path,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct OperationActivity {
@@ -323,6 +487,61 @@ pub struct OperationActivity {
     pub message: String,
     pub progress: Option<OperationProgress>,
     pub recoverable: bool,
+    pub conflict: Option<FileOperationConflict>,
+}
+
+/// An item a paused copy or move job needs an answer for.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOperationConflict {
+    pub source_path: String,
+    pub target_path: String,
+    pub source_is_dir: bool,
+    pub target_is_dir: bool,
+}
+
+/// How a copy or move job handles an item whose name is already taken.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    Replace,
+    Skip,
+    KeepBoth,
+    Merge,
+    #[default]
+    Ask,
+}
+
+/// Debounced changes to one watched folder, emitted on `watcher_update`.
+/// Tabs apply the update whose view flags match their own.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherUpdate {
+    pub path: String,
+    pub hidden_files_visible: bool,
+    pub file_extensions_visible: bool,
+    pub added: Vec<FileEntry>,
+    pub removed: Vec<String>,
+    pub modified: Vec<FileEntry>,
+    pub renamed: Vec<RenamedEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedEntry {
+    pub old_path: String,
+    pub entry: FileEntry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedItem {
+    pub id: String,
+    pub name: String,
+    pub original_path: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    pub trashed_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -337,6 +556,10 @@ pub enum FileOperationKind {
     OpenWith,
     Indexing,
     Preview,
+    Undo,
+    Redo,
+    PutBack,
+    EmptyTrash,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -344,6 +567,7 @@ pub enum FileOperationKind {
 pub enum OperationStatus {
     Queued,
     Running,
+    Paused,
     Succeeded,
     Failed,
     Cancelled,
@@ -367,7 +591,7 @@ pub struct PreviewDescriptor {
     pub metadata: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum PreviewRenderer {
     Image,
@@ -378,6 +602,49 @@ pub enum PreviewRenderer {
     FallbackMetadata,
 }
 
+/// The head of a text file as shown by the text renderer. `line_count` counts
+/// the lines in `content`, not in the whole file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TextPreview {
+    pub path: String,
+    pub content: String,
+    pub encoding: Option<TextEncoding>,
+    pub is_binary: bool,
+    pub line_count: usize,
+    pub truncated: bool,
+    pub language: Option<String>,
+    pub spans: Vec<SyntaxSpan>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+/// A highlighted range of `TextPreview.content`, in UTF-16 code units.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxSpan {
+    pub start: usize,
+    pub end: usize,
+    pub token: SyntaxToken,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SyntaxToken {
+    Comment,
+    Keyword,
+    Number,
+    String,
+    Tag,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct AppCapabilities {
@@ -394,7 +661,9 @@ pub struct EventNames {
     pub directory_listing_progress: String,
     pub indexing_progress: String,
     pub file_operation_progress: String,
+    pub file_operation_conflict: String,
     pub watcher_update: String,
+    pub search_results: String,
     pub settings_changed: String,
     pub activity_failure: String,
 }
@@ -405,7 +674,9 @@ impl Default for EventNames {
             directory_listing_progress: "frogger://directory-listing-progress".to_string(),
             indexing_progress: "frogger://indexing-progress".to_string(),
             file_operation_progress: "frogger://file-operation-progress".to_string(),
+            file_operation_conflict: "frogger://file-operation-conflict".to_string(),
             watcher_update: "frogger://watcher-update".to_string(),
+            search_results: "frogger://search-results".to_string(),
             settings_changed: "frogger://settings-changed".to_string(),
             activity_failure: "frogger://activity-failure".to_string(),
         }
@@ -442,6 +713,11 @@ mod tests {
                 restore_enabled: true,
                 local_only_indexing: true,
                 previews_enabled: true,
+                indexing_entries_per_second: None,
+                search_ignore_diacritics: true,
+                search_content_indexing: false,
+                search_photo_metadata_indexing: false,
+                thumbnail_cache_budget_bytes: 256 * 1024 * 1024,
                 list_column_visibility: BTreeMap::new(),
                 list_column_widths: BTreeMap::new(),
                 raw: BTreeMap::new(),
@@ -452,6 +728,7 @@ mod tests {
                 recent_items: vec![],
                 favorites: vec![],
                 locations: vec![],
+                smart_folders: vec![],
                 recents_virtual_folder_id: "recents".to_string(),
             },
             indexing: IndexingState {
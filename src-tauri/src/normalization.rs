@@ -0,0 +1,137 @@
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+pub const IGNORE_DIACRITICS_SETTING: &str = "search.ignoreDiacritics";
+
+/// How text is folded before search compares it.
+///
+/// Folding applies full Unicode case folding and composes the result, so
+/// decomposed macOS names and composed queries meet. With diacritics ignored,
+/// combining marks are dropped as well and "resume" finds "Résumé". Index rows
+/// and queries must be folded with the same options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchNormalization {
+    pub ignore_diacritics: bool,
+}
+
+impl Default for SearchNormalization {
+    fn default() -> Self {
+        Self {
+            ignore_diacritics: true,
+        }
+    }
+}
+
+impl SearchNormalization {
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let value = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                [IGNORE_DIACRITICS_SETTING],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(Self {
+            ignore_diacritics: value
+                .map_or(Self::default().ignore_diacritics, |value| value == "true"),
+        })
+    }
+
+    pub fn fold(&self, value: &str) -> String {
+        let decomposed = caseless::default_case_fold_str(value)
+            .nfd()
+            .collect::<String>();
+        if self.ignore_diacritics {
+            decomposed
+                .chars()
+                .filter(|character| !is_combining_mark(*character))
+                .nfc()
+                .collect()
+        } else {
+            decomposed.nfc().collect()
+        }
+    }
+}
+
+/// Orders names the way people read them: case and accents only break ties,
+/// so "éclair" sorts beside "eclair" rather than after "zebra".
+pub fn compare_names(left: &str, right: &str) -> std::cmp::Ordering {
+    if left.is_ascii() && right.is_ascii() {
+        return left
+            .bytes()
+            .map(|byte| byte.to_ascii_lowercase())
+            .cmp(right.bytes().map(|byte| byte.to_ascii_lowercase()));
+    }
+    NameSortKey::new(left).cmp(&NameSortKey::new(right))
+}
+
+/// A name folded once so sorts can order it like [`compare_names`] without
+/// folding it again on every comparison.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NameSortKey {
+    loose: String,
+    strict: String,
+}
+
+impl NameSortKey {
+    pub fn new(name: &str) -> Self {
+        if name.is_ascii() {
+            // ASCII folds to its lowercase form and has no accents to drop.
+            let folded = name.to_ascii_lowercase();
+            return Self {
+                loose: folded.clone(),
+                strict: folded,
+            };
+        }
+        Self {
+            loose: SearchNormalization {
+                ignore_diacritics: true,
+            }
+            .fold(name),
+            strict: SearchNormalization {
+                ignore_diacritics: false,
+            }
+            .fold(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn folds_case_and_normalization_forms_together() {
+        let strict = SearchNormalization {
+            ignore_diacritics: false,
+        };
+
+        assert_eq!(strict.fold("ÄBC"), strict.fold("äbc"));
+        assert_eq!(strict.fold("Re\u{301}sume\u{301}"), "résumé");
+        assert_eq!(strict.fold("Straße"), "strasse");
+        assert_ne!(strict.fold("Résumé"), strict.fold("resume"));
+    }
+
+    #[test]
+    fn drops_diacritics_only_when_asked() {
+        let loose = SearchNormalization::default();
+
+        assert_eq!(loose.fold("Résumé"), "resume");
+        assert_eq!(loose.fold("Re\u{301}sume\u{301}"), "resume");
+        assert_eq!(loose.fold("한글"), "한글");
+    }
+
+    #[test]
+    fn compares_names_without_case_or_accents_first() {
+        assert_eq!(compare_names("éclair", "Eclair"), Ordering::Greater);
+        assert_eq!(compare_names("éclair", "zebra"), Ordering::Less);
+        assert_eq!(compare_names("Apple", "apple"), Ordering::Equal);
+        assert_eq!(compare_names("Eclair", "éclair"), Ordering::Less);
+        assert_eq!(compare_names("apple", "Banana"), Ordering::Less);
+        assert!(NameSortKey::new("éclair") > NameSortKey::new("Eclair"));
+        assert!(NameSortKey::new("éclair") < NameSortKey::new("zebra"));
+    }
+}
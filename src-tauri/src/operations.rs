@@ -0,0 +1,1711 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::errors::CommandError;
+use crate::journal::{self, JournalStep};
+use crate::models::{
+    ConflictPolicy, EventNames, FileOperationConflict, FileOperationKind, OperationActivity,
+    OperationProgress, OperationStatus,
+};
+
+const NEW_FOLDER_BASE_NAME: &str = "untitled folder";
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const OPERATION_WORKER_COUNT: usize = 2;
+
+static OPERATION_QUEUE: OnceLock<OperationQueue> = OnceLock::new();
+
+/// Creates a folder inside `parent`.
+///
+/// Without an explicit name this follows Finder and picks the first free
+/// "untitled folder", "untitled folder 2", ... name.
+pub fn create_folder(parent: &Path, name: Option<&str>) -> Result<OperationActivity, CommandError> {
+    let metadata = std::fs::metadata(parent).map_err(|error| operation_error(parent, error))?;
+    if !metadata.is_dir() {
+        return Err(CommandError::invalid_input(
+            "New folders can only be created inside a folder.",
+            Some(parent.to_string_lossy().into_owned()),
+        ));
+    }
+
+    let target = match name {
+        Some(name) => {
+            let name = validate_item_name(name)?;
+            let target = parent.join(name);
+            if target.symlink_metadata().is_ok() {
+                return Err(already_exists_error(&target));
+            }
+            target
+        }
+        None => next_available_name(parent, NEW_FOLDER_BASE_NAME, ""),
+    };
+
+    std::fs::create_dir(&target).map_err(|error| operation_error(&target, error))?;
+
+    Ok(succeeded_activity(
+        FileOperationKind::NewFolder,
+        &target,
+        format!("Created {}.", item_label(&target)),
+        1,
+    ))
+}
+
+/// Renames an item in place. Case-only renames are allowed on
+/// case-insensitive filesystems where the new name already "exists".
+pub fn rename_item(path: &Path, new_name: &str) -> Result<OperationActivity, CommandError> {
+    std::fs::symlink_metadata(path).map_err(|error| operation_error(path, error))?;
+    let new_name = validate_item_name(new_name)?;
+    let parent = path.parent().ok_or_else(|| {
+        CommandError::invalid_input(
+            "This item cannot be renamed.",
+            Some(path.to_string_lossy().into_owned()),
+        )
+    })?;
+    let target = parent.join(new_name);
+    if target == path {
+        return Ok(succeeded_activity(
+            FileOperationKind::Rename,
+            path,
+            format!("{} was left unchanged.", item_label(path)),
+            1,
+        ));
+    }
+
+    let case_only_rename = path
+        .file_name()
+        .and_then(|value| value.to_str())
+        .map(|current| current.to_lowercase() == new_name.to_lowercase())
+        .unwrap_or(false);
+    if !case_only_rename && target.symlink_metadata().is_ok() {
+        return Err(already_exists_error(&target));
+    }
+
+    std::fs::rename(path, &target).map_err(|error| operation_error(path, error))?;
+
+    Ok(succeeded_activity(
+        FileOperationKind::Rename,
+        &target,
+        format!("Renamed {} to {}.", item_label(path), item_label(&target)),
+        1,
+    ))
+}
+
+/// Moves items to the platform trash (Trash, Recycle Bin, or the XDG trash).
+pub fn move_to_trash(paths: &[PathBuf]) -> Result<OperationActivity, CommandError> {
+    let first = require_sources(paths)?;
+    for path in paths {
+        std::fs::symlink_metadata(path).map_err(|error| operation_error(path, error))?;
+    }
+
+    trash::delete_all(paths).map_err(|error| {
+        CommandError::unavailable(
+            "The items could not be moved to the Trash.",
+            Some(error.to_string()),
+        )
+    })?;
+
+    Ok(succeeded_activity(
+        FileOperationKind::MoveToTrash,
+        first,
+        format!("Moved {} to the Trash.", items_label(paths)),
+        paths.len() as u64,
+    ))
+}
+
+/// Returns the app-wide operation queue, starting its workers on first use.
+pub fn shared_queue(app: &AppHandle) -> &'static OperationQueue {
+    OPERATION_QUEUE.get_or_init(|| {
+        let journal_database = app
+            .path()
+            .app_data_dir()
+            .ok()
+            .map(|directory| directory.join("frogger.sqlite3"));
+        let app = app.clone();
+        OperationQueue::new(
+            OPERATION_WORKER_COUNT,
+            journal_database,
+            Arc::new(move |activity| emit_operation_activity(&app, activity)),
+        )
+    })
+}
+
+fn emit_operation_activity(app: &AppHandle, activity: &OperationActivity) {
+    let events = EventNames::default();
+    let _ = app.emit(&events.file_operation_progress, activity.clone());
+    if activity.status == OperationStatus::Paused && activity.conflict.is_some() {
+        let _ = app.emit(&events.file_operation_conflict, activity.clone());
+    }
+    if activity.status == OperationStatus::Failed {
+        let _ = app.emit(&events.activity_failure, activity.clone());
+    }
+}
+
+/// Background engine for copy and move jobs.
+///
+/// Jobs are validated when they are queued, then run on dedicated worker
+/// threads so large trees never block the command thread. Every state change is
+/// reported through the emitter as an [`OperationActivity`]. Name clashes are
+/// settled by the job's [`ConflictPolicy`]; with [`ConflictPolicy::Ask`] the
+/// job pauses with the clash in [`OperationActivity::conflict`] until
+/// [`OperationQueue::resolve_conflict`] answers it. When a journal
+/// database is configured, every item that was transferred is journaled so the
+/// job can be undone, even if the job later failed or was cancelled.
+pub struct OperationQueue {
+    inner: Arc<QueueInner>,
+}
+
+pub type ActivityEmitter = Arc<dyn Fn(&OperationActivity) + Send + Sync>;
+
+struct QueueInner {
+    state: Mutex<QueueState>,
+    wake: Condvar,
+    journal_database: Option<PathBuf>,
+    emit: ActivityEmitter,
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: VecDeque<Arc<OperationJob>>,
+    active: HashMap<String, Arc<OperationJob>>,
+    shutdown: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransferKind {
+    Copy,
+    Move,
+}
+
+struct OperationJob {
+    id: String,
+    kind: TransferKind,
+    sources: Vec<PathBuf>,
+    destination: PathBuf,
+    cancelled: AtomicBool,
+    activity: Mutex<OperationActivity>,
+    conflicts: Mutex<ConflictState>,
+    answered: Condvar,
+}
+
+struct ConflictState {
+    policy: ConflictPolicy,
+    answer: Option<ConflictAnswer>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ConflictAnswer {
+    resolution: ConflictPolicy,
+    apply_to_all: bool,
+}
+
+impl OperationQueue {
+    pub fn new(
+        worker_count: usize,
+        journal_database: Option<PathBuf>,
+        emit: ActivityEmitter,
+    ) -> Self {
+        let inner = Arc::new(QueueInner {
+            state: Mutex::new(QueueState::default()),
+            wake: Condvar::new(),
+            journal_database,
+            emit,
+        });
+
+        for index in 0..worker_count.max(1) {
+            let worker_inner = Arc::clone(&inner);
+            thread::Builder::new()
+                .name(format!("frogger-file-operation-{index}"))
+                .spawn(move || worker_loop(worker_inner))
+                .expect("file operation worker thread should spawn");
+        }
+
+        Self { inner }
+    }
+
+    /// Queues a copy of `sources` into `destination`.
+    pub fn enqueue_copy(
+        &self,
+        sources: Vec<PathBuf>,
+        destination: PathBuf,
+        policy: ConflictPolicy,
+    ) -> Result<OperationActivity, CommandError> {
+        self.enqueue(TransferKind::Copy, sources, destination, policy)
+    }
+
+    /// Queues a move of `sources` into `destination`.
+    pub fn enqueue_move(
+        &self,
+        sources: Vec<PathBuf>,
+        destination: PathBuf,
+        policy: ConflictPolicy,
+    ) -> Result<OperationActivity, CommandError> {
+        self.enqueue(TransferKind::Move, sources, destination, policy)
+    }
+
+    /// Returns the queued and running jobs.
+    pub fn activities(&self) -> Vec<OperationActivity> {
+        let state = self.inner.lock_state();
+        state.active.values().map(|job| job.snapshot()).collect()
+    }
+
+    /// Cancels a job.
+    ///
+    /// Queued jobs are cancelled immediately. Running jobs stop at the next
+    /// chunk boundary, remove the item they were writing, and then report
+    /// [`OperationStatus::Cancelled`] through the emitter.
+    pub fn cancel(&self, id: &str) -> Result<OperationActivity, CommandError> {
+        let mut state = self.inner.lock_state();
+        let job = state.active.get(id).cloned().ok_or_else(|| {
+            CommandError::unavailable(
+                "That file operation has already finished.",
+                Some(id.to_string()),
+            )
+        })?;
+        job.cancelled.store(true, AtomicOrdering::SeqCst);
+        {
+            // Wake a job that is paused on a conflict so it can stop.
+            let _conflicts = job.lock_conflicts();
+            job.answered.notify_all();
+        }
+
+        let queued_position = state.pending.iter().position(|pending| pending.id == id);
+        if let Some(position) = queued_position {
+            state.pending.remove(position);
+            state.active.remove(id);
+            drop(state);
+            let activity = job.update(|activity| {
+                activity.status = OperationStatus::Cancelled;
+                activity.message = format!("Cancelled {}.", job.describe(false));
+            });
+            (self.inner.emit)(&activity);
+            return Ok(activity);
+        }
+
+        drop(state);
+        Ok(job.update(|activity| {
+            activity.message = format!("Cancelling {}\u{2026}", job.describe(false));
+        }))
+    }
+
+    /// Answers the conflict a paused job is waiting on. With `apply_to_all`
+    /// the resolution also settles every later conflict in the same job.
+    pub fn resolve_conflict(
+        &self,
+        id: &str,
+        resolution: ConflictPolicy,
+        apply_to_all: bool,
+    ) -> Result<OperationActivity, CommandError> {
+        if resolution == ConflictPolicy::Ask {
+            return Err(CommandError::invalid_input(
+                "Choose how to handle the item that already exists.",
+                Some(id.to_string()),
+            ));
+        }
+
+        let job = self
+            .inner
+            .lock_state()
+            .active
+            .get(id)
+            .cloned()
+            .ok_or_else(|| {
+                CommandError::unavailable(
+                    "That file operation has already finished.",
+                    Some(id.to_string()),
+                )
+            })?;
+        let activity = job.snapshot();
+        if activity.conflict.is_none() {
+            return Err(CommandError::invalid_input(
+                "That file operation is not waiting for an answer.",
+                Some(id.to_string()),
+            ));
+        }
+
+        job.lock_conflicts().answer = Some(ConflictAnswer {
+            resolution,
+            apply_to_all,
+        });
+        job.answered.notify_all();
+        Ok(activity)
+    }
+
+    fn enqueue(
+        &self,
+        kind: TransferKind,
+        sources: Vec<PathBuf>,
+        destination: PathBuf,
+        policy: ConflictPolicy,
+    ) -> Result<OperationActivity, CommandError> {
+        require_sources(&sources)?;
+        plan_transfer(&sources, &destination)?;
+
+        let total = sources.iter().map(|source| measure_tree(source)).sum();
+        let id = format!("operation-{}", Uuid::new_v4());
+        let job = Arc::new(OperationJob {
+            activity: Mutex::new(OperationActivity {
+                id: id.clone(),
+                operation: kind.operation(),
+                status: OperationStatus::Queued,
+                primary_path: Some(destination.to_string_lossy().into_owned()),
+                message: String::new(),
+                progress: Some(OperationProgress {
+                    completed: 0,
+                    total: Some(total),
+                }),
+                recoverable: false,
+                conflict: None,
+            }),
+            id,
+            kind,
+            sources,
+            destination,
+            cancelled: AtomicBool::new(false),
+            conflicts: Mutex::new(ConflictState {
+                policy,
+                answer: None,
+            }),
+            answered: Condvar::new(),
+        });
+        let activity = job.update(|activity| {
+            activity.message = format!("Waiting to {}\u{2026}", job.verb_phrase());
+        });
+
+        {
+            let mut state = self.inner.lock_state();
+            state.active.insert(job.id.clone(), Arc::clone(&job));
+            state.pending.push_back(job);
+        }
+        (self.inner.emit)(&activity);
+        self.inner.wake.notify_one();
+
+        Ok(activity)
+    }
+}
+
+impl Drop for OperationQueue {
+    fn drop(&mut self) {
+        self.inner.lock_state().shutdown = true;
+        self.inner.wake.notify_all();
+    }
+}
+
+impl QueueInner {
+    fn lock_state(&self) -> MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .expect("file operation queue state should not be poisoned")
+    }
+}
+
+impl TransferKind {
+    fn operation(self) -> FileOperationKind {
+        match self {
+            TransferKind::Copy => FileOperationKind::Copy,
+            TransferKind::Move => FileOperationKind::Move,
+        }
+    }
+}
+
+impl OperationJob {
+    fn snapshot(&self) -> OperationActivity {
+        self.activity
+            .lock()
+            .expect("file operation activity should not be poisoned")
+            .clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut OperationActivity)) -> OperationActivity {
+        let mut activity = self
+            .activity
+            .lock()
+            .expect("file operation activity should not be poisoned");
+        change(&mut activity);
+        activity.clone()
+    }
+
+    fn lock_conflicts(&self) -> MutexGuard<'_, ConflictState> {
+        self.conflicts
+            .lock()
+            .expect("file operation conflict state should not be poisoned")
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(AtomicOrdering::SeqCst)
+    }
+
+    /// "copying 3 items", or "Copying 3 items" when `capitalized`.
+    fn describe(&self, capitalized: bool) -> String {
+        let verb = match (self.kind, capitalized) {
+            (TransferKind::Copy, false) => "copying",
+            (TransferKind::Copy, true) => "Copying",
+            (TransferKind::Move, false) => "moving",
+            (TransferKind::Move, true) => "Moving",
+        };
+        format!("{verb} {}", items_label(&self.sources))
+    }
+
+    fn verb_phrase(&self) -> String {
+        let verb = match self.kind {
+            TransferKind::Copy => "copy",
+            TransferKind::Move => "move",
+        };
+        format!("{verb} {}", items_label(&self.sources))
+    }
+
+    fn completion_message(&self) -> String {
+        let verb = match self.kind {
+            TransferKind::Copy => "Copied",
+            TransferKind::Move => "Moved",
+        };
+        format!(
+            "{verb} {} to {}.",
+            items_label(&self.sources),
+            item_label(&self.destination)
+        )
+    }
+}
+
+fn worker_loop(inner: Arc<QueueInner>) {
+    loop {
+        let job = {
+            let mut state = inner.lock_state();
+            loop {
+                if state.shutdown {
+                    return;
+                }
+                if let Some(job) = state.pending.pop_front() {
+                    break job;
+                }
+                state = inner
+                    .wake
+                    .wait(state)
+                    .expect("file operation queue state should not be poisoned");
+            }
+        };
+
+        run_job(&inner, &job);
+        inner.lock_state().active.remove(&job.id);
+    }
+}
+
+fn run_job(inner: &QueueInner, job: &OperationJob) {
+    let running = job.update(|activity| {
+        activity.status = OperationStatus::Running;
+        activity.message = format!("{}\u{2026}", job.describe(true));
+    });
+    (inner.emit)(&running);
+
+    // Backdate the last emit so the first chunk reports progress right away.
+    let mut transfer = TransferProgress {
+        inner,
+        job,
+        completed: 0,
+        last_emit: Instant::now()
+            .checked_sub(PROGRESS_INTERVAL)
+            .unwrap_or_else(Instant::now),
+        transferred: Vec::new(),
+    };
+    let result = execute_transfer(&mut transfer);
+    let completed = transfer.completed;
+    if let Some(database_path) = inner.journal_database.as_deref() {
+        record_transfer(database_path, job, transfer.transferred);
+    }
+
+    let finished = job.update(|activity| {
+        if let Some(progress) = activity.progress.as_mut() {
+            progress.completed = completed;
+        }
+        match &result {
+            Ok(()) => {
+                activity.status = OperationStatus::Succeeded;
+                activity.message = job.completion_message();
+            }
+            Err(_) if job.is_cancelled() => {
+                activity.status = OperationStatus::Cancelled;
+                activity.message = format!("Cancelled {}.", job.describe(false));
+            }
+            Err(error) => {
+                activity.status = OperationStatus::Failed;
+                activity.message = error.message.clone();
+                activity.recoverable = error.recoverable;
+            }
+        }
+    });
+    (inner.emit)(&finished);
+}
+
+impl TransferProgress<'_> {
+    /// Decides what to do with `source` when `target` is already taken,
+    /// pausing the job for an answer when its policy is to ask.
+    fn conflict_action(
+        &mut self,
+        source: &Path,
+        target: &Path,
+    ) -> Result<ConflictPolicy, CommandError> {
+        let job = self.job;
+        {
+            let mut conflicts = job.lock_conflicts();
+            if conflicts.policy != ConflictPolicy::Ask {
+                return Ok(conflicts.policy);
+            }
+            conflicts.answer = None;
+        }
+
+        let paused = job.update(|activity| {
+            activity.status = OperationStatus::Paused;
+            activity.message = format!(
+                "An item named {} already exists in this location.",
+                item_label(target)
+            );
+            activity.conflict = Some(FileOperationConflict {
+                source_path: source.to_string_lossy().into_owned(),
+                target_path: target.to_string_lossy().into_owned(),
+                source_is_dir: source.is_dir(),
+                target_is_dir: target.is_dir(),
+            });
+        });
+        (self.inner.emit)(&paused);
+
+        let mut conflicts = job.lock_conflicts();
+        let answer = loop {
+            if job.is_cancelled() {
+                return Err(operation_error(
+                    source,
+                    io::Error::new(io::ErrorKind::Interrupted, "file operation cancelled"),
+                ));
+            }
+            if let Some(answer) = conflicts.answer.take() {
+                break answer;
+            }
+            conflicts = job
+                .answered
+                .wait(conflicts)
+                .expect("file operation conflict state should not be poisoned");
+        };
+        if answer.apply_to_all {
+            conflicts.policy = answer.resolution;
+        }
+        drop(conflicts);
+
+        let resumed = job.update(|activity| {
+            activity.status = OperationStatus::Running;
+            activity.message = format!("{}\u{2026}", job.describe(true));
+            activity.conflict = None;
+        });
+        (self.inner.emit)(&resumed);
+        Ok(answer.resolution)
+    }
+}
+
+fn record_transfer(database_path: &Path, job: &OperationJob, transferred: Vec<JournalStep>) {
+    if transferred.is_empty() {
+        return;
+    }
+
+    let recorded = crate::persistence::open_database(database_path)
+        .and_then(|conn| journal::record(&conn, job.kind.operation(), &transferred));
+    if let Err(_error) = recorded {
+        #[cfg(debug_assertions)]
+        eprintln!(
+            "[frogger] failed to journal file operation {}: {_error}",
+            job.id
+        );
+    }
+}
+
+/// Lets the copy and move helpers report progress and observe cancellation
+/// without caring whether they run inside a queued job.
+trait TransferObserver {
+    fn check_cancelled(&self) -> io::Result<()>;
+    fn advance(&mut self, amount: u64);
+}
+
+/// Observer for one-off transfers, such as undo and redo, that run outside the
+/// queue and cannot be cancelled.
+struct Unobserved;
+
+impl TransferObserver for Unobserved {
+    fn check_cancelled(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn advance(&mut self, _amount: u64) {}
+}
+
+struct TransferProgress<'a> {
+    inner: &'a QueueInner,
+    job: &'a OperationJob,
+    completed: u64,
+    last_emit: Instant,
+    transferred: Vec<JournalStep>,
+}
+
+impl TransferObserver for TransferProgress<'_> {
+    fn check_cancelled(&self) -> io::Result<()> {
+        if self.job.is_cancelled() {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "file operation cancelled",
+            ));
+        }
+        Ok(())
+    }
+
+    fn advance(&mut self, amount: u64) {
+        self.completed = self.completed.saturating_add(amount);
+        if self.last_emit.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+
+        let completed = self.completed;
+        let activity = self.job.update(|activity| {
+            if let Some(progress) = activity.progress.as_mut() {
+                progress.completed = completed;
+            }
+        });
+        (self.inner.emit)(&activity);
+        self.last_emit = Instant::now();
+    }
+}
+
+/// Runs a copy or move job. The item being written when an error or
+/// cancellation occurs is removed so the destination never holds a partial copy.
+fn execute_transfer(transfer: &mut TransferProgress<'_>) -> Result<(), CommandError> {
+    let job = transfer.job;
+    let targets = plan_transfer(&job.sources, &job.destination)?;
+
+    for (source, target) in job.sources.iter().zip(targets) {
+        transfer_item(transfer, source, target)?;
+    }
+
+    Ok(())
+}
+
+/// Transfers one item, settling a clash with an existing item first.
+fn transfer_item(
+    transfer: &mut TransferProgress<'_>,
+    source: &Path,
+    target: PathBuf,
+) -> Result<(), CommandError> {
+    let Ok(existing) = target.symlink_metadata() else {
+        return place_item(transfer, source, &target);
+    };
+
+    if target == source {
+        // Moving an item into its own folder does nothing; copying it there
+        // makes a duplicate next to it.
+        return match transfer.job.kind {
+            TransferKind::Move => Ok(()),
+            TransferKind::Copy => place_item(transfer, source, &keep_both_path(&target)),
+        };
+    }
+
+    let source_is_dir = std::fs::symlink_metadata(source)
+        .map_err(|error| operation_error(source, error))?
+        .is_dir();
+    match transfer.conflict_action(source, &target)? {
+        ConflictPolicy::Skip => {
+            transfer.advance(measure_tree(source));
+            Ok(())
+        }
+        ConflictPolicy::Replace => replace_item(transfer, source, &target),
+        ConflictPolicy::Merge if source_is_dir && existing.is_dir() => {
+            merge_folder(transfer, source, &target)
+        }
+        ConflictPolicy::Merge | ConflictPolicy::KeepBoth | ConflictPolicy::Ask => {
+            place_item(transfer, source, &keep_both_path(&target))
+        }
+    }
+}
+
+fn place_item(
+    transfer: &mut TransferProgress<'_>,
+    source: &Path,
+    target: &Path,
+) -> Result<(), CommandError> {
+    // Both helpers clean up only what they created themselves, so a failure
+    // never touches an item that appeared at `target` after the conflict check.
+    let result = match transfer.job.kind {
+        TransferKind::Copy => copy_tree(transfer, source, target),
+        TransferKind::Move => move_tree(transfer, source, target),
+    };
+    result.map_err(|error| operation_error(source, error))?;
+
+    transfer.transferred.push(JournalStep {
+        source: Some(source.to_path_buf()),
+        target: Some(target.to_path_buf()),
+        replaced: None,
+    });
+    Ok(())
+}
+
+/// Replaces `target` with `source`. The existing item is set aside first.
+/// Once the transfer succeeded it moves to the journal's holding area so undo
+/// can put it back; without a journal it is deleted.
+fn replace_item(
+    transfer: &mut TransferProgress<'_>,
+    source: &Path,
+    target: &Path,
+) -> Result<(), CommandError> {
+    if source.starts_with(target) {
+        return Err(CommandError::invalid_input(
+            "An item cannot replace the folder that contains it.",
+            Some(target.to_string_lossy().into_owned()),
+        ));
+    }
+
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let set_aside = target.with_file_name(format!(
+        ".{name}.frogger-replaced-{}",
+        Uuid::new_v4().simple()
+    ));
+    std::fs::rename(target, &set_aside).map_err(|error| operation_error(target, error))?;
+
+    match place_item(transfer, source, target) {
+        Ok(()) => {
+            let Some(database_path) = transfer.inner.journal_database.as_deref() else {
+                remove_recursively(&set_aside).ok();
+                return Ok(());
+            };
+            let held = journal::holding_path(database_path, target.file_name().unwrap_or_default());
+            let moved = held
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| relocate_item(&set_aside, &held));
+            match moved {
+                Ok(()) => {
+                    if let Some(step) = transfer.transferred.last_mut() {
+                        step.replaced = Some(held);
+                    }
+                }
+                // The replaced item stays set aside next to `target` rather
+                // than being lost.
+                Err(_error) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!(
+                        "[frogger] failed to hold replaced item {}: {_error}",
+                        set_aside.display()
+                    );
+                }
+            }
+            Ok(())
+        }
+        Err(error) => {
+            // A move that copied everything but could not clear its source
+            // leaves the only complete copy at `target`; keep it.
+            if target.symlink_metadata().is_err() {
+                std::fs::rename(&set_aside, target).ok();
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Merges the contents of folder `source` into the existing folder `target`.
+/// Clashes inside are settled the same way as top-level ones.
+fn merge_folder(
+    transfer: &mut TransferProgress<'_>,
+    source: &Path,
+    target: &Path,
+) -> Result<(), CommandError> {
+    let entries = std::fs::read_dir(source)
+        .and_then(|entries| entries.collect::<io::Result<Vec<_>>>())
+        .map_err(|error| operation_error(source, error))?;
+    for entry in entries {
+        transfer_item(transfer, &entry.path(), target.join(entry.file_name()))?;
+    }
+
+    if transfer.job.kind == TransferKind::Move {
+        // Skipped items keep the source folder alive; otherwise it is empty now.
+        std::fs::remove_dir(source).ok();
+    }
+    Ok(())
+}
+
+/// Finder-style "name 2.ext" for the first free name next to `target`.
+fn keep_both_path(target: &Path) -> PathBuf {
+    let parent = target.parent().unwrap_or_else(|| Path::new(""));
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let is_dir = target
+        .symlink_metadata()
+        .map(|metadata| metadata.is_dir())
+        .unwrap_or(false);
+    let (stem, extension) = match name.rfind('.') {
+        Some(index) if index > 0 && !is_dir => name.split_at(index),
+        _ => (name.as_str(), ""),
+    };
+
+    next_available_name(parent, stem, extension)
+}
+
+/// Moves a single item outside the queue, falling back to copy and delete
+/// across volumes.
+pub(crate) fn relocate_item(source: &Path, target: &Path) -> io::Result<()> {
+    move_tree(&mut Unobserved, source, target)
+}
+
+/// Copies a single item outside the queue. A failed copy leaves nothing behind.
+pub(crate) fn duplicate_item(source: &Path, target: &Path) -> io::Result<()> {
+    copy_tree(&mut Unobserved, source, target)
+}
+
+/// Renames `source` to `target`, or copies and then deletes it across volumes.
+///
+/// Once a cross-volume copy is complete, `target` is never removed: if the
+/// source cannot be fully deleted afterwards, the error names what is left of
+/// it and both stay on disk.
+fn move_tree(transfer: &mut impl TransferObserver, source: &Path, target: &Path) -> io::Result<()> {
+    transfer.check_cancelled()?;
+    match rename_new(source, target) {
+        Ok(()) => {
+            transfer.advance(measure_tree(target));
+            Ok(())
+        }
+        Err(error) if error.kind() == io::ErrorKind::CrossesDevices => {
+            copy_tree(transfer, source, target)?;
+            if let Err(error) = transfer.check_cancelled() {
+                // The source is still whole, so the copy can go.
+                remove_partial_output(target);
+                return Err(error);
+            }
+            remove_recursively(source).map_err(|error| {
+                io::Error::new(
+                    error.kind(),
+                    format!(
+                        "copied to {}, but {} could not be removed: {error}",
+                        target.display(),
+                        source.display()
+                    ),
+                )
+            })
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Renames `source` to `target` without replacing an item that appeared at
+/// `target` after the caller checked it was free, which a plain rename does
+/// silently on Unix. Files are hard-linked into place and then unlinked, so
+/// the link fails if anything is there. Folders cannot be linked and are only
+/// checked once more right before the rename; renaming onto a folder that is
+/// not empty fails anyway. Case-only renames keep the plain rename, since the
+/// new name already "exists" on case-insensitive filesystems.
+fn rename_new(source: &Path, target: &Path) -> io::Result<()> {
+    let case_only_rename =
+        source.to_string_lossy().to_lowercase() == target.to_string_lossy().to_lowercase();
+    if case_only_rename {
+        return std::fs::rename(source, target);
+    }
+
+    if std::fs::symlink_metadata(source)?.is_file() {
+        match std::fs::hard_link(source, target) {
+            Ok(()) => {
+                return std::fs::remove_file(source).inspect_err(|_| {
+                    std::fs::remove_file(target).ok();
+                });
+            }
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::AlreadyExists | io::ErrorKind::CrossesDevices
+                ) =>
+            {
+                return Err(error);
+            }
+            // The filesystem has no hard links; fall back to the checked rename.
+            Err(_) => {}
+        }
+    }
+
+    if target.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    std::fs::rename(source, target)
+}
+
+/// Copies `source` to `target`, which must not exist yet. Creating `target`
+/// fails if anything is already there, so when the copy fails afterwards only
+/// output this call created is removed.
+fn copy_tree(transfer: &mut impl TransferObserver, source: &Path, target: &Path) -> io::Result<()> {
+    transfer.check_cancelled()?;
+    let metadata = std::fs::symlink_metadata(source)?;
+    let file_type = metadata.file_type();
+
+    if file_type.is_symlink() {
+        return copy_symlink(source, target);
+    }
+    let result = if file_type.is_dir() {
+        std::fs::create_dir(target)?;
+        copy_folder_contents(transfer, source, target, &metadata)
+    } else {
+        let writer = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(target)?;
+        copy_file(transfer, source, target, writer, &metadata)
+    };
+    result.inspect_err(|_| remove_partial_output(target))
+}
+
+fn copy_folder_contents(
+    transfer: &mut impl TransferObserver,
+    source: &Path,
+    target: &Path,
+    metadata: &std::fs::Metadata,
+) -> io::Result<()> {
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        copy_tree(transfer, &entry.path(), &target.join(entry.file_name()))?;
+    }
+    std::fs::set_permissions(target, metadata.permissions())
+}
+
+fn copy_file(
+    transfer: &mut impl TransferObserver,
+    source: &Path,
+    target: &Path,
+    mut writer: File,
+    metadata: &std::fs::Metadata,
+) -> io::Result<()> {
+    let mut reader = File::open(source)?;
+    let mut buffer = vec![0_u8; COPY_CHUNK_SIZE];
+
+    loop {
+        transfer.check_cancelled()?;
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        transfer.advance(read as u64);
+    }
+
+    writer.flush()?;
+    std::fs::set_permissions(target, metadata.permissions())
+}
+
+/// Total size in bytes of the files under `path`, used as the progress total.
+fn measure_tree(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Validates a copy/move request and returns the target path for each source.
+/// Targets that already exist are left to the job's conflict policy.
+fn plan_transfer(sources: &[PathBuf], destination: &Path) -> Result<Vec<PathBuf>, CommandError> {
+    let metadata =
+        std::fs::metadata(destination).map_err(|error| operation_error(destination, error))?;
+    if !metadata.is_dir() {
+        return Err(CommandError::invalid_input(
+            "Items can only be copied or moved into a folder.",
+            Some(destination.to_string_lossy().into_owned()),
+        ));
+    }
+
+    let mut targets = Vec::with_capacity(sources.len());
+    for source in sources {
+        std::fs::symlink_metadata(source).map_err(|error| operation_error(source, error))?;
+        let name = source.file_name().ok_or_else(|| {
+            CommandError::invalid_input(
+                "This item cannot be copied or moved.",
+                Some(source.to_string_lossy().into_owned()),
+            )
+        })?;
+
+        if destination.starts_with(source) {
+            return Err(CommandError::invalid_input(
+                "A folder cannot be copied or moved into itself.",
+                Some(source.to_string_lossy().into_owned()),
+            ));
+        }
+
+        let target = destination.join(name);
+        if targets.contains(&target) {
+            return Err(already_exists_error(&target));
+        }
+        targets.push(target);
+    }
+
+    Ok(targets)
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(source)?, target)
+}
+
+#[cfg(windows)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    let link_target = std::fs::read_link(source)?;
+    if std::fs::metadata(source)
+        .map(|metadata| metadata.is_dir())
+        .unwrap_or(false)
+    {
+        std::os::windows::fs::symlink_dir(link_target, target)
+    } else {
+        std::os::windows::fs::symlink_file(link_target, target)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::fs::copy(source, target).map(|_| ())
+}
+
+pub(crate) fn remove_recursively(path: &Path) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+fn remove_partial_output(path: &Path) {
+    if path.symlink_metadata().is_ok() {
+        remove_recursively(path).ok();
+    }
+}
+
+/// First free "stem.ext", "stem 2.ext", "stem 3.ext", ... name in `parent`.
+fn next_available_name(parent: &Path, stem: &str, extension: &str) -> PathBuf {
+    let first = parent.join(format!("{stem}{extension}"));
+    if first.symlink_metadata().is_err() {
+        return first;
+    }
+
+    (2_u64..)
+        .map(|index| parent.join(format!("{stem} {index}{extension}")))
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .expect("an unused name should exist")
+}
+
+fn validate_item_name(name: &str) -> Result<&str, CommandError> {
+    let trimmed = name.trim();
+    let invalid = trimmed.is_empty()
+        || trimmed == "."
+        || trimmed == ".."
+        || trimmed.contains('/')
+        || trimmed.contains('\0')
+        || (cfg!(windows) && trimmed.contains(['\\', ':', '*', '?', '"', '<', '>', '|']));
+
+    if invalid {
+        return Err(CommandError::invalid_input(
+            "That name cannot be used for a file or folder.",
+            Some(name.to_string()),
+        ));
+    }
+
+    Ok(trimmed)
+}
+
+fn require_sources(paths: &[PathBuf]) -> Result<&PathBuf, CommandError> {
+    paths
+        .first()
+        .ok_or_else(|| CommandError::invalid_input("No items were selected.", None))
+}
+
+pub(crate) fn succeeded_activity(
+    operation: FileOperationKind,
+    primary_path: &Path,
+    message: String,
+    completed: u64,
+) -> OperationActivity {
+    OperationActivity {
+        id: format!("operation-{}", Uuid::new_v4()),
+        operation,
+        status: OperationStatus::Succeeded,
+        primary_path: Some(primary_path.to_string_lossy().into_owned()),
+        message,
+        progress: Some(OperationProgress {
+            completed,
+            total: Some(completed),
+        }),
+        recoverable: false,
+        conflict: None,
+    }
+}
+
+pub(crate) fn item_label(path: &Path) -> String {
+    path.file_name()
+        .map(|name| format!("\u{201c}{}\u{201d}", name.to_string_lossy()))
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+pub(crate) fn items_label(paths: &[PathBuf]) -> String {
+    match paths {
+        [single] => item_label(single),
+        _ => format!("{} items", paths.len()),
+    }
+}
+
+fn already_exists_error(target: &Path) -> CommandError {
+    CommandError::already_exists(
+        "An item with that name already exists in this location.",
+        Some(target.to_string_lossy().into_owned()),
+    )
+}
+
+pub(crate) fn operation_error(path: &Path, error: io::Error) -> CommandError {
+    match error.kind() {
+        io::ErrorKind::NotFound => CommandError::missing_path(
+            "The item no longer exists.",
+            Some(path.to_string_lossy().into_owned()),
+        ),
+        io::ErrorKind::PermissionDenied => CommandError::permission_denied(
+            "Frogger does not have permission to change this item.",
+            Some(path.to_string_lossy().into_owned()),
+        ),
+        io::ErrorKind::AlreadyExists => already_exists_error(path),
+        _ => CommandError::unavailable(
+            "The file operation could not be completed.",
+            Some(format!("{}: {error}", path.display())),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn create_folder_picks_finder_style_untitled_names() {
+        let temp = tempdir().expect("tempdir should exist");
+
+        let first = create_folder(temp.path(), None).expect("first folder should be created");
+        let second = create_folder(temp.path(), None).expect("second folder should be created");
+
+        assert_eq!(first.operation, FileOperationKind::NewFolder);
+        assert_eq!(first.status, OperationStatus::Succeeded);
+        assert!(temp.path().join("untitled folder").is_dir());
+        assert!(temp.path().join("untitled folder 2").is_dir());
+        assert_eq!(
+            second.primary_path.as_deref(),
+            Some(
+                temp.path()
+                    .join("untitled folder 2")
+                    .to_string_lossy()
+                    .as_ref()
+            )
+        );
+
+        let error = create_folder(temp.path(), Some("untitled folder"))
+            .expect_err("duplicate folder should fail");
+        assert_eq!(error.code, "already_exists");
+    }
+
+    #[test]
+    fn rename_rejects_invalid_names_and_existing_targets() {
+        let temp = tempdir().expect("tempdir should exist");
+        let source = temp.path().join("draft.txt");
+        std::fs::write(&source, "draft").expect("source should write");
+        std::fs::write(temp.path().join("final.txt"), "final").expect("target should write");
+
+        let invalid = rename_item(&source, "nested/name.txt").expect_err("slash should fail");
+        assert_eq!(invalid.code, "invalid_input");
+
+        let conflict = rename_item(&source, "final.txt").expect_err("existing name should fail");
+        assert_eq!(conflict.code, "already_exists");
+
+        let activity = rename_item(&source, "notes.txt").expect("rename should succeed");
+        assert_eq!(activity.operation, FileOperationKind::Rename);
+        assert!(!source.exists());
+        assert!(temp.path().join("notes.txt").is_file());
+
+        let missing = rename_item(&source, "again.txt").expect_err("missing source should fail");
+        assert_eq!(missing.code, "missing_path");
+    }
+
+    #[test]
+    fn failed_copies_never_remove_items_they_did_not_create() {
+        let temp = tempdir().expect("tempdir should exist");
+        let source = temp.path().join("project");
+        std::fs::create_dir(&source).expect("source should be created");
+        std::fs::write(source.join("notes.txt"), "new").expect("source file should write");
+        // Stands in for an item that appeared after the conflict check.
+        let target = temp.path().join("arrived");
+        std::fs::create_dir(&target).expect("target should be created");
+        std::fs::write(target.join("keep.txt"), "keep").expect("target file should write");
+
+        let error = duplicate_item(&source, &target).expect_err("copy onto an item should fail");
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        let error = relocate_item(&source, &target).expect_err("move onto an item should fail");
+        assert_ne!(error.kind(), io::ErrorKind::CrossesDevices);
+        assert_eq!(
+            std::fs::read_to_string(target.join("keep.txt")).expect("target should survive"),
+            "keep"
+        );
+        assert!(source.join("notes.txt").is_file());
+
+        let arrived_file = temp.path().join("arrived.txt");
+        std::fs::write(&arrived_file, "keep").expect("target file should write");
+        let error = relocate_item(&source.join("notes.txt"), &arrived_file)
+            .expect_err("move onto a file should fail");
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(
+            std::fs::read_to_string(&arrived_file).expect("target file should survive"),
+            "keep"
+        );
+        assert!(source.join("notes.txt").is_file());
+    }
+
+    #[test]
+    fn queued_copy_recurses_into_folders_and_reports_progress() {
+        let temp = tempdir().expect("tempdir should exist");
+        let source = temp.path().join("project");
+        let destination = temp.path().join("backup");
+        std::fs::create_dir_all(source.join("src")).expect("source tree should be created");
+        std::fs::create_dir(&destination).expect("destination should be created");
+        std::fs::write(source.join("src/main.rs"), "fn main() {}").expect("file should write");
+        let (queue, events) = recording_queue();
+
+        let queued = queue
+            .enqueue_copy(
+                vec![source.clone()],
+                destination.clone(),
+                ConflictPolicy::Ask,
+            )
+            .expect("copy should queue");
+        assert_eq!(queued.status, OperationStatus::Queued);
+        assert_eq!(queued.operation, FileOperationKind::Copy);
+
+        let finished = wait_for_finish(&events, &queued.id);
+        assert_eq!(finished.status, OperationStatus::Succeeded);
+        assert_eq!(
+            finished.progress,
+            Some(OperationProgress {
+                completed: 12,
+                total: Some(12)
+            })
+        );
+        assert!(source.join("src/main.rs").is_file());
+        assert_eq!(
+            std::fs::read_to_string(destination.join("project/src/main.rs"))
+                .expect("copied file should read"),
+            "fn main() {}"
+        );
+
+        let again = queue
+            .enqueue_copy(vec![source], destination.clone(), ConflictPolicy::KeepBoth)
+            .expect("second copy should queue");
+        assert_eq!(
+            wait_for_finish(&events, &again.id).status,
+            OperationStatus::Succeeded
+        );
+        assert!(destination.join("project 2/src/main.rs").is_file());
+    }
+
+    #[test]
+    fn ask_policy_pauses_until_the_conflict_is_answered() {
+        let temp = tempdir().expect("tempdir should exist");
+        let destination = temp.path().join("destination");
+        std::fs::create_dir(&destination).expect("destination should be created");
+        let mut sources = Vec::new();
+        for name in ["a.txt", "b.txt"] {
+            let source = temp.path().join(name);
+            std::fs::write(&source, "new").expect("source should write");
+            std::fs::write(destination.join(name), "old").expect("existing file should write");
+            sources.push(source);
+        }
+        let (queue, events) = recording_queue();
+
+        let queued = queue
+            .enqueue_copy(sources, destination.clone(), ConflictPolicy::Ask)
+            .expect("copy should queue");
+        let paused = loop {
+            let activity = events
+                .recv_timeout(Duration::from_secs(10))
+                .expect("conflict should be reported");
+            if activity.status == OperationStatus::Paused {
+                break activity;
+            }
+        };
+        let conflict = paused
+            .conflict
+            .expect("paused job should carry its conflict");
+        assert_eq!(
+            conflict.target_path,
+            destination.join("a.txt").to_string_lossy()
+        );
+
+        let ask_again = queue
+            .resolve_conflict(&queued.id, ConflictPolicy::Ask, false)
+            .expect_err("asking again is not an answer");
+        assert_eq!(ask_again.code, "invalid_input");
+        queue
+            .resolve_conflict(&queued.id, ConflictPolicy::Replace, true)
+            .expect("answer should be accepted");
+
+        let mut finished = None;
+        while finished.is_none() {
+            let activity = events
+                .recv_timeout(Duration::from_secs(10))
+                .expect("job should finish");
+            assert_ne!(
+                activity.status,
+                OperationStatus::Paused,
+                "apply to all should not ask again"
+            );
+            if activity.status == OperationStatus::Succeeded {
+                finished = Some(activity);
+            }
+        }
+        for name in ["a.txt", "b.txt"] {
+            assert_eq!(
+                std::fs::read_to_string(destination.join(name)).expect("file should read"),
+                "new"
+            );
+        }
+        let leftovers = std::fs::read_dir(&destination)
+            .expect("destination should list")
+            .count();
+        assert_eq!(leftovers, 2);
+    }
+
+    #[test]
+    fn skip_keep_both_and_merge_policies_settle_conflicts() {
+        let temp = tempdir().expect("tempdir should exist");
+        let destination = temp.path().join("destination");
+        std::fs::create_dir_all(destination.join("photos")).expect("destination should exist");
+        std::fs::write(destination.join("report.pdf"), "old").expect("file should write");
+        std::fs::write(destination.join("photos/a.jpg"), "old a").expect("file should write");
+        std::fs::write(destination.join("photos/c.jpg"), "old c").expect("file should write");
+        let report = temp.path().join("report.pdf");
+        let photos = temp.path().join("photos");
+        std::fs::write(&report, "new").expect("file should write");
+        std::fs::create_dir(&photos).expect("folder should be created");
+        std::fs::write(photos.join("a.jpg"), "new a").expect("file should write");
+        std::fs::write(photos.join("b.jpg"), "new b").expect("file should write");
+        let (queue, events) = recording_queue();
+
+        let skipped = queue
+            .enqueue_copy(
+                vec![report.clone()],
+                destination.clone(),
+                ConflictPolicy::Skip,
+            )
+            .expect("copy should queue");
+        wait_for_finish(&events, &skipped.id);
+        assert_eq!(
+            std::fs::read_to_string(destination.join("report.pdf")).expect("file should read"),
+            "old"
+        );
+
+        let kept = queue
+            .enqueue_copy(vec![report], destination.clone(), ConflictPolicy::KeepBoth)
+            .expect("copy should queue");
+        wait_for_finish(&events, &kept.id);
+        assert_eq!(
+            std::fs::read_to_string(destination.join("report 2.pdf")).expect("file should read"),
+            "new"
+        );
+
+        let merged = queue
+            .enqueue_move(
+                vec![photos.clone()],
+                destination.clone(),
+                ConflictPolicy::Merge,
+            )
+            .expect("move should queue");
+        assert_eq!(
+            wait_for_finish(&events, &merged.id).status,
+            OperationStatus::Succeeded
+        );
+        let merged_photos = destination.join("photos");
+        for (name, contents) in [
+            ("a.jpg", "old a"),
+            ("a 2.jpg", "new a"),
+            ("b.jpg", "new b"),
+            ("c.jpg", "old c"),
+        ] {
+            assert_eq!(
+                std::fs::read_to_string(merged_photos.join(name)).expect("file should read"),
+                contents
+            );
+        }
+        assert!(!photos.exists());
+    }
+
+    #[test]
+    fn queued_move_relocates_sources_and_refuses_moving_into_self() {
+        let temp = tempdir().expect("tempdir should exist");
+        let folder = temp.path().join("folder");
+        let file = temp.path().join("report.pdf");
+        std::fs::create_dir(&folder).expect("folder should be created");
+        std::fs::write(&file, "pdf").expect("file should write");
+        let (queue, events) = recording_queue();
+
+        let into_self = queue
+            .enqueue_move(vec![folder.clone()], folder.clone(), ConflictPolicy::Ask)
+            .expect_err("moving a folder into itself should fail");
+        assert_eq!(into_self.code, "invalid_input");
+
+        let queued = queue
+            .enqueue_move(vec![file.clone()], folder.clone(), ConflictPolicy::Ask)
+            .expect("move should queue");
+        let finished = wait_for_finish(&events, &queued.id);
+        assert_eq!(finished.operation, FileOperationKind::Move);
+        assert_eq!(finished.status, OperationStatus::Succeeded);
+        assert!(!file.exists());
+        assert!(folder.join("report.pdf").is_file());
+    }
+
+    #[test]
+    fn queued_move_is_journaled_for_undo() {
+        let temp = tempdir().expect("tempdir should exist");
+        let database_path =
+            std::env::temp_dir().join(format!("frogger-operations-{}.sqlite3", Uuid::new_v4()));
+        let conn =
+            crate::persistence::open_database(&database_path).expect("database should migrate");
+        let folder = temp.path().join("folder");
+        let file = temp.path().join("report.pdf");
+        std::fs::create_dir(&folder).expect("folder should be created");
+        std::fs::write(&file, "pdf").expect("file should write");
+        let (sender, events) = mpsc::channel();
+        let queue = OperationQueue::new(
+            1,
+            Some(database_path.clone()),
+            Arc::new(move |activity: &OperationActivity| {
+                sender.send(activity.clone()).ok();
+            }),
+        );
+
+        let queued = queue
+            .enqueue_move(vec![file.clone()], folder.clone(), ConflictPolicy::Ask)
+            .expect("move should queue");
+        wait_for_finish(&events, &queued.id);
+        assert!(folder.join("report.pdf").is_file());
+
+        journal::undo(&conn).expect("queued move should be undoable");
+        assert!(file.is_file());
+        assert!(!folder.join("report.pdf").exists());
+
+        std::fs::remove_file(database_path).ok();
+    }
+
+    #[test]
+    fn replaced_items_come_back_on_undo_and_go_again_on_redo() {
+        let temp = tempdir().expect("tempdir should exist");
+        let database_path =
+            std::env::temp_dir().join(format!("frogger-operations-{}.sqlite3", Uuid::new_v4()));
+        let conn =
+            crate::persistence::open_database(&database_path).expect("database should migrate");
+        let folder = temp.path().join("folder");
+        let file = temp.path().join("report.pdf");
+        let existing = folder.join("report.pdf");
+        std::fs::create_dir(&folder).expect("folder should be created");
+        std::fs::write(&file, "new").expect("file should write");
+        std::fs::write(&existing, "old").expect("existing file should write");
+        let (sender, events) = mpsc::channel();
+        let queue = OperationQueue::new(
+            1,
+            Some(database_path.clone()),
+            Arc::new(move |activity: &OperationActivity| {
+                sender.send(activity.clone()).ok();
+            }),
+        );
+
+        let queued = queue
+            .enqueue_move(vec![file.clone()], folder.clone(), ConflictPolicy::Replace)
+            .expect("move should queue");
+        assert_eq!(
+            wait_for_finish(&events, &queued.id).status,
+            OperationStatus::Succeeded
+        );
+        assert_eq!(
+            std::fs::read_to_string(&existing).expect("target should exist"),
+            "new"
+        );
+        assert_eq!(
+            std::fs::read_dir(&folder)
+                .expect("folder should list")
+                .count(),
+            1
+        );
+
+        journal::undo(&conn).expect("replacing move should be undoable");
+        assert_eq!(
+            std::fs::read_to_string(&file).expect("source should be back"),
+            "new"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&existing).expect("replaced file should be back"),
+            "old"
+        );
+
+        journal::redo(&conn).expect("replacing move should be redoable");
+        assert!(!file.exists());
+        assert_eq!(
+            std::fs::read_to_string(&existing).expect("target should exist"),
+            "new"
+        );
+        journal::undo(&conn).expect("replacing move should be undoable again");
+        assert_eq!(
+            std::fs::read_to_string(&existing).expect("replaced file should be back"),
+            "old"
+        );
+
+        std::fs::remove_file(&database_path).ok();
+        std::fs::remove_dir_all(database_path.with_extension("replaced")).ok();
+    }
+
+    #[test]
+    fn transfer_into_missing_destination_reports_missing_path() {
+        let temp = tempdir().expect("tempdir should exist");
+        let file = temp.path().join("a.txt");
+        std::fs::write(&file, "a").expect("file should write");
+        let (queue, _events) = recording_queue();
+
+        let error = queue
+            .enqueue_copy(vec![file], temp.path().join("missing"), ConflictPolicy::Ask)
+            .expect_err("missing destination should fail");
+        assert_eq!(error.code, "missing_path");
+        assert!(error.recoverable);
+    }
+
+    #[test]
+    fn cancel_stops_queued_and_running_jobs_and_removes_partial_output() {
+        let temp = tempdir().expect("tempdir should exist");
+        let source = temp.path().join("large");
+        let destination = temp.path().join("destination");
+        std::fs::create_dir_all(&source).expect("source should be created");
+        std::fs::create_dir(&destination).expect("destination should be created");
+        for name in ["a.bin", "b.bin"] {
+            std::fs::write(source.join(name), vec![7_u8; COPY_CHUNK_SIZE * 2])
+                .expect("large file should write");
+        }
+        let small = temp.path().join("small.txt");
+        std::fs::write(&small, "small").expect("small file should write");
+
+        // Hold the worker on its first progress report so the test can cancel
+        // while the copy is half written.
+        let (sender, events) = mpsc::channel();
+        let (release, released) = mpsc::channel::<()>();
+        let released = Mutex::new(released);
+        let queue = OperationQueue::new(
+            1,
+            None,
+            Arc::new(move |activity: &OperationActivity| {
+                sender.send(activity.clone()).ok();
+                let started_writing = activity.status == OperationStatus::Running
+                    && activity
+                        .progress
+                        .as_ref()
+                        .map(|progress| progress.completed > 0)
+                        .unwrap_or(false);
+                if started_writing {
+                    released
+                        .lock()
+                        .expect("release lock should not be poisoned")
+                        .recv_timeout(Duration::from_secs(10))
+                        .ok();
+                }
+            }),
+        );
+
+        let running = queue
+            .enqueue_copy(vec![source], destination.clone(), ConflictPolicy::Ask)
+            .expect("large copy should queue");
+        let waiting = queue
+            .enqueue_copy(vec![small], destination.clone(), ConflictPolicy::Ask)
+            .expect("small copy should queue");
+        loop {
+            let activity = events
+                .recv_timeout(Duration::from_secs(10))
+                .expect("progress should be reported");
+            if activity.id == running.id
+                && activity
+                    .progress
+                    .as_ref()
+                    .map(|progress| progress.completed > 0)
+                    .unwrap_or(false)
+            {
+                break;
+            }
+        }
+
+        let cancelled_waiting = queue.cancel(&waiting.id).expect("queued job should cancel");
+        assert_eq!(cancelled_waiting.status, OperationStatus::Cancelled);
+        queue
+            .cancel(&running.id)
+            .expect("running job should cancel");
+        release.send(()).expect("worker should be waiting");
+
+        let finished = wait_for_finish(&events, &running.id);
+        assert_eq!(finished.status, OperationStatus::Cancelled);
+        assert!(!destination.join("large").exists());
+        assert!(!destination.join("small.txt").exists());
+        assert!(wait_until_idle(&queue));
+
+        let finished_error = queue
+            .cancel(&running.id)
+            .expect_err("finished job should not cancel again");
+        assert_eq!(finished_error.code, "unavailable");
+    }
+
+    fn recording_queue() -> (OperationQueue, mpsc::Receiver<OperationActivity>) {
+        let (sender, receiver) = mpsc::channel();
+        let queue = OperationQueue::new(
+            1,
+            None,
+            Arc::new(move |activity: &OperationActivity| {
+                sender.send(activity.clone()).ok();
+            }),
+        );
+        (queue, receiver)
+    }
+
+    fn wait_for_finish(events: &mpsc::Receiver<OperationActivity>, id: &str) -> OperationActivity {
+        loop {
+            let activity = events
+                .recv_timeout(Duration::from_secs(10))
+                .expect("operation should report progress");
+            let finished = matches!(
+                activity.status,
+                OperationStatus::Succeeded | OperationStatus::Failed | OperationStatus::Cancelled
+            );
+            if activity.id == id && finished {
+                return activity;
+            }
+        }
+    }
+
+    fn wait_until_idle(queue: &OperationQueue) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if queue.activities().is_empty() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+}
@@ -0,0 +1,187 @@
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::errors::CommandError;
+use crate::models::SearchPatternMode;
+
+/// Compiled regexes larger than this are rejected rather than built, so a
+/// pathological pattern cannot stall search.
+const MAX_REGEX_SIZE: usize = 1 << 20;
+const MAX_PATTERN_CHARS: usize = 1_000;
+
+/// A regex or glob matched against indexed names, or against whole paths when
+/// the pattern mentions a `/`. Matching ignores case, and names are composed
+/// first so decomposed macOS names match composed patterns.
+pub struct NamePattern {
+    matcher: Matcher,
+    matches_path: bool,
+    affixes: Option<(String, String)>,
+}
+
+enum Matcher {
+    Regex(Regex),
+    Glob(GlobMatcher),
+}
+
+impl NamePattern {
+    pub fn compile(pattern: &str, mode: SearchPatternMode) -> Result<Self, CommandError> {
+        if pattern.trim().is_empty() {
+            return Err(CommandError::invalid_input(
+                "Search patterns cannot be empty.",
+                None,
+            ));
+        }
+        if pattern.chars().count() > MAX_PATTERN_CHARS {
+            return Err(CommandError::invalid_input(
+                "That search pattern is too long.",
+                Some(format!("{MAX_PATTERN_CHARS} characters at most")),
+            ));
+        }
+
+        let pattern = pattern.nfc().collect::<String>();
+        let affixes = match mode {
+            SearchPatternMode::Regex => None,
+            SearchPatternMode::Glob => glob_affixes(&pattern),
+        };
+        let matcher = match mode {
+            SearchPatternMode::Regex => RegexBuilder::new(&pattern)
+                .case_insensitive(true)
+                .size_limit(MAX_REGEX_SIZE)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|error| {
+                    CommandError::invalid_input(
+                        "That search pattern is not a valid regular expression.",
+                        Some(error.to_string()),
+                    )
+                })?,
+            SearchPatternMode::Glob => GlobBuilder::new(&pattern)
+                .case_insensitive(true)
+                .literal_separator(true)
+                .build()
+                .map(|glob| Matcher::Glob(glob.compile_matcher()))
+                .map_err(|error| {
+                    CommandError::invalid_input(
+                        "That search pattern is not a valid glob.",
+                        Some(error.to_string()),
+                    )
+                })?,
+        };
+
+        Ok(Self {
+            matcher,
+            matches_path: pattern.contains('/'),
+            affixes,
+        })
+    }
+
+    /// Whether the pattern is matched against whole paths instead of names.
+    pub fn matches_path(&self) -> bool {
+        self.matches_path
+    }
+
+    /// Literal text every match starts and ends with, so a scan can skip
+    /// rows before running the matcher. Only plain ASCII is kept, which is
+    /// all SQLite's `LIKE` folds case for.
+    pub fn literal_affixes(&self) -> Option<(&str, &str)> {
+        self.affixes
+            .as_ref()
+            .map(|(prefix, suffix)| (prefix.as_str(), suffix.as_str()))
+    }
+
+    pub fn is_match(&self, name: &str, path: &str) -> bool {
+        let haystack = if self.matches_path { path } else { name }
+            .nfc()
+            .collect::<String>();
+        match &self.matcher {
+            Matcher::Regex(regex) => regex.is_match(&haystack),
+            Matcher::Glob(glob) => glob.is_match(&haystack),
+        }
+    }
+}
+
+/// The literal ASCII runs at the start and end of a glob, e.g. `.test.ts`
+/// for `**/*.test.ts`. Stops at anything that could match more than itself.
+fn glob_affixes(pattern: &str) -> Option<(String, String)> {
+    let is_literal = |character: &char| {
+        character.is_ascii() && !matches!(character, '*' | '?' | '[' | ']' | '{' | '}' | '\\')
+    };
+    let prefix = pattern.chars().take_while(is_literal).collect::<String>();
+    if prefix.len() == pattern.len() {
+        return Some((prefix, String::new()));
+    }
+    let mut suffix = pattern
+        .chars()
+        .rev()
+        .take_while(is_literal)
+        .collect::<Vec<_>>();
+    suffix.reverse();
+    let suffix = suffix.into_iter().collect::<String>();
+    (!prefix.is_empty() || !suffix.is_empty()).then_some((prefix, suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_match_names_or_whole_paths() {
+        let tests = NamePattern::compile("**/*.test.ts", SearchPatternMode::Glob)
+            .expect("glob should compile");
+        assert!(tests.is_match("app.test.ts", "/home/me/src/app.test.ts"));
+        assert!(!tests.is_match("app.ts", "/home/me/src/app.ts"));
+
+        let photos = NamePattern::compile("img_*.JPG", SearchPatternMode::Glob)
+            .expect("glob should compile");
+        assert!(photos.is_match("IMG_0042.jpg", "/home/me/Pictures/IMG_0042.jpg"));
+        assert!(!photos.is_match("IMG_0042.jpg.bak", "/home/me/IMG_0042.jpg.bak"));
+    }
+
+    #[test]
+    fn globs_expose_their_literal_ends() {
+        let affixes = |pattern: &str| {
+            NamePattern::compile(pattern, SearchPatternMode::Glob)
+                .expect("glob should compile")
+                .literal_affixes()
+                .map(|(prefix, suffix)| (prefix.to_string(), suffix.to_string()))
+        };
+        let pair = |prefix: &str, suffix: &str| Some((prefix.to_string(), suffix.to_string()));
+        assert_eq!(affixes("**/*.test.ts"), pair("", ".test.ts"));
+        assert_eq!(affixes("IMG_*.jpg"), pair("IMG_", ".jpg"));
+        assert_eq!(affixes("notes.md"), pair("notes.md", ""));
+        assert_eq!(affixes("café*"), pair("caf", ""));
+        assert_eq!(affixes("{a,b}*"), None);
+        assert!(NamePattern::compile("img", SearchPatternMode::Regex)
+            .expect("regex should compile")
+            .literal_affixes()
+            .is_none());
+    }
+
+    #[test]
+    fn regexes_search_names_without_case() {
+        let photos = NamePattern::compile(r"IMG_[0-9]{4}\.jpg$", SearchPatternMode::Regex)
+            .expect("regex should compile");
+        assert!(photos.is_match("img_2024.JPG", "/photos/img_2024.JPG"));
+        assert!(!photos.is_match("IMG_24.jpg", "/photos/IMG_24.jpg"));
+
+        let accented =
+            NamePattern::compile("^café", SearchPatternMode::Regex).expect("regex should compile");
+        assert!(accented.is_match("Cafe\u{301} menu.pdf", "/menus/Cafe\u{301} menu.pdf"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported_as_input_errors() {
+        let regex = NamePattern::compile("IMG_[0-9", SearchPatternMode::Regex)
+            .err()
+            .expect("unclosed class should fail");
+        assert_eq!(regex.code, "invalid_input");
+        assert!(regex.details.is_some());
+
+        let glob = NamePattern::compile("{a,b", SearchPatternMode::Glob)
+            .err()
+            .expect("unclosed alternation should fail");
+        assert_eq!(glob.code, "invalid_input");
+        assert!(NamePattern::compile("  ", SearchPatternMode::Glob).is_err());
+    }
+}
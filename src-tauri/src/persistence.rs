@@ -3,7 +3,7 @@ use std::path::Path;
 use anyhow::{Context, Result};
 use rusqlite::Connection;
 
-pub const CURRENT_SCHEMA_VERSION: i64 = 1;
+pub const CURRENT_SCHEMA_VERSION: i64 = 12;
 
 pub fn open_database(path: &Path) -> Result<Connection> {
     if let Some(parent) = path.parent() {
@@ -18,6 +18,15 @@ pub fn open_database(path: &Path) -> Result<Connection> {
     Ok(conn)
 }
 
+/// Opens a private in-memory database with the full schema, for throwaway
+/// work such as searching a folder the index does not cover.
+pub fn open_memory_database() -> Result<Connection> {
+    let mut conn = Connection::open_in_memory().context("failed to open in-memory database")?;
+    configure_connection(&conn)?;
+    apply_migrations(&mut conn)?;
+    Ok(conn)
+}
+
 fn configure_connection(conn: &Connection) -> Result<()> {
     conn.pragma_update(None, "foreign_keys", "ON")?;
     conn.pragma_update(None, "journal_mode", "WAL")?;
@@ -46,7 +55,117 @@ fn apply_migrations(conn: &mut Connection) -> Result<()> {
         tx.execute_batch(V1_SCHEMA)?;
         tx.execute(
             "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
-            (CURRENT_SCHEMA_VERSION, "phase_1_initial_state"),
+            (1_i64, "phase_1_initial_state"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 2 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V2_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (2_i64, "operation_journal"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 3 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V3_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (3_i64, "trash_records"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 4 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V4_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (4_i64, "index_configuration"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 5 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V5_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (5_i64, "metadata_search_fts"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 6 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V6_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (6_i64, "smart_folders"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 7 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V7_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (7_i64, "search_open_counts"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 8 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V8_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (8_i64, "content_search_fts"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 9 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V9_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (9_i64, "search_history"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 10 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V10_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (10_i64, "photo_metadata"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 11 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V11_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (11_i64, "thumbnail_cache_budget"),
+        )?;
+        tx.commit()?;
+    }
+
+    if current_version < 12 {
+        let tx = conn.transaction()?;
+        tx.execute_batch(V12_SCHEMA)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            (12_i64, "index_covered_roots"),
         )?;
         tx.commit()?;
     }
@@ -219,6 +338,285 @@ INSERT OR IGNORE INTO index_state (id, status, has_initial_index) VALUES
     ('metadata', 'not_started', 0);
 "#;
 
+const V2_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS operation_journal (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    operation TEXT NOT NULL,
+    state TEXT NOT NULL DEFAULT 'done',
+    steps_json TEXT NOT NULL,
+    recorded_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_operation_journal_state ON operation_journal(state, id);
+"#;
+
+const V3_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS trash_records (
+    id TEXT PRIMARY KEY,
+    original_path TEXT NOT NULL,
+    is_dir INTEGER NOT NULL,
+    size INTEGER,
+    trashed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_trash_records_original_path ON trash_records(original_path);
+"#;
+
+const V4_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS index_roots (
+    path TEXT PRIMARY KEY,
+    is_default INTEGER NOT NULL DEFAULT 0,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS index_exclusions (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    is_default INTEGER NOT NULL DEFAULT 0,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    UNIQUE(kind, pattern)
+);
+
+INSERT OR IGNORE INTO index_exclusions (id, kind, pattern, is_default) VALUES
+    ('default-hidden-folders', 'glob', '.*/', 1),
+    ('default-node-modules', 'glob', 'node_modules/', 1),
+    ('default-target', 'glob', 'target/', 1),
+    ('default-dist', 'glob', 'dist/', 1),
+    ('default-build', 'glob', 'build/', 1),
+    ('default-coverage', 'glob', 'coverage/', 1),
+    ('default-pycache', 'glob', '__pycache__/', 1),
+    ('default-vendor', 'glob', 'vendor/', 1),
+    ('default-tmp', 'glob', 'tmp/', 1),
+    ('default-temp', 'glob', 'temp/', 1),
+    ('default-cache', 'glob', 'cache/', 1),
+    ('default-caches', 'glob', 'caches/', 1),
+    ('default-appdata', 'glob', 'appdata/', 1),
+    ('default-system-volume-information', 'glob', 'system volume information/', 1),
+    ('default-recycle-bin', 'glob', '$recycle.bin/', 1),
+    ('default-venv', 'glob', 'venv/', 1),
+    ('default-env', 'glob', 'env/', 1);
+"#;
+
+// Trigram full-text index over the columns search matches substrings in. It
+// borrows its content from metadata_index by rowid, and the triggers keep it
+// in step with every insert, upsert and delete. Nothing in Frogger runs
+// VACUUM, which could renumber those rowids.
+const V5_SCHEMA: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS metadata_search USING fts5(
+    path,
+    search_text,
+    content='metadata_index',
+    content_rowid='rowid',
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS metadata_search_after_insert AFTER INSERT ON metadata_index BEGIN
+    INSERT INTO metadata_search (rowid, path, search_text)
+    VALUES (new.rowid, new.path, new.search_text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS metadata_search_after_delete AFTER DELETE ON metadata_index BEGIN
+    INSERT INTO metadata_search (metadata_search, rowid, path, search_text)
+    VALUES ('delete', old.rowid, old.path, old.search_text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS metadata_search_after_update
+AFTER UPDATE OF path, search_text ON metadata_index BEGIN
+    INSERT INTO metadata_search (metadata_search, rowid, path, search_text)
+    VALUES ('delete', old.rowid, old.path, old.search_text);
+    INSERT INTO metadata_search (rowid, path, search_text)
+    VALUES (new.rowid, new.path, new.search_text);
+END;
+
+INSERT INTO metadata_search (metadata_search) VALUES ('rebuild');
+"#;
+
+// Smart folders keep the query text rather than compiled filters, so relative
+// dates such as "modified:7d" are re-read every time the folder opens.
+const V6_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS smart_folders (
+    id TEXT PRIMARY KEY,
+    label TEXT NOT NULL,
+    query TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+INSERT OR IGNORE INTO sidebar_sections (id, visible, position)
+SELECT 'smartFolders', 1, COALESCE(MAX(position), -1) + 1 FROM sidebar_sections;
+"#;
+
+// Opens from search results are counted apart from other opens so frecency
+// can weigh them as an explicit ranking signal.
+const V7_SCHEMA: &str = r#"
+ALTER TABLE recents ADD COLUMN search_open_count INTEGER NOT NULL DEFAULT 0;
+"#;
+
+// Opt-in document text for content search. Rows hang off metadata_index by
+// path and are dropped with it, so roots and exclusions apply to content too.
+// Words rather than trigrams are indexed: documents are long and people
+// search them for words, not fragments of file names.
+const V8_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS content_index (
+    path TEXT PRIMARY KEY,
+    size INTEGER,
+    modified_at TEXT,
+    indexed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    body TEXT NOT NULL
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS content_search USING fts5(
+    body,
+    content='content_index',
+    content_rowid='rowid',
+    tokenize='unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS content_search_after_insert AFTER INSERT ON content_index BEGIN
+    INSERT INTO content_search (rowid, body) VALUES (new.rowid, new.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_search_after_delete AFTER DELETE ON content_index BEGIN
+    INSERT INTO content_search (content_search, rowid, body)
+    VALUES ('delete', old.rowid, old.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_search_after_update
+AFTER UPDATE OF body ON content_index BEGIN
+    INSERT INTO content_search (content_search, rowid, body)
+    VALUES ('delete', old.rowid, old.body);
+    INSERT INTO content_search (rowid, body) VALUES (new.rowid, new.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_index_after_metadata_delete
+AFTER DELETE ON metadata_index BEGIN
+    DELETE FROM content_index WHERE path = old.path;
+END;
+
+INSERT OR IGNORE INTO settings (key, value) VALUES ('search.contentIndexing', 'false');
+"#;
+
+const V9_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS search_history (
+    id TEXT PRIMARY KEY,
+    query TEXT NOT NULL UNIQUE,
+    last_used_at TEXT NOT NULL,
+    use_count INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_history_last_used ON search_history(last_used_at DESC);
+CREATE INDEX IF NOT EXISTS idx_metadata_extension ON metadata_index(extension);
+"#;
+
+// Opt-in EXIF details for photos, kept beside metadata_index like document
+// text so the walker never has to open image files.
+const V10_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS photo_metadata (
+    path TEXT PRIMARY KEY,
+    size INTEGER,
+    modified_at TEXT,
+    indexed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    captured_at TEXT,
+    camera TEXT,
+    lens TEXT,
+    width INTEGER,
+    height INTEGER,
+    has_gps INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_photo_metadata_captured_at ON photo_metadata(captured_at);
+
+CREATE TRIGGER IF NOT EXISTS photo_metadata_after_metadata_delete
+AFTER DELETE ON metadata_index BEGIN
+    DELETE FROM photo_metadata WHERE path = old.path;
+END;
+
+INSERT OR IGNORE INTO settings (key, value) VALUES ('search.photoMetadataIndexing', 'false');
+"#;
+
+// Thumbnail file sizes, so the cache can be held to a byte budget without
+// walking the cache directory. Existing rows start at 0 and are measured on
+// the next eviction pass.
+const V11_SCHEMA: &str = r#"
+ALTER TABLE thumbnail_metadata ADD COLUMN byte_size INTEGER NOT NULL DEFAULT 0;
+
+INSERT OR IGNORE INTO settings (key, value) VALUES ('thumbnails.cacheBudgetBytes', '268435456');
+"#;
+
+// Roots the last completed run walked, so a root added since then is not
+// trusted for folder search before it has been indexed. A ready index keeps
+// the roots its checkpoint recorded.
+const V12_SCHEMA: &str = r#"
+ALTER TABLE index_state ADD COLUMN covered_roots_json TEXT NOT NULL DEFAULT '[]';
+
+UPDATE index_state
+SET covered_roots_json = coalesce(json_extract(checkpoint_json, '$.roots'), '[]')
+WHERE status = 'ready' AND json_valid(checkpoint_json);
+"#;
+
+/// Builds `metadata_index` rows for tests that need search data without
+/// running the indexer.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use rusqlite::{params, Connection};
+
+    #[derive(Debug, Clone, Copy, Default)]
+    pub(crate) struct TestMetadataRow<'a> {
+        pub path: &'a str,
+        pub parent_path: &'a str,
+        pub name: &'a str,
+        pub display_name: &'a str,
+        pub kind: &'a str,
+        pub is_dir: bool,
+        pub size: Option<i64>,
+        pub modified_at: Option<&'a str>,
+        pub extension: Option<&'a str>,
+        pub search_text: &'a str,
+    }
+
+    impl<'a> TestMetadataRow<'a> {
+        /// A file in `/tmp` whose names and search text are its whole path.
+        pub(crate) fn file(path: &'a str, kind: &'a str) -> Self {
+            Self {
+                path,
+                parent_path: "/tmp",
+                name: path,
+                display_name: path,
+                kind,
+                search_text: path,
+                ..Self::default()
+            }
+        }
+    }
+
+    pub(crate) fn insert_metadata_row(conn: &Connection, row: TestMetadataRow<'_>) {
+        conn.execute(
+            "INSERT INTO metadata_index (
+                path, parent_path, name, display_name, kind, is_dir, size, modified_at,
+                extension, search_text
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                row.path,
+                row.parent_path,
+                row.name,
+                row.display_name,
+                row.kind,
+                row.is_dir,
+                row.size,
+                row.modified_at,
+                row.extension,
+                row.search_text,
+            ],
+        )
+        .expect("metadata row should insert");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -248,6 +646,16 @@ mod tests {
             "index_state",
             "thumbnail_metadata",
             "activity_failures",
+            "operation_journal",
+            "trash_records",
+            "index_roots",
+            "index_exclusions",
+            "metadata_search",
+            "smart_folders",
+            "content_index",
+            "content_search",
+            "search_history",
+            "photo_metadata",
         ];
 
         for table in tables {
@@ -275,7 +683,7 @@ mod tests {
                 row.get(0)
             })
             .expect("migration count should be readable");
-        assert_eq!(migration_count, 1);
+        assert_eq!(migration_count, CURRENT_SCHEMA_VERSION);
 
         let version: i64 = conn
             .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| {
@@ -363,4 +771,69 @@ mod tests {
 
         std::fs::remove_file(path).ok();
     }
+
+    #[test]
+    fn metadata_search_tracks_metadata_index_changes() {
+        let path = test_database_path();
+        let conn = open_database(&path).expect("database should open and migrate");
+        let matches = |query: &str| -> Vec<String> {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT m.path FROM metadata_search s
+                     JOIN metadata_index m ON m.rowid = s.rowid
+                     WHERE metadata_search MATCH ?1
+                     ORDER BY m.path",
+                )
+                .expect("search should prepare");
+            stmt.query_map([query], |row| row.get(0))
+                .expect("search should run")
+                .collect::<rusqlite::Result<Vec<String>>>()
+                .expect("search rows should read")
+        };
+
+        conn.execute(
+            "INSERT INTO metadata_index (
+                path, parent_path, name, display_name, kind, is_dir, search_text
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                "/Users/example/Quarterly.txt",
+                "/Users/example",
+                "Quarterly.txt",
+                "Quarterly",
+                "Text Document",
+                0_i64,
+                "quarterly.txt quarterly /users/example text document txt"
+            ],
+        )
+        .expect("metadata row should insert");
+        assert_eq!(matches("\"arterl\""), vec!["/Users/example/Quarterly.txt"]);
+        assert_eq!(
+            matches("\"example/qua\""),
+            vec!["/Users/example/Quarterly.txt"]
+        );
+
+        conn.execute(
+            "UPDATE metadata_index SET search_text = 'annual.txt annual text document txt'
+             WHERE path = '/Users/example/Quarterly.txt'",
+            [],
+        )
+        .expect("metadata row should update");
+        assert_eq!(matches("\"annual\""), vec!["/Users/example/Quarterly.txt"]);
+        assert_eq!(
+            matches("\"text doc\""),
+            vec!["/Users/example/Quarterly.txt"]
+        );
+        assert!(matches("\"arterly /users\"").is_empty());
+
+        conn.execute("DELETE FROM metadata_index", [])
+            .expect("metadata rows should delete");
+        assert!(matches("\"annual\"").is_empty());
+        conn.execute(
+            "INSERT INTO metadata_search (metadata_search) VALUES ('integrity-check')",
+            [],
+        )
+        .expect("full-text index should match its content table");
+
+        std::fs::remove_file(path).ok();
+    }
 }
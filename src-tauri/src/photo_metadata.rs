@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use exif::{Exif, In, Tag, Value};
+use image::metadata::Orientation;
+use image::DynamicImage;
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub const PHOTO_METADATA_INDEXING_SETTING: &str = "search.photoMetadataIndexing";
+
+/// How many photos one [`sync`] call reads at most.
+pub const SYNC_BATCH_SIZE: usize = 64;
+
+/// Extensions whose containers can carry EXIF.
+const EXIF_EXTENSIONS: &[&str] = &["jpg", "jpeg", "tif", "tiff", "heic", "heif", "png", "webp"];
+
+/// What a camera recorded about a photo. Every field is optional because
+/// screenshots, edited exports and scans each keep a different subset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PhotoMetadata {
+    /// The EXIF orientation, 1 through 8.
+    pub orientation: Option<u8>,
+    pub camera: Option<String>,
+    pub lens: Option<String>,
+    pub captured_at: Option<DateTime<Utc>>,
+    /// Pixel dimensions as stored, before orientation is applied.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub has_gps: bool,
+}
+
+impl PhotoMetadata {
+    /// Whether showing the photo upright swaps its width and height.
+    pub fn is_transposed(&self) -> bool {
+        matches!(self.orientation, Some(5..=8))
+    }
+
+    /// Adds the fields to preview metadata. Dimensions are reported upright.
+    pub fn insert_into(&self, metadata: &mut BTreeMap<String, String>) {
+        if let Some(camera) = &self.camera {
+            metadata.insert("camera".to_string(), camera.clone());
+        }
+        if let Some(lens) = &self.lens {
+            metadata.insert("lens".to_string(), lens.clone());
+        }
+        if let Some(captured_at) = self.captured_at {
+            metadata.insert("capturedAt".to_string(), captured_at.to_rfc3339());
+        }
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            let (width, height) = if self.is_transposed() {
+                (height, width)
+            } else {
+                (width, height)
+            };
+            metadata.insert("width".to_string(), width.to_string());
+            metadata.insert("height".to_string(), height.to_string());
+        }
+        metadata.insert("hasGps".to_string(), self.has_gps.to_string());
+    }
+}
+
+/// Reads EXIF from a JPEG, TIFF, HEIF, PNG or WebP file. Returns `None` when
+/// the file has no EXIF or is some other format.
+pub fn read(path: &Path) -> Result<Option<PhotoMetadata>> {
+    let mut reader = BufReader::new(std::fs::File::open(path)?);
+    let exif = match exif::Reader::new().read_from_container(&mut reader) {
+        Ok(exif) => exif,
+        Err(exif::Error::Io(error)) => return Err(error.into()),
+        Err(_) => return Ok(None),
+    };
+
+    let uint = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0))
+    };
+    let make = ascii_field(&exif, Tag::Make);
+    let model = ascii_field(&exif, Tag::Model);
+    let camera = match (make, model) {
+        // Most models already start with the brand, as in "Canon EOS R6".
+        (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => {
+            Some(model)
+        }
+        (Some(make), Some(model)) => Some(format!("{make} {model}")),
+        (make, model) => model.or(make),
+    };
+
+    Ok(Some(PhotoMetadata {
+        orientation: uint(Tag::Orientation)
+            .and_then(|value| u8::try_from(value).ok())
+            .filter(|value| (1..=8).contains(value)),
+        camera,
+        lens: ascii_field(&exif, Tag::LensModel).or_else(|| ascii_field(&exif, Tag::LensMake)),
+        captured_at: captured_at(&exif),
+        width: uint(Tag::PixelXDimension).or_else(|| uint(Tag::ImageWidth)),
+        height: uint(Tag::PixelYDimension).or_else(|| uint(Tag::ImageLength)),
+        has_gps: exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_some(),
+    }))
+}
+
+/// Turns a decoded image upright according to its EXIF orientation.
+pub fn apply_orientation(image: &mut DynamicImage, orientation: Option<u8>) {
+    if let Some(orientation) = orientation.and_then(Orientation::from_exif) {
+        image.apply_orientation(orientation);
+    }
+}
+
+/// The moment the shutter fired. EXIF stores wall-clock time and only newer
+/// cameras add the offset, so times without one are taken as local time.
+fn captured_at(exif: &Exif) -> Option<DateTime<Utc>> {
+    let (time_tag, offset_tag) = [
+        (Tag::DateTimeOriginal, Tag::OffsetTimeOriginal),
+        (Tag::DateTimeDigitized, Tag::OffsetTimeDigitized),
+        (Tag::DateTime, Tag::OffsetTime),
+    ]
+    .into_iter()
+    .find(|(tag, _)| exif.get_field(*tag, In::PRIMARY).is_some())?;
+
+    let Value::Ascii(values) = &exif.get_field(time_tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let mut stamp = exif::DateTime::from_ascii(values.first()?).ok()?;
+    if let Some(Value::Ascii(offsets)) = exif
+        .get_field(offset_tag, In::PRIMARY)
+        .map(|field| &field.value)
+    {
+        if let Some(offset) = offsets.first() {
+            stamp.parse_offset(offset).ok();
+        }
+    }
+
+    let naive = NaiveDate::from_ymd_opt(
+        i32::from(stamp.year),
+        u32::from(stamp.month),
+        u32::from(stamp.day),
+    )?
+    .and_hms_opt(
+        u32::from(stamp.hour),
+        u32::from(stamp.minute),
+        u32::from(stamp.second),
+    )?;
+    let captured = match stamp.offset {
+        Some(minutes) => FixedOffset::east_opt(i32::from(minutes) * 60)?
+            .from_local_datetime(&naive)
+            .single()?
+            .with_timezone(&Utc),
+        None => Local
+            .from_local_datetime(&naive)
+            .earliest()?
+            .with_timezone(&Utc),
+    };
+    Some(captured)
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    let Value::Ascii(values) = &exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let text = String::from_utf8_lossy(values.first()?);
+    let text = text.trim_matches(|value: char| value == '\0' || value.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+pub fn is_enabled(conn: &Connection) -> Result<bool> {
+    let value = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [PHOTO_METADATA_INDEXING_SETTING],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+    Ok(value.as_deref() == Some("true"))
+}
+
+/// Persists the opt-in. Turning it off forgets every indexed photo so
+/// `taken:` filters stop matching; turning it on leaves the backfill to
+/// [`sync`].
+pub fn set_enabled(conn: &mut Connection, enabled: bool) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO settings (key, value, updated_at)
+         VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+         ON CONFLICT(key) DO UPDATE SET
+            value = excluded.value,
+            updated_at = excluded.updated_at",
+        params![PHOTO_METADATA_INDEXING_SETTING, enabled.to_string()],
+    )?;
+    if !enabled {
+        tx.execute("DELETE FROM photo_metadata", [])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Reads EXIF from up to `limit` photos whose row is missing or older than
+/// their metadata row, and returns how many it visited. Photos without EXIF
+/// or that cannot be read get an empty row so they are not retried until
+/// they change.
+pub fn sync(conn: &mut Connection, limit: usize) -> Result<usize> {
+    if !is_enabled(conn)? {
+        return Ok(0);
+    }
+
+    let candidates = {
+        let extensions = EXIF_EXTENSIONS
+            .iter()
+            .map(|extension| format!("'{extension}'"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT metadata_index.path, metadata_index.size, metadata_index.modified_at
+             FROM metadata_index
+             LEFT JOIN photo_metadata ON photo_metadata.path = metadata_index.path
+             WHERE metadata_index.is_dir = 0
+               AND lower(metadata_index.extension) IN ({extensions})
+               AND (photo_metadata.path IS NULL
+                    OR photo_metadata.size IS NOT metadata_index.size
+                    OR photo_metadata.modified_at IS NOT metadata_index.modified_at)
+             LIMIT ?1"
+        ))?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO photo_metadata (
+                path, size, modified_at, indexed_at, captured_at, camera, lens, width, height,
+                has_gps
+             ) VALUES (
+                ?1, ?2, ?3, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), ?4, ?5, ?6, ?7, ?8, ?9
+             )
+             ON CONFLICT(path) DO UPDATE SET
+                size = excluded.size,
+                modified_at = excluded.modified_at,
+                indexed_at = excluded.indexed_at,
+                captured_at = excluded.captured_at,
+                camera = excluded.camera,
+                lens = excluded.lens,
+                width = excluded.width,
+                height = excluded.height,
+                has_gps = excluded.has_gps",
+        )?;
+        for (path, size, modified_at) in &candidates {
+            let photo = match read(Path::new(path)) {
+                Ok(photo) => photo.unwrap_or_default(),
+                Err(_error) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("[frogger] failed to read photo metadata of {path}: {_error:?}");
+                    PhotoMetadata::default()
+                }
+            };
+            stmt.execute(params![
+                path,
+                size,
+                modified_at,
+                photo
+                    .captured_at
+                    .map(|captured_at| captured_at.to_rfc3339()),
+                photo.camera,
+                photo.lens,
+                photo.width,
+                photo.height,
+                photo.has_gps
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(candidates.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalization::SearchNormalization;
+    use crate::persistence::{
+        self,
+        test_support::{insert_metadata_row, TestMetadataRow},
+    };
+    use crate::search_query::SearchQuery;
+    use exif::Field;
+    use rusqlite::params_from_iter;
+    use std::io::Cursor;
+
+    /// Splices an EXIF segment into a JPEG right after its start marker.
+    fn jpeg_with_exif(jpeg: &[u8], fields: &[Field]) -> Vec<u8> {
+        let mut writer = exif::experimental::Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).expect("exif should encode");
+        let tiff = tiff.into_inner();
+
+        let mut output = jpeg[..2].to_vec();
+        output.extend_from_slice(&[0xff, 0xe1]);
+        output.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        output.extend_from_slice(b"Exif\0\0");
+        output.extend_from_slice(&tiff);
+        output.extend_from_slice(&jpeg[2..]);
+        output
+    }
+
+    fn ascii(tag: Tag, value: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        }
+    }
+
+    fn short(tag: Tag, value: u16) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Short(vec![value]),
+        }
+    }
+
+    fn plain_jpeg() -> Vec<u8> {
+        let mut jpeg = Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(8, 4, image::Rgb([90, 90, 90]))
+            .write_to(&mut jpeg, image::ImageFormat::Jpeg)
+            .expect("jpeg should encode");
+        jpeg.into_inner()
+    }
+
+    #[test]
+    fn reads_camera_lens_capture_time_and_gps() {
+        let temp = tempfile::tempdir().expect("tempdir should exist");
+        let path = temp.path().join("IMG_0001.jpg");
+        let fields = [
+            ascii(Tag::Make, "Canon"),
+            ascii(Tag::Model, "Canon EOS R6"),
+            ascii(Tag::LensModel, "RF24-105mm F4 L IS USM"),
+            ascii(Tag::DateTimeOriginal, "2024:07:04 18:30:00"),
+            ascii(Tag::OffsetTimeOriginal, "-04:00"),
+            short(Tag::Orientation, 6),
+            short(Tag::PixelXDimension, 6000),
+            short(Tag::PixelYDimension, 4000),
+            Field {
+                tag: Tag::GPSLatitude,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![(40, 1).into(), (42, 1).into(), (46, 1).into()]),
+            },
+        ];
+        std::fs::write(&path, jpeg_with_exif(&plain_jpeg(), &fields)).expect("file should write");
+
+        let photo = read(&path)
+            .expect("photo should read")
+            .expect("photo should have exif");
+        assert_eq!(photo.camera.as_deref(), Some("Canon EOS R6"));
+        assert_eq!(photo.lens.as_deref(), Some("RF24-105mm F4 L IS USM"));
+        assert_eq!(
+            photo.captured_at,
+            Some(Utc.with_ymd_and_hms(2024, 7, 4, 22, 30, 0).unwrap())
+        );
+        assert_eq!(photo.orientation, Some(6));
+        assert!(photo.has_gps);
+
+        let mut metadata = BTreeMap::new();
+        photo.insert_into(&mut metadata);
+        assert_eq!(metadata.get("width").map(String::as_str), Some("4000"));
+        assert_eq!(metadata.get("height").map(String::as_str), Some("6000"));
+        assert_eq!(metadata.get("hasGps").map(String::as_str), Some("true"));
+
+        let plain = temp.path().join("plain.jpg");
+        std::fs::write(&plain, plain_jpeg()).expect("file should write");
+        assert_eq!(read(&plain).expect("photo should read"), None);
+    }
+
+    #[test]
+    fn sync_indexes_capture_dates_only_while_enabled() {
+        let mut conn = persistence::open_memory_database().expect("database should migrate");
+        let temp = tempfile::tempdir().expect("tempdir should exist");
+        let path = temp.path().join("beach.jpg");
+        let fields = [
+            ascii(Tag::DateTimeOriginal, "2023:08:12 09:15:00"),
+            ascii(Tag::OffsetTimeOriginal, "+00:00"),
+        ];
+        std::fs::write(&path, jpeg_with_exif(&plain_jpeg(), &fields)).expect("file should write");
+        insert_metadata_row(
+            &conn,
+            TestMetadataRow {
+                path: &path.to_string_lossy(),
+                parent_path: &temp.path().to_string_lossy(),
+                name: "beach.jpg",
+                display_name: "beach.jpg",
+                kind: "Image",
+                extension: Some("jpg"),
+                search_text: "beach.jpg",
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            sync(&mut conn, SYNC_BATCH_SIZE).expect("sync should run"),
+            0
+        );
+        set_enabled(&mut conn, true).expect("setting should save");
+        assert_eq!(
+            sync(&mut conn, SYNC_BATCH_SIZE).expect("sync should run"),
+            1
+        );
+        assert_eq!(
+            sync(&mut conn, SYNC_BATCH_SIZE).expect("sync should run"),
+            0
+        );
+
+        let captured_at: Option<String> = conn
+            .query_row("SELECT captured_at FROM photo_metadata", [], |row| {
+                row.get(0)
+            })
+            .expect("photo row should exist");
+        assert_eq!(captured_at.as_deref(), Some("2023-08-12T09:15:00+00:00"));
+
+        let matches = |query: &str| -> i64 {
+            let compiled =
+                SearchQuery::parse(query, None, Local::now(), SearchNormalization::default())
+                    .expect("query should parse")
+                    .compile();
+            conn.query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM metadata_index WHERE {}",
+                    compiled.clauses.join(" AND ")
+                ),
+                params_from_iter(compiled.params),
+                |row| row.get(0),
+            )
+            .expect("filter should run")
+        };
+        assert_eq!(matches("taken:2023-08"), 1);
+        assert_eq!(matches("taken:2024"), 0);
+
+        set_enabled(&mut conn, false).expect("setting should save");
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM photo_metadata", [], |row| row.get(0))
+            .expect("count should read");
+        assert_eq!(remaining, 0);
+    }
+}
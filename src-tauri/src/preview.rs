@@ -0,0 +1,355 @@
+use crate::models::{PreviewRenderer, TextEncoding};
+
+/// How much of a file is read to recognise its format.
+pub const SNIFF_BYTES: usize = 512;
+
+/// How much of a file the text renderer gets unless it asks for more.
+pub const DEFAULT_TEXT_PREVIEW_BYTES: usize = 64 * 1024;
+pub const MAX_TEXT_PREVIEW_BYTES: usize = 1024 * 1024;
+
+/// A format recognised from the leading bytes of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSignature {
+    pub renderer: PreviewRenderer,
+    pub format: &'static str,
+    pub mime_type: &'static str,
+}
+
+const fn signature(
+    renderer: PreviewRenderer,
+    format: &'static str,
+    mime_type: &'static str,
+) -> FileSignature {
+    FileSignature {
+        renderer,
+        format,
+        mime_type,
+    }
+}
+
+/// Recognises a file from its first bytes. Extensions are not consulted, so
+/// a renamed or extensionless file still previews as what it really is.
+pub fn sniff(header: &[u8]) -> Option<FileSignature> {
+    use PreviewRenderer::{Audio, FallbackMetadata, Image, Pdf, Video};
+
+    let starts = |magic: &[u8]| header.starts_with(magic);
+    let riff = |form: &[u8]| header.len() >= 12 && starts(b"RIFF") && &header[8..12] == form;
+
+    let found = if starts(b"\x89PNG\r\n\x1a\n") {
+        signature(Image, "PNG", "image/png")
+    } else if starts(b"\xff\xd8\xff") {
+        signature(Image, "JPEG", "image/jpeg")
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        signature(Image, "GIF", "image/gif")
+    } else if riff(b"WEBP") {
+        signature(Image, "WebP", "image/webp")
+    } else if starts(b"BM") && header.len() >= 14 {
+        signature(Image, "BMP", "image/bmp")
+    } else if starts(b"II*\0") || starts(b"MM\0*") {
+        signature(Image, "TIFF", "image/tiff")
+    } else if starts(b"\0\0\x01\0") {
+        signature(Image, "ICO", "image/x-icon")
+    } else if starts(b"%PDF-") {
+        signature(Pdf, "PDF", "application/pdf")
+    } else if riff(b"WAVE") {
+        signature(Audio, "WAV", "audio/wav")
+    } else if riff(b"AVI ") {
+        signature(Video, "AVI", "video/x-msvideo")
+    } else if starts(b"fLaC") {
+        signature(Audio, "FLAC", "audio/flac")
+    } else if starts(b"OggS") {
+        signature(Audio, "Ogg", "audio/ogg")
+    } else if starts(b"ID3") || is_mp3_frame(header) {
+        signature(Audio, "MP3", "audio/mpeg")
+    } else if starts(b"\x1a\x45\xdf\xa3") {
+        if contains(header, b"webm") {
+            signature(Video, "WebM", "video/webm")
+        } else {
+            signature(Video, "Matroska", "video/x-matroska")
+        }
+    } else if header.len() >= 12 && &header[4..8] == b"ftyp" {
+        match &header[8..12] {
+            b"M4A " | b"M4B " => signature(Audio, "MPEG-4 Audio", "audio/mp4"),
+            b"heic" | b"heix" | b"mif1" | b"msf1" => signature(Image, "HEIC", "image/heic"),
+            b"qt  " => signature(Video, "QuickTime", "video/quicktime"),
+            _ => signature(Video, "MPEG-4", "video/mp4"),
+        }
+    } else if starts(b"PK\x03\x04") {
+        signature(FallbackMetadata, "ZIP", "application/zip")
+    } else if is_svg(header) {
+        signature(Image, "SVG", "image/svg+xml")
+    } else {
+        return None;
+    };
+    Some(found)
+}
+
+/// Whether the header looks like text rather than binary data. A byte-order
+/// mark always counts as text; otherwise any NUL byte means binary.
+pub fn looks_like_text(header: &[u8]) -> bool {
+    if header.starts_with(b"\xef\xbb\xbf")
+        || header.starts_with(b"\xff\xfe")
+        || header.starts_with(b"\xfe\xff")
+    {
+        return true;
+    }
+    !header.contains(&0)
+}
+
+/// Text decoded from the head of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    pub content: String,
+    pub encoding: Option<TextEncoding>,
+    pub is_binary: bool,
+}
+
+impl DecodedText {
+    fn binary() -> Self {
+        Self {
+            content: String::new(),
+            encoding: None,
+            is_binary: true,
+        }
+    }
+}
+
+/// Decodes `bytes`, the first bytes of a file. A byte-order mark decides
+/// between UTF-8 and UTF-16; without one, valid UTF-8 wins and anything else
+/// is read as Latin-1. When `truncated` is set, a character cut in half at
+/// the end is dropped instead of making the whole sample invalid.
+pub fn decode_text(bytes: &[u8], truncated: bool) -> DecodedText {
+    if let Some(rest) = bytes.strip_prefix(b"\xef\xbb\xbf") {
+        return decode_utf8(rest, truncated).unwrap_or_else(DecodedText::binary);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xff\xfe") {
+        return decode_utf16(rest, TextEncoding::Utf16Le, truncated);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xfe\xff") {
+        return decode_utf16(rest, TextEncoding::Utf16Be, truncated);
+    }
+    if bytes.contains(&0) {
+        return DecodedText::binary();
+    }
+
+    let decoded = decode_utf8(bytes, truncated).unwrap_or_else(|| DecodedText {
+        content: bytes.iter().map(|byte| char::from(*byte)).collect(),
+        encoding: Some(TextEncoding::Latin1),
+        is_binary: false,
+    });
+    if is_mostly_control(&decoded.content) {
+        return DecodedText::binary();
+    }
+    decoded
+}
+
+fn decode_utf8(bytes: &[u8], truncated: bool) -> Option<DecodedText> {
+    let content = match std::str::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(error) if truncated && error.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..error.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    Some(DecodedText {
+        content: content.to_string(),
+        encoding: Some(TextEncoding::Utf8),
+        is_binary: false,
+    })
+}
+
+fn decode_utf16(bytes: &[u8], encoding: TextEncoding, truncated: bool) -> DecodedText {
+    let mut units = bytes
+        .chunks_exact(2)
+        .map(|pair| match encoding {
+            TextEncoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+            _ => u16::from_le_bytes([pair[0], pair[1]]),
+        })
+        .collect::<Vec<_>>();
+    if truncated
+        && units
+            .last()
+            .is_some_and(|unit| (0xd800..0xdc00).contains(unit))
+    {
+        units.pop();
+    }
+    DecodedText {
+        content: char::decode_utf16(units)
+            .map(|value| value.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect(),
+        encoding: Some(encoding),
+        is_binary: false,
+    }
+}
+
+/// Binary data that happens to avoid NUL bytes still shows up as a run of
+/// control characters. Tabs, line breaks, form feeds and escapes are normal
+/// in text and logs, so they do not count.
+fn is_mostly_control(content: &str) -> bool {
+    let mut total = 0;
+    let mut control = 0;
+    for value in content.chars() {
+        total += 1;
+        if value.is_control() && !matches!(value, '\t' | '\n' | '\r' | '\x0c' | '\x1b') {
+            control += 1;
+        }
+    }
+    control * 10 > total
+}
+
+/// The `%PDF-x.y` version from a PDF header.
+pub fn pdf_version(header: &[u8]) -> Option<String> {
+    let rest = header.strip_prefix(b"%PDF-")?;
+    let version = rest
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit() || **byte == b'.')
+        .map(|byte| char::from(*byte))
+        .collect::<String>();
+    (!version.is_empty()).then_some(version)
+}
+
+/// Stream details from the `fmt ` chunk of a WAV header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavFormat {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub byte_rate: u32,
+}
+
+pub fn wav_format(header: &[u8]) -> Option<WavFormat> {
+    let mut offset = 12;
+    while offset + 8 <= header.len() {
+        let id = &header[offset..offset + 4];
+        let size = u32::from_le_bytes(header[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let body = offset + 8;
+        if id == b"fmt " {
+            let chunk = header.get(body..body + 16)?;
+            return Some(WavFormat {
+                channels: u16::from_le_bytes([chunk[2], chunk[3]]),
+                sample_rate: u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+                byte_rate: u32::from_le_bytes([chunk[8], chunk[9], chunk[10], chunk[11]]),
+                bits_per_sample: u16::from_le_bytes([chunk[14], chunk[15]]),
+            });
+        }
+        offset = body + size + size % 2;
+    }
+    None
+}
+
+/// The language name shown for a source or markup file, by extension.
+pub fn language_for(extension: &str) -> Option<&'static str> {
+    let language = match extension.to_ascii_lowercase().as_str() {
+        "rs" => "Rust",
+        "ts" | "tsx" => "TypeScript",
+        "js" | "jsx" => "JavaScript",
+        "json" => "JSON",
+        "html" => "HTML",
+        "css" => "CSS",
+        "scss" => "SCSS",
+        "md" | "markdown" => "Markdown",
+        _ => return None,
+    };
+    Some(language)
+}
+
+fn is_svg(header: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(header);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    (text.starts_with("<?xml") || text.starts_with("<svg") || text.starts_with("<!--"))
+        && text.contains("<svg")
+}
+
+/// A bare MPEG layer III frame sync. Checking the layer bits keeps UTF-16
+/// little-endian byte-order marks from passing as audio.
+fn is_mp3_frame(header: &[u8]) -> bool {
+    header.len() >= 2 && header[0] == 0xff && header[1] & 0xe6 == 0xe2
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffing_reads_magic_bytes_not_extensions() {
+        let cases: [(&[u8], &str); 8] = [
+            (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "PNG"),
+            (b"\xff\xd8\xff\xe0\0\x10JFIF", "JPEG"),
+            (b"GIF89a\x01\0\x01\0", "GIF"),
+            (b"%PDF-1.7\n%\xe2\xe3", "PDF"),
+            (b"RIFF\x24\0\0\0WAVEfmt ", "WAV"),
+            (b"\0\0\0\x20ftypqt  \0\0\x02\0", "QuickTime"),
+            (b"\0\0\0\x1cftypM4A \0\0\0\0", "MPEG-4 Audio"),
+            (
+                b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\">",
+                "SVG",
+            ),
+        ];
+        for (header, format) in cases {
+            assert_eq!(sniff(header).map(|found| found.format), Some(format));
+        }
+        assert_eq!(sniff(b"hello world"), None);
+        assert_eq!(sniff(b"\xff\xfeh\0i\0"), None);
+        assert_eq!(pdf_version(b"%PDF-1.7\n"), Some("1.7".to_string()));
+    }
+
+    #[test]
+    fn decoding_detects_encodings_and_binary_data() {
+        let utf8 = decode_text("naïve\ncafé".as_bytes(), false);
+        assert_eq!(utf8.encoding, Some(TextEncoding::Utf8));
+        assert_eq!(utf8.content, "naïve\ncafé");
+
+        let cut = "café".as_bytes();
+        let truncated = decode_text(&cut[..cut.len() - 1], true);
+        assert_eq!(truncated.encoding, Some(TextEncoding::Utf8));
+        assert_eq!(truncated.content, "caf");
+
+        let mut utf16 = vec![0xff, 0xfe];
+        utf16.extend("héllo 🐸".encode_utf16().flat_map(u16::to_le_bytes));
+        let decoded = decode_text(&utf16[..utf16.len() - 2], true);
+        assert_eq!(decoded.encoding, Some(TextEncoding::Utf16Le));
+        assert_eq!(decoded.content, "héllo ");
+
+        let mut utf16_be = vec![0xfe, 0xff];
+        utf16_be.extend("hi".encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(decode_text(&utf16_be, false).content, "hi");
+
+        let latin1 = decode_text(b"caf\xe9 cr\xe8me", false);
+        assert_eq!(latin1.encoding, Some(TextEncoding::Latin1));
+        assert_eq!(latin1.content, "café crème");
+
+        assert!(decode_text(b"\x7fELF\x02\x01\x01\0\0", false).is_binary);
+        assert!(decode_text(b"\x01\x02\x03\x04abc\x05\x06", false).is_binary);
+        assert!(!decode_text(b"\x1b[31merror\x1b[0m\tdone\r\n", false).is_binary);
+    }
+
+    #[test]
+    fn text_detection_and_wav_headers() {
+        assert!(looks_like_text("plain café notes".as_bytes()));
+        assert!(looks_like_text(b"\xff\xfeh\0i\0"));
+        assert!(!looks_like_text(b"\x7fELF\x02\x01\x01\0"));
+
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+        wav.extend_from_slice(&1_u16.to_le_bytes());
+        wav.extend_from_slice(&2_u16.to_le_bytes());
+        wav.extend_from_slice(&44_100_u32.to_le_bytes());
+        wav.extend_from_slice(&176_400_u32.to_le_bytes());
+        wav.extend_from_slice(&4_u16.to_le_bytes());
+        wav.extend_from_slice(&16_u16.to_le_bytes());
+        assert_eq!(
+            wav_format(&wav),
+            Some(WavFormat {
+                channels: 2,
+                sample_rate: 44_100,
+                bits_per_sample: 16,
+                byte_rate: 176_400,
+            })
+        );
+    }
+}
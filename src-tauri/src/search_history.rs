@@ -0,0 +1,304 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+use crate::models::{SearchHistoryEntry, SearchSuggestion, SearchSuggestionSource};
+use crate::normalization::SearchNormalization;
+
+pub const LOCAL_ONLY_INDEXING_SETTING: &str = "privacy.localOnlyIndexing";
+
+/// Older queries are forgotten once history grows past this.
+const MAX_HISTORY_ENTRIES: i64 = 200;
+/// How many distinct kinds or extensions are offered as completions.
+const MAX_FIELD_VALUES: i64 = 30;
+
+/// History is part of the local index database, so it is only kept while the
+/// user has indexing set to stay on this machine.
+pub fn is_recording_allowed(conn: &Connection) -> Result<bool> {
+    let value = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [LOCAL_ONLY_INDEXING_SETTING],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+    Ok(value.is_none_or(|value| value == "true"))
+}
+
+/// Records a submitted query. Returns false when nothing was recorded because
+/// the query was blank or history is not allowed.
+pub fn record(conn: &Connection, query: &str, now: DateTime<Utc>) -> Result<bool> {
+    let query = query.trim();
+    if query.is_empty() || !is_recording_allowed(conn)? {
+        return Ok(false);
+    }
+
+    conn.execute(
+        "INSERT INTO search_history (id, query, last_used_at, use_count)
+         VALUES (?1, ?2, ?3, 1)
+         ON CONFLICT(query) DO UPDATE SET
+            last_used_at = excluded.last_used_at,
+            use_count = search_history.use_count + 1",
+        params![
+            format!("search-{}", Uuid::new_v4()),
+            query,
+            now.to_rfc3339()
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM search_history WHERE id NOT IN (
+            SELECT id FROM search_history ORDER BY last_used_at DESC LIMIT ?1
+         )",
+        [MAX_HISTORY_ENTRIES],
+    )?;
+    Ok(true)
+}
+
+pub fn list(conn: &Connection, limit: Option<usize>) -> Result<Vec<SearchHistoryEntry>> {
+    let limit = limit.map_or(MAX_HISTORY_ENTRIES, |limit| limit as i64);
+    let mut stmt = conn.prepare(
+        "SELECT id, query, last_used_at, use_count FROM search_history
+         ORDER BY last_used_at DESC, id
+         LIMIT ?1",
+    )?;
+    let rows = stmt.query_map([limit], |row| {
+        Ok(SearchHistoryEntry {
+            id: row.get(0)?,
+            query: row.get(1)?,
+            last_used_at: row.get(2)?,
+            use_count: row.get(3)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<()> {
+    conn.execute("DELETE FROM search_history WHERE id = ?1", [id])?;
+    Ok(())
+}
+
+pub fn clear(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM search_history", [])?;
+    Ok(())
+}
+
+/// Suggests completions for `input` as typed so far.
+///
+/// Earlier queries that start with the input come first. The word being typed
+/// is then completed as a `kind:` or `ext:` filter from values common in the
+/// index, or as an `in:` filter from favorite folder names. Every suggestion
+/// is the whole query with the last word replaced.
+pub fn suggestions(
+    conn: &Connection,
+    input: &str,
+    normalization: SearchNormalization,
+    limit: usize,
+) -> Result<Vec<SearchSuggestion>> {
+    let folded_input = normalization.fold(input.trim());
+    let (head, word) = match input.rfind(char::is_whitespace) {
+        Some(index) => input.split_at(index + 1),
+        None => ("", input),
+    };
+    let (negation, word) = match word.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", word),
+    };
+    let (field, partial) = match word.split_once(':') {
+        Some((field, value)) => (Some(field.to_ascii_lowercase()), value.trim_matches('"')),
+        None => (None, word),
+    };
+    let partial = normalization.fold(partial);
+
+    let mut suggestions = Vec::new();
+    if !folded_input.is_empty() && is_recording_allowed(conn)? {
+        for entry in list(conn, None)? {
+            let folded = normalization.fold(&entry.query);
+            if folded.starts_with(&folded_input) && folded != folded_input {
+                suggestions.push(SearchSuggestion {
+                    text: entry.query.clone(),
+                    label: entry.query,
+                    source: SearchSuggestionSource::History,
+                });
+            }
+        }
+    }
+
+    let complete =
+        |field: &str, value: &str, label: &str, source: SearchSuggestionSource| SearchSuggestion {
+            text: format!("{head}{negation}{field}:{}", quote_filter_value(value)),
+            label: label.to_string(),
+            source,
+        };
+    let plain_word = field.is_none() && partial.chars().count() >= 2;
+
+    if field.as_deref() == Some("kind") || plain_word {
+        for kind in common_values(conn, "kind")? {
+            let value = kind.to_lowercase();
+            if value != "folder" && normalization.fold(&value).starts_with(&partial) {
+                suggestions.push(complete(
+                    "kind",
+                    &value,
+                    &kind,
+                    SearchSuggestionSource::Kind,
+                ));
+            }
+        }
+    }
+    if field.as_deref() == Some("ext") || plain_word {
+        for extension in common_values(conn, "extension")? {
+            let value = extension.to_lowercase();
+            if normalization.fold(&value).starts_with(&partial) {
+                suggestions.push(complete(
+                    "ext",
+                    &value,
+                    &format!(".{value}"),
+                    SearchSuggestionSource::Extension,
+                ));
+            }
+        }
+    }
+    if field.as_deref() == Some("in") || plain_word {
+        let mut stmt =
+            conn.prepare("SELECT path, label FROM favorites ORDER BY position, label")?;
+        let favorites = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (path, label) in favorites {
+            if normalization.fold(&label).starts_with(&partial) {
+                suggestions.push(complete(
+                    "in",
+                    &path,
+                    &label,
+                    SearchSuggestionSource::Favorite,
+                ));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    suggestions.retain(|suggestion| seen.insert(suggestion.text.clone()));
+    suggestions.truncate(limit);
+    Ok(suggestions)
+}
+
+/// The most common values of `column`, most frequent first.
+fn common_values(conn: &Connection, column: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {column} FROM metadata_index
+         WHERE {column} IS NOT NULL AND {column} != ''
+         GROUP BY {column}
+         ORDER BY COUNT(*) DESC, {column}
+         LIMIT ?1"
+    ))?;
+    let rows = stmt.query_map([MAX_FIELD_VALUES], |row| row.get::<_, String>(0))?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+fn quote_filter_value(value: &str) -> String {
+    if value.chars().any(char::is_whitespace) {
+        format!("\"{value}\"")
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{
+        self,
+        test_support::{insert_metadata_row, TestMetadataRow},
+    };
+
+    fn texts(suggestions: &[SearchSuggestion]) -> Vec<&str> {
+        suggestions
+            .iter()
+            .map(|suggestion| suggestion.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn history_records_lists_and_forgets_queries() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        let earlier = Utc::now() - chrono::Duration::minutes(5);
+
+        assert!(record(&conn, "report kind:pdf", earlier).expect("query should record"));
+        assert!(record(&conn, "budget", Utc::now()).expect("query should record"));
+        assert!(record(&conn, "  report kind:pdf ", Utc::now()).expect("query should record"));
+        assert!(!record(&conn, "   ", Utc::now()).expect("blank query should be skipped"));
+
+        let entries = list(&conn, None).expect("history should list");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].query, "report kind:pdf");
+        assert_eq!(entries[0].use_count, 2);
+
+        delete(&conn, &entries[1].id).expect("entry should delete");
+        assert_eq!(list(&conn, None).expect("history should list").len(), 1);
+        clear(&conn).expect("history should clear");
+        assert!(list(&conn, None).expect("history should list").is_empty());
+
+        conn.execute(
+            "UPDATE settings SET value = 'false' WHERE key = ?1",
+            [LOCAL_ONLY_INDEXING_SETTING],
+        )
+        .expect("setting should update");
+        assert!(!record(&conn, "budget", Utc::now()).expect("record should run"));
+        assert!(list(&conn, None).expect("history should list").is_empty());
+    }
+
+    #[test]
+    fn suggestions_complete_history_filters_and_favorites() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        let normalization = SearchNormalization::default();
+        for (path, kind, extension) in [
+            ("/tmp/a.pdf", "PDF Document", "pdf"),
+            ("/tmp/b.pdf", "PDF Document", "pdf"),
+            ("/tmp/c.png", "Image", "png"),
+            ("/tmp/d.md", "Markdown Document", "md"),
+        ] {
+            insert_metadata_row(
+                &conn,
+                TestMetadataRow {
+                    extension: Some(extension),
+                    ..TestMetadataRow::file(path, kind)
+                },
+            );
+        }
+        conn.execute(
+            "INSERT INTO favorites (id, path, label, position)
+             VALUES ('favorite-1', '/Users/frog/Project Notes', 'Project Notes', 0)",
+            [],
+        )
+        .expect("favorite should insert");
+        record(&conn, "Résumé drafts", Utc::now()).expect("query should record");
+
+        let history = suggestions(&conn, "resume", normalization, 10).expect("suggest");
+        assert_eq!(texts(&history), vec!["Résumé drafts"]);
+        assert_eq!(history[0].source, SearchSuggestionSource::History);
+
+        let kinds = suggestions(&conn, "report kind:", normalization, 10).expect("suggest");
+        assert_eq!(
+            texts(&kinds),
+            vec![
+                "report kind:\"pdf document\"",
+                "report kind:image",
+                "report kind:\"markdown document\"",
+            ]
+        );
+
+        let extensions = suggestions(&conn, "-ext:p", normalization, 10).expect("suggest");
+        assert_eq!(texts(&extensions), vec!["-ext:pdf", "-ext:png"]);
+
+        let plain = suggestions(&conn, "notes pr", normalization, 10).expect("suggest");
+        assert_eq!(
+            texts(&plain),
+            vec!["notes in:\"/Users/frog/Project Notes\""]
+        );
+        assert_eq!(plain[0].label, "Project Notes");
+    }
+}
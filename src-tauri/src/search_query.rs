@@ -0,0 +1,676 @@
+use chrono::{DateTime, Duration, Local, Months, NaiveDate, TimeZone, Utc};
+use rusqlite::types::Value;
+
+use crate::errors::CommandError;
+use crate::normalization::SearchNormalization;
+
+/// A search box query split into free text and field filters.
+///
+/// Free text is what fuzzy ranking runs against. Quoted phrases must appear
+/// verbatim, `-term` excludes names containing the term, and `field:value`
+/// tokens for known fields become filters. Unknown fields stay free text so
+/// names like `notes:draft.txt` still search normally.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub phrases: Vec<String>,
+    pub excluded_terms: Vec<String>,
+    pub filters: Vec<QueryFilter>,
+    pub normalization: SearchNormalization,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryFilter {
+    pub negated: bool,
+    pub condition: FilterCondition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterCondition {
+    Kind(String),
+    Extension(Vec<String>),
+    Size(Comparison, u64),
+    Modified(TimeRange),
+    Created(TimeRange),
+    /// When a photo was taken, from indexed EXIF.
+    Taken(TimeRange),
+    InFolder(String),
+    IsFolder(bool),
+    Hidden(bool),
+    Alias,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+/// A half-open `[start, end)` range of instants; either end may be open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// SQL conditions over `metadata_index` columns with positional `?` params.
+#[derive(Debug, Default)]
+pub struct CompiledFilters {
+    pub clauses: Vec<String>,
+    pub params: Vec<Value>,
+}
+
+#[derive(Debug)]
+struct Token {
+    negated: bool,
+    quoted: bool,
+    field: Option<String>,
+    value: String,
+}
+
+const FIELDS: &[&str] = &[
+    "kind", "ext", "size", "modified", "created", "taken", "in", "is", "hidden",
+];
+
+impl SearchQuery {
+    /// Parses `query`, resolving `~` against `home_dir` and relative dates
+    /// against `now`. Free text is folded with `normalization` so it compares
+    /// against indexed text. Filters with values we cannot read are rejected
+    /// rather than silently matching everything.
+    pub fn parse(
+        query: &str,
+        home_dir: Option<&str>,
+        now: DateTime<Local>,
+        normalization: SearchNormalization,
+    ) -> Result<Self, CommandError> {
+        let mut words = Vec::new();
+        let mut phrases = Vec::new();
+        let mut excluded_terms = Vec::new();
+        let mut filters = Vec::new();
+
+        for token in tokenize(query) {
+            let Some(field) = token.field else {
+                let value = normalization.fold(token.value.trim());
+                if value.is_empty() {
+                    continue;
+                }
+                if token.negated {
+                    excluded_terms.push(value);
+                } else {
+                    if token.quoted {
+                        phrases.push(value.clone());
+                    }
+                    words.push(value);
+                }
+                continue;
+            };
+
+            let condition = parse_filter(&field, token.value.trim(), home_dir, now)?;
+            filters.push(QueryFilter {
+                negated: token.negated,
+                condition,
+            });
+        }
+
+        Ok(Self {
+            text: words.join(" "),
+            phrases,
+            excluded_terms,
+            filters,
+            normalization,
+        })
+    }
+
+    /// Exclusions alone would list the whole index, so they need free text or
+    /// a filter alongside them.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.filters.is_empty()
+    }
+
+    /// Compiles phrases, exclusions and filters. Free text is left to the
+    /// caller because it drives candidate selection and ranking.
+    pub fn compile(&self) -> CompiledFilters {
+        let mut compiled = CompiledFilters::default();
+
+        for phrase in &self.phrases {
+            let pattern = format!("%{}%", escape_like(phrase));
+            compiled.clauses.push(
+                "(search_text LIKE ? ESCAPE '\\' OR lower(path) LIKE ? ESCAPE '\\')".to_string(),
+            );
+            compiled.params.push(Value::Text(pattern.clone()));
+            compiled.params.push(Value::Text(pattern));
+        }
+
+        for term in &self.excluded_terms {
+            let pattern = format!("%{}%", escape_like(term));
+            compiled.clauses.push(
+                "NOT (lower(name) LIKE ? ESCAPE '\\' OR lower(display_name) LIKE ? ESCAPE '\\')"
+                    .to_string(),
+            );
+            compiled.params.push(Value::Text(pattern.clone()));
+            compiled.params.push(Value::Text(pattern));
+        }
+
+        for filter in &self.filters {
+            let clause = compile_condition(&filter.condition, &mut compiled.params);
+            compiled.clauses.push(if filter.negated {
+                format!("NOT coalesce(({clause}), 0)")
+            } else {
+                clause
+            });
+        }
+
+        compiled
+    }
+}
+
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    loop {
+        while chars
+            .next_if(|character| character.is_whitespace())
+            .is_some()
+        {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+
+        let mut negated = false;
+        if first == '-' {
+            chars.next();
+            match chars.peek() {
+                Some(next) if !next.is_whitespace() => negated = true,
+                _ => {
+                    tokens.push(Token {
+                        negated: false,
+                        quoted: false,
+                        field: None,
+                        value: "-".to_string(),
+                    });
+                    continue;
+                }
+            }
+        }
+
+        let mut raw = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let mut field_end = None;
+        while let Some(&character) = chars.peek() {
+            if character.is_whitespace() && !in_quotes {
+                break;
+            }
+            chars.next();
+            match character {
+                '"' => {
+                    quoted = true;
+                    in_quotes = !in_quotes;
+                }
+                ':' if !quoted && field_end.is_none() => {
+                    field_end = Some(raw.len());
+                    raw.push(character);
+                }
+                _ => raw.push(character),
+            }
+        }
+
+        let field = field_end
+            .map(|end| raw[..end].to_ascii_lowercase())
+            .filter(|field| FIELDS.contains(&field.as_str()));
+        let (field, value) = match (field, field_end) {
+            (Some(field), Some(end)) => (Some(field), raw[end + 1..].to_string()),
+            _ => (None, raw),
+        };
+
+        tokens.push(Token {
+            negated,
+            quoted: quoted && field.is_none(),
+            field,
+            value,
+        });
+    }
+
+    tokens
+}
+
+fn parse_filter(
+    field: &str,
+    value: &str,
+    home_dir: Option<&str>,
+    now: DateTime<Local>,
+) -> Result<FilterCondition, CommandError> {
+    let invalid = || {
+        CommandError::invalid_input(
+            format!("The {field}: search filter could not be read."),
+            Some(format!("{field}:{value}")),
+        )
+    };
+    if value.is_empty() {
+        return Err(invalid());
+    }
+
+    let condition = match field {
+        "kind" => FilterCondition::Kind(value.to_lowercase()),
+        "ext" => {
+            let extensions = value
+                .split(',')
+                .map(|extension| {
+                    extension
+                        .trim()
+                        .trim_start_matches('.')
+                        .to_ascii_lowercase()
+                })
+                .filter(|extension| !extension.is_empty())
+                .collect::<Vec<_>>();
+            if extensions.is_empty() {
+                return Err(invalid());
+            }
+            FilterCondition::Extension(extensions)
+        }
+        "size" => {
+            let (comparison, amount) = split_comparison(value);
+            FilterCondition::Size(comparison, parse_size(amount).ok_or_else(invalid)?)
+        }
+        "modified" => FilterCondition::Modified(parse_time_range(value, now).ok_or_else(invalid)?),
+        "created" => FilterCondition::Created(parse_time_range(value, now).ok_or_else(invalid)?),
+        "taken" => FilterCondition::Taken(parse_time_range(value, now).ok_or_else(invalid)?),
+        "in" => FilterCondition::InFolder(expand_folder(value, home_dir).ok_or_else(invalid)?),
+        "is" => match value.to_ascii_lowercase().as_str() {
+            "folder" | "dir" | "directory" => FilterCondition::IsFolder(true),
+            "file" => FilterCondition::IsFolder(false),
+            "hidden" => FilterCondition::Hidden(true),
+            "alias" | "symlink" => FilterCondition::Alias,
+            _ => return Err(invalid()),
+        },
+        "hidden" => match value.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" => FilterCondition::Hidden(true),
+            "false" | "no" | "0" => FilterCondition::Hidden(false),
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+
+    Ok(condition)
+}
+
+fn split_comparison(value: &str) -> (Comparison, &str) {
+    for (prefix, comparison) in [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (comparison, rest.trim());
+        }
+    }
+    (Comparison::Equal, value)
+}
+
+/// Reads sizes like `500`, `10kb` or `1.5gb` using the same 1024-based units
+/// the browser shows.
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.to_ascii_lowercase();
+    let split = value
+        .find(|character: char| !(character.is_ascii_digit() || character == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number.parse::<f64>().ok()?;
+    let multiplier = match unit.trim() {
+        "" | "b" => 1_u64,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        "t" | "tb" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64).round() as u64)
+}
+
+/// Reads relative ages (`7d`, `<2w`, `>1y`) and calendar periods (`2025`,
+/// `2025-03`, `2025-03-14`, `today`, `yesterday`). Ages compare against how
+/// long ago something happened, so `<7d` means within the last week.
+fn parse_time_range(value: &str, now: DateTime<Local>) -> Option<TimeRange> {
+    let (comparison, value) = split_comparison(value);
+    let value = value.to_ascii_lowercase();
+
+    if let Some(instant) = parse_age(&value, now) {
+        let instant = instant.with_timezone(&Utc);
+        return Some(match comparison {
+            Comparison::Greater | Comparison::GreaterOrEqual => TimeRange {
+                start: None,
+                end: Some(instant),
+            },
+            _ => TimeRange {
+                start: Some(instant),
+                end: None,
+            },
+        });
+    }
+
+    let (start, end) = parse_period(&value, now)?;
+    Some(match comparison {
+        Comparison::Equal => TimeRange {
+            start: Some(start),
+            end: Some(end),
+        },
+        Comparison::Greater => TimeRange {
+            start: Some(end),
+            end: None,
+        },
+        Comparison::GreaterOrEqual => TimeRange {
+            start: Some(start),
+            end: None,
+        },
+        Comparison::Less => TimeRange {
+            start: None,
+            end: Some(start),
+        },
+        Comparison::LessOrEqual => TimeRange {
+            start: None,
+            end: Some(end),
+        },
+    })
+}
+
+fn parse_age(value: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let split = value.find(|character: char| !character.is_ascii_digit())?;
+    let (amount, unit) = value.split_at(split);
+    let amount = amount.parse::<u32>().ok()?;
+    match unit {
+        "h" => now.checked_sub_signed(Duration::hours(i64::from(amount))),
+        "d" => now.checked_sub_signed(Duration::days(i64::from(amount))),
+        "w" => now.checked_sub_signed(Duration::weeks(i64::from(amount))),
+        "m" => now.checked_sub_months(Months::new(amount)),
+        "y" => now.checked_sub_months(Months::new(amount.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+fn parse_period(value: &str, now: DateTime<Local>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let today = now.date_naive();
+    let (start, end) = match value {
+        "today" => (today, today.succ_opt()?),
+        "yesterday" => (today.pred_opt()?, today),
+        _ => {
+            let parts = value.split('-').collect::<Vec<_>>();
+            let numbers = parts
+                .iter()
+                .map(|part| part.parse::<u32>().ok())
+                .collect::<Option<Vec<_>>>()?;
+            match numbers.as_slice() {
+                [year] if parts[0].len() == 4 => (
+                    NaiveDate::from_ymd_opt(*year as i32, 1, 1)?,
+                    NaiveDate::from_ymd_opt(*year as i32 + 1, 1, 1)?,
+                ),
+                [year, month] if parts[0].len() == 4 => {
+                    let start = NaiveDate::from_ymd_opt(*year as i32, *month, 1)?;
+                    (start, start.checked_add_months(Months::new(1))?)
+                }
+                [year, month, day] if parts[0].len() == 4 => {
+                    let start = NaiveDate::from_ymd_opt(*year as i32, *month, *day)?;
+                    (start, start.succ_opt()?)
+                }
+                _ => return None,
+            }
+        }
+    };
+
+    Some((local_midnight(start)?, local_midnight(end)?))
+}
+
+fn local_midnight(date: NaiveDate) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|value| value.with_timezone(&Utc))
+}
+
+fn expand_folder(value: &str, home_dir: Option<&str>) -> Option<String> {
+    let expanded = if value == "~" {
+        home_dir?.to_string()
+    } else if let Some(rest) = value.strip_prefix("~/") {
+        format!("{}/{rest}", home_dir?.trim_end_matches('/'))
+    } else {
+        value.to_string()
+    };
+
+    if expanded == "/" {
+        return Some(expanded);
+    }
+    let trimmed = expanded.trim_end_matches('/');
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+fn compile_condition(condition: &FilterCondition, params: &mut Vec<Value>) -> String {
+    match condition {
+        FilterCondition::Kind(kind) => {
+            params.push(Value::Text(format!("%{}%", escape_like(kind))));
+            "lower(kind) LIKE ? ESCAPE '\\'".to_string()
+        }
+        FilterCondition::Extension(extensions) => {
+            let placeholders = vec!["?"; extensions.len()].join(", ");
+            params.extend(
+                extensions
+                    .iter()
+                    .map(|extension| Value::Text(extension.clone())),
+            );
+            format!("extension IN ({placeholders})")
+        }
+        FilterCondition::Size(comparison, bytes) => {
+            params.push(Value::Integer(i64::try_from(*bytes).unwrap_or(i64::MAX)));
+            format!("size {} ?", comparison_operator(*comparison))
+        }
+        FilterCondition::Modified(range) => compile_time_range("modified_at", range, params),
+        FilterCondition::Created(range) => compile_time_range("created_at", range, params),
+        FilterCondition::Taken(range) => format!(
+            "path IN (SELECT path FROM photo_metadata WHERE {})",
+            compile_time_range("captured_at", range, params)
+        ),
+        FilterCondition::InFolder(folder) if folder.starts_with('/') => {
+            let prefix = folder.trim_end_matches('/');
+            params.push(Value::Text(format!("{}/%", escape_like(prefix))));
+            "path LIKE ? ESCAPE '\\'".to_string()
+        }
+        FilterCondition::InFolder(folder) => {
+            let name = escape_like(&folder.to_lowercase());
+            params.push(Value::Text(format!("%/{name}")));
+            params.push(Value::Text(format!("%/{name}/%")));
+            "(lower(parent_path) LIKE ? ESCAPE '\\' OR lower(parent_path) LIKE ? ESCAPE '\\')"
+                .to_string()
+        }
+        FilterCondition::IsFolder(is_dir) => {
+            params.push(Value::Integer(i64::from(*is_dir)));
+            "is_dir = ?".to_string()
+        }
+        FilterCondition::Hidden(hidden) => {
+            params.push(Value::Integer(i64::from(*hidden)));
+            "hidden = ?".to_string()
+        }
+        FilterCondition::Alias => "kind = 'Alias'".to_string(),
+    }
+}
+
+/// Timestamps are stored as UTC RFC 3339 text, so second-precision bounds
+/// in the same shape compare correctly as strings.
+fn compile_time_range(column: &str, range: &TimeRange, params: &mut Vec<Value>) -> String {
+    let mut parts = Vec::new();
+    if let Some(start) = range.start {
+        params.push(Value::Text(start.format("%Y-%m-%dT%H:%M:%S").to_string()));
+        parts.push(format!("{column} >= ?"));
+    }
+    if let Some(end) = range.end {
+        params.push(Value::Text(end.format("%Y-%m-%dT%H:%M:%S").to_string()));
+        parts.push(format!("{column} < ?"));
+    }
+    format!("({})", parts.join(" AND "))
+}
+
+fn comparison_operator(comparison: Comparison) -> &'static str {
+    match comparison {
+        Comparison::Less => "<",
+        Comparison::LessOrEqual => "<=",
+        Comparison::Equal => "=",
+        Comparison::GreaterOrEqual => ">=",
+        Comparison::Greater => ">",
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        if matches!(character, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 6, 15, 12, 0, 0).unwrap()
+    }
+
+    fn parse(query: &str) -> SearchQuery {
+        SearchQuery::parse(
+            query,
+            Some("/Users/frog"),
+            now(),
+            SearchNormalization::default(),
+        )
+        .expect("query should parse")
+    }
+
+    #[test]
+    fn splits_free_text_phrases_and_exclusions() {
+        let query = parse("Annual \"Q3 report\" -draft notes:todo");
+
+        assert_eq!(query.text, "annual q3 report notes:todo");
+        assert_eq!(query.phrases, vec!["q3 report".to_string()]);
+        assert_eq!(query.excluded_terms, vec!["draft".to_string()]);
+        assert!(query.filters.is_empty());
+    }
+
+    #[test]
+    fn folds_free_text_like_indexed_text() {
+        let query = parse("Re\u{301}sume\u{301} \"ÄBC Straße\" -ÉTÉ");
+
+        assert_eq!(query.text, "resume abc strasse");
+        assert_eq!(query.phrases, vec!["abc strasse".to_string()]);
+        assert_eq!(query.excluded_terms, vec!["ete".to_string()]);
+    }
+
+    #[test]
+    fn parses_field_filters() {
+        let query =
+            parse("kind:pdf ext:.RS,md size:>10mb in:~/Projects is:folder hidden:false -is:alias");
+
+        assert!(query.text.is_empty());
+        assert_eq!(
+            query
+                .filters
+                .iter()
+                .map(|filter| (filter.negated, filter.condition.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (false, FilterCondition::Kind("pdf".to_string())),
+                (
+                    false,
+                    FilterCondition::Extension(vec!["rs".to_string(), "md".to_string()])
+                ),
+                (
+                    false,
+                    FilterCondition::Size(Comparison::Greater, 10 * 1024 * 1024)
+                ),
+                (
+                    false,
+                    FilterCondition::InFolder("/Users/frog/Projects".to_string())
+                ),
+                (false, FilterCondition::IsFolder(true)),
+                (false, FilterCondition::Hidden(false)),
+                (true, FilterCondition::Alias),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_relative_ages_and_calendar_periods() {
+        let query = parse("modified:<7d created:2025 modified:>=2025-03 taken:2024-07");
+
+        assert_eq!(
+            query.filters[0].condition,
+            FilterCondition::Modified(TimeRange {
+                start: Some((now() - Duration::days(7)).with_timezone(&Utc)),
+                end: None,
+            })
+        );
+        assert_eq!(
+            query.filters[1].condition,
+            FilterCondition::Created(TimeRange {
+                start: local_midnight(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()),
+                end: local_midnight(NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()),
+            })
+        );
+        assert_eq!(
+            query.filters[2].condition,
+            FilterCondition::Modified(TimeRange {
+                start: local_midnight(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()),
+                end: None,
+            })
+        );
+        assert_eq!(
+            query.filters[3].condition,
+            FilterCondition::Taken(TimeRange {
+                start: local_midnight(NaiveDate::from_ymd_opt(2024, 7, 1).unwrap()),
+                end: local_midnight(NaiveDate::from_ymd_opt(2024, 8, 1).unwrap()),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unreadable_filter_values() {
+        for query in [
+            "size:>lots",
+            "modified:soon",
+            "is:purple",
+            "ext:",
+            "hidden:maybe",
+        ] {
+            let error = SearchQuery::parse(
+                query,
+                Some("/Users/frog"),
+                now(),
+                SearchNormalization::default(),
+            )
+            .expect_err("filter should be rejected");
+            assert_eq!(error.code, "invalid_input", "{query}");
+        }
+    }
+
+    #[test]
+    fn compiles_negated_filters_with_positional_params() {
+        let compiled = parse("-ext:log size:<=1kb").compile();
+
+        assert_eq!(
+            compiled.clauses,
+            vec![
+                "NOT coalesce((extension IN (?)), 0)".to_string(),
+                "size <= ?".to_string(),
+            ]
+        );
+        assert_eq!(
+            compiled.params,
+            vec![Value::Text("log".to_string()), Value::Integer(1024)]
+        );
+    }
+}
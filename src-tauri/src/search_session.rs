@@ -0,0 +1,244 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use rusqlite::InterruptHandle;
+use uuid::Uuid;
+
+use crate::errors::CommandError;
+use crate::models::{SearchResult, SearchResultPage};
+
+/// How many finished searches keep their results around for paging.
+const RETAINED_SESSIONS: usize = 4;
+
+static SEARCH_SESSIONS: OnceLock<SearchSessions> = OnceLock::new();
+
+/// Tracks the search that is running and the results of recent ones.
+///
+/// Only one search runs at a time: starting a search interrupts the SQLite
+/// query of the one before it, so a fast typist never waits on stale work.
+/// Finished results stay available to [`SearchSessions::page`] for the last
+/// few sessions.
+#[derive(Default)]
+pub struct SearchSessions {
+    state: Mutex<SearchSessionsState>,
+}
+
+#[derive(Default)]
+struct SearchSessionsState {
+    active: Option<ActiveSearch>,
+    finished: VecDeque<FinishedSearch>,
+}
+
+struct ActiveSearch {
+    id: String,
+    interrupt: InterruptHandle,
+}
+
+struct FinishedSearch {
+    id: String,
+    results: Vec<SearchResult>,
+}
+
+pub fn shared_search_sessions() -> &'static SearchSessions {
+    SEARCH_SESSIONS.get_or_init(SearchSessions::default)
+}
+
+impl SearchSessions {
+    /// Registers a new search running on the connection behind `interrupt`
+    /// and cancels the previous one. Returns the new session id.
+    pub fn begin(&self, interrupt: InterruptHandle) -> String {
+        let id = Uuid::new_v4().to_string();
+        let mut state = self.lock();
+        if let Some(previous) = state.active.replace(ActiveSearch {
+            id: id.clone(),
+            interrupt,
+        }) {
+            previous.interrupt.interrupt();
+        }
+        id
+    }
+
+    pub fn is_current(&self, id: &str) -> bool {
+        self.lock()
+            .active
+            .as_ref()
+            .is_some_and(|active| active.id == id)
+    }
+
+    /// Stores the complete results of `id`. Returns false when the session was
+    /// superseded or cancelled, in which case the results are dropped.
+    pub fn finish(&self, id: &str, results: Vec<SearchResult>) -> bool {
+        let mut state = self.lock();
+        if state.active.as_ref().is_none_or(|active| active.id != id) {
+            return false;
+        }
+        state.active = None;
+        state.finished.push_front(FinishedSearch {
+            id: id.to_string(),
+            results,
+        });
+        state.finished.truncate(RETAINED_SESSIONS);
+        true
+    }
+
+    pub fn cancel(&self, id: &str) -> Result<(), CommandError> {
+        let mut state = self.lock();
+        match state.active.take() {
+            Some(active) if active.id == id => {
+                active.interrupt.interrupt();
+                Ok(())
+            }
+            other => {
+                state.active = other;
+                Err(CommandError::unavailable(
+                    "That search is no longer running.",
+                    Some(id.to_string()),
+                ))
+            }
+        }
+    }
+
+    /// Pages through the results of a finished session, like a directory
+    /// listing: `cursor` is the offset returned by the previous page.
+    pub fn page(
+        &self,
+        id: &str,
+        cursor: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<SearchResultPage, CommandError> {
+        let state = self.lock();
+        let Some(finished) = state.finished.iter().find(|finished| finished.id == id) else {
+            let message = if state.active.as_ref().is_some_and(|active| active.id == id) {
+                "Search results are still loading."
+            } else {
+                "Those search results are no longer available."
+            };
+            return Err(CommandError::unavailable(message, Some(id.to_string())));
+        };
+
+        let total_count = finished.results.len();
+        let offset = cursor
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0)
+            .min(total_count);
+        // A zero limit would hand back the same cursor forever.
+        let requested_limit = limit.map_or(total_count - offset, |limit| limit.max(1));
+        let next_offset = offset.saturating_add(requested_limit).min(total_count);
+
+        Ok(SearchResultPage {
+            session_id: id.to_string(),
+            results: finished.results[offset..next_offset].to_vec(),
+            total_count,
+            next_cursor: (next_offset < total_count).then(|| next_offset.to_string()),
+            loading_complete: next_offset >= total_count,
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SearchSessionsState> {
+        self.state
+            .lock()
+            .expect("search sessions should not be poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SearchMatchReason;
+    use rusqlite::Connection;
+
+    fn result(name: &str) -> SearchResult {
+        SearchResult {
+            path: format!("/folder/{name}"),
+            parent_path: "/folder".to_string(),
+            name: name.to_string(),
+            display_name: name.to_string(),
+            kind: "Plain Text".to_string(),
+            is_dir: false,
+            size: Some(1),
+            modified_at: None,
+            rank: 0,
+            match_reason: SearchMatchReason::Exact,
+            snippet: None,
+        }
+    }
+
+    fn names(page: &SearchResultPage) -> Vec<&str> {
+        page.results
+            .iter()
+            .map(|result| result.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn pages_advance_to_the_end_of_finished_results() {
+        let conn = Connection::open_in_memory().expect("database should open");
+        let sessions = SearchSessions::default();
+        let id = sessions.begin(conn.get_interrupt_handle());
+        assert_eq!(
+            sessions
+                .page(&id, None, None)
+                .expect_err("running search should not page")
+                .message,
+            "Search results are still loading."
+        );
+        assert!(sessions.finish(&id, ["a", "b", "c"].map(result).to_vec()));
+
+        let first = sessions
+            .page(&id, None, Some(2))
+            .expect("first page should load");
+        assert_eq!(names(&first), vec!["a", "b"]);
+        assert_eq!(first.total_count, 3);
+        assert_eq!(first.next_cursor.as_deref(), Some("2"));
+        assert!(!first.loading_complete);
+
+        let last = sessions
+            .page(&id, first.next_cursor.as_deref(), Some(2))
+            .expect("last page should load");
+        assert_eq!(names(&last), vec!["c"]);
+        assert_eq!(last.next_cursor, None);
+        assert!(last.loading_complete);
+
+        let rest = sessions
+            .page(&id, Some("1"), None)
+            .expect("unlimited page should load");
+        assert_eq!(names(&rest), vec!["b", "c"]);
+
+        let past_end = sessions
+            .page(&id, Some("9"), Some(2))
+            .expect("page past the end should load");
+        assert!(past_end.results.is_empty());
+        assert_eq!(past_end.next_cursor, None);
+    }
+
+    #[test]
+    fn zero_limit_still_advances_the_cursor() {
+        let conn = Connection::open_in_memory().expect("database should open");
+        let sessions = SearchSessions::default();
+        let id = sessions.begin(conn.get_interrupt_handle());
+        assert!(sessions.finish(&id, ["a", "b"].map(result).to_vec()));
+
+        let page = sessions.page(&id, None, Some(0)).expect("page should load");
+        assert_eq!(names(&page), vec!["a"]);
+        assert_eq!(page.next_cursor.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn superseded_sessions_drop_their_results() {
+        let conn = Connection::open_in_memory().expect("database should open");
+        let sessions = SearchSessions::default();
+        let stale = sessions.begin(conn.get_interrupt_handle());
+        let current = sessions.begin(conn.get_interrupt_handle());
+
+        assert!(!sessions.is_current(&stale));
+        assert!(!sessions.finish(&stale, vec![result("a")]));
+        assert!(sessions.finish(&current, vec![result("b")]));
+        assert_eq!(
+            sessions
+                .page(&stale, None, None)
+                .expect_err("superseded results should be gone")
+                .message,
+            "Those search results are no longer available."
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use crate::models::{SyntaxSpan, SyntaxToken};
+
+/// Highlighting stops after this many spans; the rest of a huge preview is
+/// shown plain rather than flooding the frontend.
+const MAX_SPANS: usize = 50_000;
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+const JAVASCRIPT_KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "from",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "let",
+    "new",
+    "null",
+    "of",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+const TYPESCRIPT_KEYWORDS: &[&str] = &[
+    "abstract",
+    "any",
+    "as",
+    "boolean",
+    "declare",
+    "enum",
+    "implements",
+    "interface",
+    "keyof",
+    "namespace",
+    "never",
+    "number",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "string",
+    "type",
+    "unknown",
+];
+const JSON_KEYWORDS: &[&str] = &["false", "null", "true"];
+
+/// Languages with a highlighter. This is deliberately a lexer rather than a
+/// parser: comments, strings, numbers, keywords and tags are enough for a
+/// quick look, and lexing stays linear on large files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    TypeScript,
+    JavaScript,
+    Json,
+    Html,
+    Css,
+    Scss,
+}
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let language = match extension.to_ascii_lowercase().as_str() {
+            "rs" => Self::Rust,
+            "ts" | "tsx" => Self::TypeScript,
+            "js" | "jsx" => Self::JavaScript,
+            "json" => Self::Json,
+            "html" => Self::Html,
+            "css" => Self::Css,
+            "scss" => Self::Scss,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    fn is_keyword(self, word: &str) -> bool {
+        match self {
+            Self::Rust => RUST_KEYWORDS.contains(&word),
+            Self::TypeScript => {
+                JAVASCRIPT_KEYWORDS.contains(&word) || TYPESCRIPT_KEYWORDS.contains(&word)
+            }
+            Self::JavaScript => JAVASCRIPT_KEYWORDS.contains(&word),
+            Self::Json => JSON_KEYWORDS.contains(&word),
+            Self::Html | Self::Css | Self::Scss => false,
+        }
+    }
+
+    fn line_comment(self) -> Option<&'static str> {
+        match self {
+            Self::Rust | Self::TypeScript | Self::JavaScript | Self::Scss => Some("//"),
+            Self::Json | Self::Html | Self::Css => None,
+        }
+    }
+
+    fn block_comment(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Self::Html => Some(("<!--", "-->")),
+            Self::Json => None,
+            _ => Some(("/*", "*/")),
+        }
+    }
+
+    fn quotes(self) -> &'static [char] {
+        match self {
+            Self::Rust | Self::Json => &['"'],
+            Self::TypeScript | Self::JavaScript => &['"', '\'', '`'],
+            Self::Html | Self::Css | Self::Scss => &['"', '\''],
+        }
+    }
+}
+
+/// Highlight spans for `text`. Offsets count UTF-16 code units, which is how
+/// the frontend indexes strings.
+pub fn highlight(text: &str, language: Language) -> Vec<SyntaxSpan> {
+    let mut lexer = Lexer {
+        text,
+        language,
+        position: 0,
+        in_tag: false,
+        spans: Vec::new(),
+    };
+    lexer.run();
+    to_utf16_offsets(text, lexer.spans)
+}
+
+struct Lexer<'a> {
+    text: &'a str,
+    language: Language,
+    position: usize,
+    /// Whether an HTML tag is open, so quotes only start strings inside tags.
+    in_tag: bool,
+    /// Spans in byte offsets until the final conversion.
+    spans: Vec<SyntaxSpan>,
+}
+
+impl Lexer<'_> {
+    fn run(&mut self) {
+        while self.position < self.text.len() && self.spans.len() < MAX_SPANS {
+            let rest = &self.text[self.position..];
+
+            if let Some(prefix) = self.language.line_comment() {
+                if rest.starts_with(prefix) {
+                    let end = rest.find('\n').unwrap_or(rest.len());
+                    self.push(end, SyntaxToken::Comment);
+                    continue;
+                }
+            }
+            if let Some((open, close)) = self.language.block_comment() {
+                if let Some(body) = rest.strip_prefix(open) {
+                    let end = body
+                        .find(close)
+                        .map_or(rest.len(), |index| open.len() + index + close.len());
+                    self.push(end, SyntaxToken::Comment);
+                    continue;
+                }
+            }
+
+            let Some(current) = rest.chars().next() else {
+                break;
+            };
+            if self.language == Language::Html && self.lex_html(rest, current) {
+                continue;
+            }
+            if self.language == Language::Rust && current == '\'' {
+                match rust_char_literal_len(rest) {
+                    Some(len) => self.push(len, SyntaxToken::String),
+                    None => self.position += 1,
+                }
+                continue;
+            }
+            if self.language.quotes().contains(&current)
+                && (self.language != Language::Html || self.in_tag)
+            {
+                let len = self.string_len(rest, current);
+                self.push(len, SyntaxToken::String);
+                continue;
+            }
+            if current.is_ascii_digit() && self.language != Language::Html {
+                let len = rest
+                    .find(|value: char| {
+                        !(value.is_ascii_alphanumeric() || value == '_' || value == '.')
+                    })
+                    .unwrap_or(rest.len());
+                self.push(len, SyntaxToken::Number);
+                continue;
+            }
+            if current == '@' && matches!(self.language, Language::Css | Language::Scss) {
+                let len = rest[1..]
+                    .find(|value: char| !(value.is_ascii_alphanumeric() || value == '-'))
+                    .map_or(rest.len(), |index| 1 + index);
+                self.push(len, SyntaxToken::Keyword);
+                continue;
+            }
+            if is_word_start(current) {
+                let len = word_len(rest);
+                if self.language.is_keyword(&rest[..len]) {
+                    self.push(len, SyntaxToken::Keyword);
+                } else {
+                    self.position += len;
+                }
+                continue;
+            }
+            self.position += current.len_utf8();
+        }
+    }
+
+    /// Handles tag boundaries. Returns true when it consumed input.
+    fn lex_html(&mut self, rest: &str, current: char) -> bool {
+        if current == '>' {
+            self.in_tag = false;
+            self.position += 1;
+            return true;
+        }
+        if current != '<' {
+            return false;
+        }
+        let name_start = if rest[1..].starts_with('/') { 2 } else { 1 };
+        let name_len = rest[name_start..]
+            .find(|value: char| !(value.is_ascii_alphanumeric() || value == '-'))
+            .unwrap_or(rest.len() - name_start);
+        self.position += name_start;
+        if name_len > 0 {
+            self.in_tag = true;
+            self.push(name_len, SyntaxToken::Tag);
+        }
+        true
+    }
+
+    /// Length of the string starting at `rest`. Unterminated strings end at
+    /// the line break, except where the language allows multi-line strings.
+    fn string_len(&self, rest: &str, quote: char) -> usize {
+        let multiline = quote == '`' || self.language == Language::Rust;
+        let mut chars = rest.char_indices().skip(1);
+        while let Some((index, value)) = chars.next() {
+            if value == '\\' {
+                chars.next();
+            } else if value == quote {
+                return index + value.len_utf8();
+            } else if value == '\n' && !multiline {
+                return index;
+            }
+        }
+        rest.len()
+    }
+
+    fn push(&mut self, len: usize, token: SyntaxToken) {
+        let start = self.position;
+        self.position += len;
+        if len > 0 {
+            self.spans.push(SyntaxSpan {
+                start,
+                end: self.position,
+                token,
+            });
+        }
+    }
+}
+
+/// `'a'` and `'\n'` are char literals; `'a` on its own is a lifetime.
+fn rust_char_literal_len(rest: &str) -> Option<usize> {
+    let mut chars = rest.char_indices().skip(1);
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        return rest[2..]
+            .char_indices()
+            .take(10)
+            .find(|(_, value)| *value == '\'')
+            .map(|(index, _)| 2 + index + 1);
+    }
+    match chars.next() {
+        Some((index, '\'')) => Some(index + 1),
+        _ => None,
+    }
+}
+
+fn is_word_start(value: char) -> bool {
+    value.is_alphabetic() || value == '_' || value == '$'
+}
+
+fn word_len(text: &str) -> usize {
+    text.find(|value: char| !(value.is_alphanumeric() || value == '_' || value == '$'))
+        .unwrap_or(text.len())
+}
+
+fn to_utf16_offsets(text: &str, mut spans: Vec<SyntaxSpan>) -> Vec<SyntaxSpan> {
+    let mut boundaries = spans
+        .iter()
+        .flat_map(|span| [span.start, span.end])
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut converted = Vec::with_capacity(boundaries.len());
+    let mut units = 0;
+    let mut next = boundaries.iter().peekable();
+    for (index, value) in text.char_indices().chain([(text.len(), '\0')]) {
+        while next.next_if(|boundary| **boundary == index).is_some() {
+            converted.push((index, units));
+        }
+        units += value.len_utf16();
+    }
+
+    let lookup = |offset: usize| {
+        converted
+            .binary_search_by_key(&offset, |(byte, _)| *byte)
+            .map_or(0, |found| converted[found].1)
+    };
+    for span in &mut spans {
+        span.start = lookup(span.start);
+        span.end = lookup(span.end);
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str, language: Language) -> Vec<(String, SyntaxToken)> {
+        let units = text.encode_utf16().collect::<Vec<_>>();
+        highlight(text, language)
+            .into_iter()
+            .map(|span| {
+                (
+                    String::from_utf16_lossy(&units[span.start..span.end]),
+                    span.token,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rust_lexing_separates_lifetimes_from_char_literals() {
+        let source = "// greet\nfn greet<'a>(name: &'a str) -> char {\n    let _ = \"hi \\\"there\\\"\";\n    'x'\n}\nconst MAX: u32 = 0x1F;";
+        assert_eq!(
+            tokens(source, Language::Rust),
+            vec![
+                ("// greet".to_string(), SyntaxToken::Comment),
+                ("fn".to_string(), SyntaxToken::Keyword),
+                ("let".to_string(), SyntaxToken::Keyword),
+                ("\"hi \\\"there\\\"\"".to_string(), SyntaxToken::String),
+                ("'x'".to_string(), SyntaxToken::String),
+                ("const".to_string(), SyntaxToken::Keyword),
+                ("0x1F".to_string(), SyntaxToken::Number),
+            ]
+        );
+    }
+
+    #[test]
+    fn offsets_count_utf16_units_and_html_quotes_need_a_tag() {
+        let script = "const frog = `🐸 ${1}`; /* done */";
+        let spans = highlight(script, Language::TypeScript);
+        assert_eq!(spans[1].token, SyntaxToken::String);
+        assert_eq!((spans[1].start, spans[1].end), (13, 22));
+        assert_eq!(
+            tokens(script, Language::TypeScript).last(),
+            Some(&("/* done */".to_string(), SyntaxToken::Comment))
+        );
+
+        assert_eq!(
+            tokens(
+                "<p class=\"note\">Don't panic</p><!-- end -->",
+                Language::Html
+            ),
+            vec![
+                ("p".to_string(), SyntaxToken::Tag),
+                ("\"note\"".to_string(), SyntaxToken::String),
+                ("p".to_string(), SyntaxToken::Tag),
+                ("<!-- end -->".to_string(), SyntaxToken::Comment),
+            ]
+        );
+    }
+}
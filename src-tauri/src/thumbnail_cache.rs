@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::models::ThumbnailCacheStats;
+
+pub const CACHE_BUDGET_SETTING: &str = "thumbnails.cacheBudgetBytes";
+pub const DEFAULT_CACHE_BUDGET_BYTES: u64 = 256 * 1024 * 1024;
+/// Smaller budgets would evict thumbnails the current folder still shows.
+pub const MIN_CACHE_BUDGET_BYTES: u64 = 1024 * 1024;
+
+/// Reads the budget from loaded settings, falling back to the default when
+/// it is missing or unreadable.
+pub fn budget_from_settings(settings: &BTreeMap<String, String>) -> u64 {
+    parse_budget(settings.get(CACHE_BUDGET_SETTING).map(String::as_str))
+}
+
+pub fn budget(conn: &Connection) -> Result<u64> {
+    let value = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [CACHE_BUDGET_SETTING],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+    Ok(parse_budget(value.as_deref()))
+}
+
+/// Persists a new budget and evicts down to it right away. Returns how many
+/// thumbnails were evicted.
+pub fn set_budget(conn: &Connection, budget_bytes: u64) -> Result<usize> {
+    conn.execute(
+        "INSERT INTO settings (key, value, updated_at)
+         VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+         ON CONFLICT(key) DO UPDATE SET
+            value = excluded.value,
+            updated_at = excluded.updated_at",
+        params![
+            CACHE_BUDGET_SETTING,
+            budget_bytes.max(MIN_CACHE_BUDGET_BYTES).to_string()
+        ],
+    )?;
+    evict_over_budget(conn, None)
+}
+
+/// Stores the size of a freshly written thumbnail, then evicts older ones if
+/// it pushed the cache over budget.
+pub fn record_generated(
+    conn: &Connection,
+    source_path: &Path,
+    thumbnail_path: &Path,
+) -> Result<()> {
+    let source_path = source_path.to_string_lossy();
+    let byte_size = std::fs::metadata(thumbnail_path)?.len();
+    conn.execute(
+        "UPDATE thumbnail_metadata SET byte_size = ?2 WHERE source_path = ?1",
+        params![source_path.as_ref(), byte_size],
+    )?;
+    evict_over_budget(conn, Some(&source_path))?;
+    Ok(())
+}
+
+/// Deletes least recently used thumbnails, files and rows alike, until the
+/// cache fits its budget. `keep` names a source whose thumbnail was just
+/// generated and must survive even if it alone exceeds the budget.
+pub fn evict_over_budget(conn: &Connection, keep: Option<&str>) -> Result<usize> {
+    measure_unsized(conn)?;
+    let budget = budget(conn)?;
+    let mut total = total_bytes(conn)?;
+    if total <= budget {
+        return Ok(0);
+    }
+
+    let candidates = {
+        let mut stmt = conn.prepare(
+            "SELECT source_path, thumbnail_path, byte_size FROM thumbnail_metadata
+             WHERE source_path IS NOT ?1
+             ORDER BY last_accessed_at, generated_at, rowid",
+        )?;
+        let rows = stmt.query_map([keep], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u64>(2)?,
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut evicted = 0;
+    for (source_path, thumbnail_path, byte_size) in candidates {
+        if total <= budget {
+            break;
+        }
+        std::fs::remove_file(&thumbnail_path).ok();
+        conn.execute(
+            "DELETE FROM thumbnail_metadata WHERE source_path = ?1",
+            [source_path],
+        )?;
+        total = total.saturating_sub(byte_size);
+        evicted += 1;
+    }
+    Ok(evicted)
+}
+
+pub fn stats(conn: &Connection) -> Result<ThumbnailCacheStats> {
+    measure_unsized(conn)?;
+    let entry_count = conn.query_row("SELECT COUNT(*) FROM thumbnail_metadata", [], |row| {
+        row.get::<_, u64>(0)
+    })?;
+    Ok(ThumbnailCacheStats {
+        entry_count,
+        total_bytes: total_bytes(conn)?,
+        budget_bytes: budget(conn)?,
+    })
+}
+
+fn parse_budget(value: Option<&str>) -> u64 {
+    value
+        .and_then(|value| value.parse::<u64>().ok())
+        .map_or(DEFAULT_CACHE_BUDGET_BYTES, |budget| {
+            budget.max(MIN_CACHE_BUDGET_BYTES)
+        })
+}
+
+fn total_bytes(conn: &Connection) -> Result<u64> {
+    Ok(conn.query_row(
+        "SELECT coalesce(sum(byte_size), 0) FROM thumbnail_metadata",
+        [],
+        |row| row.get::<_, u64>(0),
+    )?)
+}
+
+/// Rows written before sizes were tracked have a size of 0. Their files are
+/// measured once so they count against the budget like everything else.
+fn measure_unsized(conn: &Connection) -> Result<()> {
+    let unmeasured = {
+        let mut stmt = conn.prepare(
+            "SELECT source_path, thumbnail_path FROM thumbnail_metadata WHERE byte_size = 0",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    for (source_path, thumbnail_path) in unmeasured {
+        if let Ok(metadata) = std::fs::metadata(Path::new(&thumbnail_path)) {
+            conn.execute(
+                "UPDATE thumbnail_metadata SET byte_size = ?2 WHERE source_path = ?1",
+                params![source_path, metadata.len()],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence;
+
+    fn insert_thumbnail(conn: &Connection, dir: &Path, name: &str, bytes: usize, accessed: &str) {
+        let thumbnail_path = dir.join(format!("{name}.png"));
+        std::fs::write(&thumbnail_path, vec![0_u8; bytes]).expect("thumbnail should write");
+        conn.execute(
+            "INSERT INTO thumbnail_metadata (
+                source_path, thumbnail_path, source_size, width, height, last_accessed_at
+             ) VALUES (?1, ?2, 1, 10, 10, ?3)",
+            params![
+                format!("/photos/{name}.jpg"),
+                thumbnail_path.to_string_lossy(),
+                accessed
+            ],
+        )
+        .expect("thumbnail row should insert");
+    }
+
+    fn sources(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT source_path FROM thumbnail_metadata ORDER BY source_path")
+            .expect("query should prepare");
+        stmt.query_map([], |row| row.get(0))
+            .expect("query should run")
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .expect("rows should read")
+    }
+
+    #[test]
+    fn eviction_removes_least_recently_used_rows_and_files() {
+        let conn = persistence::open_memory_database().expect("database should migrate");
+        let temp = tempfile::tempdir().expect("tempdir should exist");
+        let mib = 1024 * 1024;
+        insert_thumbnail(&conn, temp.path(), "old", mib, "2024-01-01T00:00:00.000Z");
+        insert_thumbnail(
+            &conn,
+            temp.path(),
+            "recent",
+            mib,
+            "2024-03-01T00:00:00.000Z",
+        );
+        insert_thumbnail(
+            &conn,
+            temp.path(),
+            "middle",
+            mib,
+            "2024-02-01T00:00:00.000Z",
+        );
+        insert_thumbnail(&conn, temp.path(), "fresh", mib, "2023-01-01T00:00:00.000Z");
+
+        let before = stats(&conn).expect("stats should load");
+        assert_eq!(before.entry_count, 4);
+        assert_eq!(before.total_bytes, 4 * mib as u64);
+        assert_eq!(before.budget_bytes, DEFAULT_CACHE_BUDGET_BYTES);
+
+        conn.execute(
+            "UPDATE settings SET value = ?2 WHERE key = ?1",
+            params![CACHE_BUDGET_SETTING, (2 * mib).to_string()],
+        )
+        .expect("budget should save");
+        let evicted =
+            evict_over_budget(&conn, Some("/photos/fresh.jpg")).expect("eviction should run");
+        assert_eq!(evicted, 2);
+        assert_eq!(
+            sources(&conn),
+            vec!["/photos/fresh.jpg", "/photos/recent.jpg"]
+        );
+        assert!(!temp.path().join("old.png").exists());
+        assert!(!temp.path().join("middle.png").exists());
+        assert!(temp.path().join("fresh.png").exists());
+
+        assert_eq!(set_budget(&conn, 0).expect("budget should save"), 1);
+        let after = stats(&conn).expect("stats should load");
+        assert_eq!(after.entry_count, 1);
+        assert_eq!(after.budget_bytes, MIN_CACHE_BUDGET_BYTES);
+    }
+}
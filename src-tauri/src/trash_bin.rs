@@ -0,0 +1,512 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use uuid::Uuid;
+
+use crate::errors::CommandError;
+use crate::models::{FileOperationKind, OperationActivity, TrashedItem};
+use crate::operations;
+
+/// Whether trashed items can be put back. Restoring goes through
+/// `trash::os_limited`, which only covers the Windows Recycle Bin and the
+/// freedesktop trash.
+pub const PUT_BACK_SUPPORTED: bool = cfg!(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrashRecord {
+    id: String,
+    original_path: PathBuf,
+    is_dir: bool,
+    size: Option<u64>,
+}
+
+/// Moves items to the platform trash and remembers where they came from.
+pub fn move_to_trash(
+    conn: &Connection,
+    paths: &[PathBuf],
+) -> Result<OperationActivity, CommandError> {
+    let records = paths
+        .iter()
+        .map(|path| record_for(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let activity = operations::move_to_trash(paths)?;
+
+    for record in &records {
+        if let Err(_error) = insert_record(conn, record) {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "[frogger] failed to record trashed item {}: {_error}",
+                record.original_path.display()
+            );
+        }
+    }
+
+    Ok(activity)
+}
+
+/// Lists the items Frogger moved to the trash, newest first. Records for items
+/// that were restored or deleted outside Frogger are dropped first.
+pub fn list_trash(conn: &Connection) -> Result<Vec<TrashedItem>, CommandError> {
+    if let Some(trashed) = platform::trashed_paths()? {
+        forget_missing(conn, &trashed).map_err(CommandError::from)?;
+    }
+
+    let mut statement = conn
+        .prepare(
+            "SELECT id, original_path, is_dir, size, trashed_at
+             FROM trash_records
+             ORDER BY trashed_at DESC, rowid DESC",
+        )
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)?;
+    let rows = statement
+        .query_map([], |row| {
+            let original_path = row.get::<_, String>(1)?;
+            Ok(TrashedItem {
+                id: row.get(0)?,
+                name: Path::new(&original_path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| original_path.clone()),
+                original_path,
+                is_dir: row.get::<_, i64>(2)? != 0,
+                size: row.get::<_, Option<i64>>(3)?.map(|size| size.max(0) as u64),
+                trashed_at: row.get(4)?,
+            })
+        })
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)
+}
+
+/// Restores trashed items, by record id, to the folders they came from.
+pub fn put_back(conn: &Connection, ids: &[String]) -> Result<OperationActivity, CommandError> {
+    if ids.is_empty() {
+        return Err(CommandError::invalid_input("No items were selected.", None));
+    }
+
+    let records = ids
+        .iter()
+        .map(|id| {
+            load_record(conn, "id", id)?.ok_or_else(|| {
+                CommandError::missing_path("That item is no longer in the Trash.", Some(id.clone()))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    restore_records(conn, &records)
+}
+
+/// Restores the most recently trashed item for each original path. Used to
+/// undo a move to the trash.
+pub fn put_back_paths(
+    conn: &Connection,
+    original_paths: &[PathBuf],
+) -> Result<OperationActivity, CommandError> {
+    let records = original_paths
+        .iter()
+        .map(|path| {
+            let original_path = canonical_original_path(path);
+            load_record(conn, "original_path", &original_path.to_string_lossy())?.ok_or_else(|| {
+                CommandError::missing_path(
+                    format!(
+                        "{} is no longer in the Trash.",
+                        operations::item_label(path)
+                    ),
+                    Some(path.to_string_lossy().into_owned()),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    restore_records(conn, &records)
+}
+
+/// Permanently deletes the items Frogger moved to the trash, the same items
+/// [`list_trash`] shows. Whatever other apps trashed stays in the trash.
+pub fn empty_trash(conn: &Connection) -> Result<OperationActivity, CommandError> {
+    let records = load_records(conn).map_err(CommandError::from)?;
+    let original_paths = records
+        .iter()
+        .map(|(_, original_path)| original_path.clone())
+        .collect::<Vec<_>>();
+    let removed = platform::purge(&original_paths)?;
+    for (id, _) in &records {
+        conn.execute("DELETE FROM trash_records WHERE id = ?1", [id])
+            .map_err(anyhow::Error::from)
+            .map_err(CommandError::from)?;
+    }
+
+    Ok(operations::succeeded_activity(
+        FileOperationKind::EmptyTrash,
+        Path::new(""),
+        "Emptied the Trash.".to_string(),
+        removed,
+    ))
+}
+
+fn restore_records(
+    conn: &Connection,
+    records: &[TrashRecord],
+) -> Result<OperationActivity, CommandError> {
+    let original_paths = records
+        .iter()
+        .map(|record| record.original_path.clone())
+        .collect::<Vec<_>>();
+    platform::restore(&original_paths)?;
+
+    for record in records {
+        conn.execute("DELETE FROM trash_records WHERE id = ?1", [&record.id])
+            .map_err(anyhow::Error::from)
+            .map_err(CommandError::from)?;
+    }
+
+    Ok(operations::succeeded_activity(
+        FileOperationKind::PutBack,
+        &original_paths[0],
+        format!("Put back {}.", operations::items_label(&original_paths)),
+        original_paths.len() as u64,
+    ))
+}
+
+fn record_for(path: &Path) -> Result<TrashRecord, CommandError> {
+    let metadata = std::fs::symlink_metadata(path)
+        .map_err(|error| operations::operation_error(path, error))?;
+    Ok(TrashRecord {
+        id: format!("trash-{}", Uuid::new_v4()),
+        original_path: canonical_original_path(path),
+        is_dir: metadata.is_dir(),
+        size: (!metadata.is_dir()).then_some(metadata.len()),
+    })
+}
+
+/// The trash stores items under their canonical parent folder, so records use
+/// the same form to match them later.
+fn canonical_original_path(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|parent| parent.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn insert_record(conn: &Connection, record: &TrashRecord) -> Result<()> {
+    conn.execute(
+        "INSERT INTO trash_records (id, original_path, is_dir, size) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            record.id,
+            record.original_path.to_string_lossy(),
+            record.is_dir as i64,
+            record.size.map(|size| size as i64)
+        ],
+    )?;
+    Ok(())
+}
+
+fn load_record(
+    conn: &Connection,
+    column: &str,
+    value: &str,
+) -> Result<Option<TrashRecord>, CommandError> {
+    let sql = format!(
+        "SELECT id, original_path, is_dir, size FROM trash_records
+         WHERE {column} = ?1
+         ORDER BY trashed_at DESC, rowid DESC
+         LIMIT 1"
+    );
+    let mut statement = conn
+        .prepare(&sql)
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)?;
+    let mut rows = statement
+        .query_map([value], |row| {
+            Ok(TrashRecord {
+                id: row.get(0)?,
+                original_path: PathBuf::from(row.get::<_, String>(1)?),
+                is_dir: row.get::<_, i64>(2)? != 0,
+                size: row.get::<_, Option<i64>>(3)?.map(|size| size.max(0) as u64),
+            })
+        })
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)?;
+
+    rows.next()
+        .transpose()
+        .map_err(anyhow::Error::from)
+        .map_err(CommandError::from)
+}
+
+/// Every record's id and original path.
+fn load_records(conn: &Connection) -> Result<Vec<(String, PathBuf)>> {
+    let mut statement = conn.prepare("SELECT id, original_path FROM trash_records")?;
+    let records = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                PathBuf::from(row.get::<_, String>(1)?),
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(records)
+}
+
+/// Drops records whose item is no longer in the trash.
+fn forget_missing(conn: &Connection, trashed: &HashSet<PathBuf>) -> Result<u64> {
+    let missing = load_records(conn)?
+        .into_iter()
+        .filter(|(_, original_path)| !trashed.contains(original_path))
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    for id in &missing {
+        conn.execute("DELETE FROM trash_records WHERE id = ?1", [id])?;
+    }
+    Ok(missing.len() as u64)
+}
+
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+mod platform {
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+
+    use trash::{os_limited, TrashItem};
+
+    use crate::errors::CommandError;
+    use crate::operations;
+
+    pub fn trashed_paths() -> Result<Option<HashSet<PathBuf>>, CommandError> {
+        let items = os_limited::list().map_err(trash_error)?;
+        Ok(Some(
+            items.iter().map(|item| item.original_path()).collect(),
+        ))
+    }
+
+    /// Restores the newest trash item for each original path.
+    pub fn restore(original_paths: &[PathBuf]) -> Result<(), CommandError> {
+        let mut items = os_limited::list().map_err(trash_error)?;
+        let mut selected = Vec::with_capacity(original_paths.len());
+        for path in original_paths {
+            let item = take_newest(&mut items, path).ok_or_else(|| {
+                CommandError::missing_path(
+                    format!(
+                        "{} is no longer in the Trash.",
+                        operations::item_label(path)
+                    ),
+                    Some(path.to_string_lossy().into_owned()),
+                )
+            })?;
+            selected.push(item);
+        }
+
+        os_limited::restore_all(selected).map_err(|error| match error {
+            trash::Error::RestoreCollision { path, .. } => CommandError::conflict(
+                format!(
+                    "An item named {} is already where this item came from.",
+                    operations::item_label(&path)
+                ),
+                Some(path.to_string_lossy().into_owned()),
+            ),
+            error => trash_error(error),
+        })
+    }
+
+    /// Permanently deletes the newest trash item for each original path.
+    /// Paths that are no longer in the trash are skipped.
+    pub fn purge(original_paths: &[PathBuf]) -> Result<u64, CommandError> {
+        let mut items = os_limited::list().map_err(trash_error)?;
+        let selected = original_paths
+            .iter()
+            .filter_map(|path| take_newest(&mut items, path))
+            .collect::<Vec<_>>();
+        let count = selected.len() as u64;
+        if !selected.is_empty() {
+            os_limited::purge_all(selected).map_err(trash_error)?;
+        }
+        Ok(count)
+    }
+
+    /// Removes and returns the most recently deleted item that came from
+    /// `path`, so repeated paths each claim a different item.
+    pub(super) fn take_newest(items: &mut Vec<TrashItem>, path: &Path) -> Option<TrashItem> {
+        let position = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.original_path() == path)
+            .max_by_key(|(_, item)| item.time_deleted)
+            .map(|(position, _)| position)?;
+        Some(items.remove(position))
+    }
+
+    fn trash_error(error: trash::Error) -> CommandError {
+        CommandError::unavailable("The Trash could not be updated.", Some(error.to_string()))
+    }
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+mod platform {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use crate::errors::CommandError;
+
+    pub fn trashed_paths() -> Result<Option<HashSet<PathBuf>>, CommandError> {
+        Ok(None)
+    }
+
+    pub fn restore(_original_paths: &[PathBuf]) -> Result<(), CommandError> {
+        Err(unsupported())
+    }
+
+    pub fn purge(_original_paths: &[PathBuf]) -> Result<u64, CommandError> {
+        Err(unsupported())
+    }
+
+    fn unsupported() -> CommandError {
+        CommandError::unavailable(
+            "Frogger can't manage the Trash on this platform. Use the system Trash instead.",
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence;
+    use tempfile::tempdir;
+
+    fn test_database() -> (Connection, PathBuf) {
+        let path = std::env::temp_dir().join(format!("frogger-trash-{}.sqlite3", Uuid::new_v4()));
+        let conn = persistence::open_database(&path).expect("database should migrate");
+        (conn, path)
+    }
+
+    #[test]
+    fn records_capture_the_original_location_and_size() {
+        let temp = tempdir().expect("tempdir should exist");
+        let file = temp.path().join("notes.txt");
+        std::fs::write(&file, "notes").expect("file should write");
+        std::fs::create_dir(temp.path().join("folder")).expect("folder should be created");
+
+        let file_record = record_for(&file).expect("file record should build");
+        assert_eq!(
+            file_record.original_path,
+            temp.path()
+                .canonicalize()
+                .expect("tempdir should canonicalize")
+                .join("notes.txt")
+        );
+        assert!(!file_record.is_dir);
+        assert_eq!(file_record.size, Some(5));
+
+        let folder_record =
+            record_for(&temp.path().join("folder")).expect("folder record should build");
+        assert!(folder_record.is_dir);
+        assert_eq!(folder_record.size, None);
+
+        let missing = record_for(&temp.path().join("missing")).expect_err("missing should fail");
+        assert_eq!(missing.code, "missing_path");
+    }
+
+    #[test]
+    fn listing_returns_recorded_items_and_forgets_restored_ones() {
+        let (conn, database_path) = test_database();
+        let kept = TrashRecord {
+            id: "trash-kept".to_string(),
+            original_path: PathBuf::from("/Users/example/kept.txt"),
+            is_dir: false,
+            size: Some(12),
+        };
+        let restored = TrashRecord {
+            id: "trash-restored".to_string(),
+            original_path: PathBuf::from("/Users/example/Projects"),
+            is_dir: true,
+            size: None,
+        };
+        insert_record(&conn, &kept).expect("record should insert");
+        insert_record(&conn, &restored).expect("record should insert");
+
+        let found = load_record(&conn, "original_path", "/Users/example/Projects")
+            .expect("lookup should work")
+            .expect("record should exist");
+        assert_eq!(found, restored);
+
+        let trashed = HashSet::from([kept.original_path.clone()]);
+        assert_eq!(
+            forget_missing(&conn, &trashed).expect("forget should work"),
+            1
+        );
+        assert_eq!(
+            load_record(&conn, "id", "trash-restored").expect("lookup should work"),
+            None
+        );
+
+        let error = put_back(&conn, &["trash-restored".to_string()])
+            .expect_err("forgotten record should not be put back");
+        assert_eq!(error.code, "missing_path");
+
+        std::fs::remove_file(database_path).ok();
+    }
+
+    #[cfg(any(
+        target_os = "windows",
+        all(
+            unix,
+            not(target_os = "macos"),
+            not(target_os = "ios"),
+            not(target_os = "android")
+        )
+    ))]
+    #[test]
+    fn only_recorded_items_are_picked_from_the_system_trash() {
+        let item = |id: &str, name: &str, time_deleted| trash::TrashItem {
+            id: id.into(),
+            name: name.into(),
+            original_parent: PathBuf::from("/Users/example"),
+            time_deleted,
+        };
+        let mut items = vec![
+            item("older", "notes.txt", 1),
+            item("newer", "notes.txt", 2),
+            item("other-app", "photo.jpg", 3),
+        ];
+        let notes = PathBuf::from("/Users/example/notes.txt");
+
+        let first = platform::take_newest(&mut items, &notes).expect("notes should be trashed");
+        assert_eq!(first.id, "newer");
+        let second = platform::take_newest(&mut items, &notes).expect("notes should be trashed");
+        assert_eq!(second.id, "older");
+        assert!(platform::take_newest(&mut items, &notes).is_none());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "other-app");
+    }
+}
@@ -0,0 +1,677 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::file_entry_from_path;
+use crate::errors::CommandError;
+use crate::models::{EventNames, FileEntry, RenamedEntry, WatcherUpdate, WindowState};
+
+/// Quiet period that ends a burst of changes.
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(150);
+/// Upper bound on how long a steady stream of changes can delay an update.
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+
+static FOLDER_WATCHER: OnceLock<Option<FolderWatcher>> = OnceLock::new();
+
+pub type UpdateEmitter = Arc<dyn Fn(&WatcherUpdate) + Send + Sync>;
+
+/// A folder shown in at least one open tab, with the view flags that decide
+/// which entries the tab lists.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FolderWatchRequest {
+    pub path: PathBuf,
+    pub hidden_files_visible: bool,
+    pub file_extensions_visible: bool,
+}
+
+/// Watches the folders that are open in tabs and reports what changed in them.
+///
+/// Filesystem events are collected on a background thread until the folder has
+/// been quiet for [`DEBOUNCE_WINDOW`]. Each dirty folder is then listed again
+/// and diffed against the previous listing, so the emitted [`WatcherUpdate`]
+/// always describes entries the way `list_directory` would.
+///
+/// Each request keeps its own listing, so tabs that show the same folder with
+/// different view flags each get updates in their own format. Entries and
+/// update paths use the path the tab asked for, even when that path runs
+/// through a symlink.
+pub struct FolderWatcher {
+    watcher: Mutex<RecommendedWatcher>,
+    folders: Arc<Mutex<HashMap<FolderWatchRequest, WatchedFolder>>>,
+}
+
+struct WatchedFolder {
+    /// Canonical path of the folder; filesystem events are reported under it.
+    real_path: PathBuf,
+    entries: HashMap<String, FileEntry>,
+}
+
+type WatchedFolders = Mutex<HashMap<FolderWatchRequest, WatchedFolder>>;
+
+#[derive(Default)]
+struct PendingChanges {
+    folders: HashSet<PathBuf>,
+    renames: Vec<(PathBuf, PathBuf)>,
+    rescan_all: bool,
+}
+
+/// Returns the app-wide folder watcher, or `None` when the platform refused to
+/// create one.
+pub fn shared_watcher(app: &AppHandle) -> Option<&'static FolderWatcher> {
+    FOLDER_WATCHER
+        .get_or_init(|| {
+            let app = app.clone();
+            let emit: UpdateEmitter = Arc::new(move |update| {
+                let _ = app.emit(&EventNames::default().watcher_update, update.clone());
+            });
+            match FolderWatcher::new(emit) {
+                Ok(watcher) => Some(watcher),
+                Err(_error) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("[frogger] failed to start folder watcher: {_error:?}");
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Folders to watch for the saved session: every real folder that is open in
+/// a tab. Tabs showing the same folder with the same view flags share one
+/// request.
+pub fn folders_for_windows(windows: &[WindowState]) -> Vec<FolderWatchRequest> {
+    let mut folders = BTreeSet::new();
+    for tab in windows.iter().flat_map(|window| &window.tabs) {
+        let path = PathBuf::from(&tab.path);
+        if !path.is_absolute() {
+            continue;
+        }
+
+        folders.insert(FolderWatchRequest {
+            path,
+            hidden_files_visible: tab.folder_state.hidden_files_visible,
+            file_extensions_visible: tab.folder_state.file_extensions_visible,
+        });
+    }
+    folders.into_iter().collect()
+}
+
+impl FolderWatcher {
+    pub fn new(emit: UpdateEmitter) -> Result<Self, CommandError> {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            if let Ok(event) = result {
+                let _ = sender.send(event);
+            }
+        })
+        .map_err(watch_error)?;
+        let folders = Arc::new(Mutex::new(HashMap::new()));
+
+        let worker_folders = Arc::clone(&folders);
+        thread::Builder::new()
+            .name("frogger-folder-watcher".to_string())
+            .spawn(move || debounce_loop(receiver, worker_folders, emit))
+            .map_err(|error| CommandError::internal(error.to_string()))?;
+
+        Ok(Self {
+            watcher: Mutex::new(watcher),
+            folders,
+        })
+    }
+
+    /// Watches exactly `requests`, releasing watches on folders that are no
+    /// longer open. Folders that cannot be watched are skipped.
+    ///
+    /// New folders are listed before the shared state is locked, so a slow
+    /// listing never holds up updates for folders that are already open.
+    pub fn watch_folders(&self, requests: &[FolderWatchRequest]) {
+        let mut watcher = self
+            .watcher
+            .lock()
+            .expect("folder watcher should not be poisoned");
+        let (existing, mut watching) = {
+            let folders = lock_folders(&self.folders);
+            let existing = folders.keys().cloned().collect::<HashSet<_>>();
+            (existing, real_paths(&folders))
+        };
+
+        let mut added = Vec::new();
+        for request in requests {
+            if existing.contains(request) {
+                continue;
+            }
+            let Ok(real_path) = request.path.canonicalize() else {
+                continue;
+            };
+            if !real_path.is_dir() {
+                continue;
+            }
+            if !watching.contains(&real_path) {
+                if let Err(_error) = watcher.watch(&real_path, RecursiveMode::NonRecursive) {
+                    #[cfg(debug_assertions)]
+                    eprintln!(
+                        "[frogger] failed to watch {}: {_error:?}",
+                        request.path.display()
+                    );
+                    continue;
+                }
+                watching.insert(real_path.clone());
+            }
+
+            let entries = scan_folder(request).unwrap_or_default();
+            added.push((request.clone(), WatchedFolder { real_path, entries }));
+        }
+
+        let current = {
+            let mut folders = lock_folders(&self.folders);
+            let wanted = requests.iter().collect::<HashSet<_>>();
+            folders.retain(|request, _| wanted.contains(request));
+            folders.extend(added);
+            real_paths(&folders)
+        };
+        for path in watching.difference(&current) {
+            let _ = watcher.unwatch(path);
+        }
+    }
+
+    /// Folders currently being watched, sorted by path.
+    pub fn watched_folders(&self) -> Vec<PathBuf> {
+        let paths = lock_folders(&self.folders)
+            .keys()
+            .map(|request| request.path.clone())
+            .collect::<BTreeSet<_>>();
+        paths.into_iter().collect()
+    }
+}
+
+fn real_paths(folders: &HashMap<FolderWatchRequest, WatchedFolder>) -> HashSet<PathBuf> {
+    folders
+        .values()
+        .map(|folder| folder.real_path.clone())
+        .collect()
+}
+
+fn debounce_loop(receiver: Receiver<Event>, folders: Arc<WatchedFolders>, emit: UpdateEmitter) {
+    while let Ok(first) = receiver.recv() {
+        let mut pending = PendingChanges::default();
+        pending.add(first);
+
+        let started = Instant::now();
+        let mut disconnected = false;
+        while started.elapsed() < MAX_BATCH_DELAY {
+            match receiver.recv_timeout(DEBOUNCE_WINDOW) {
+                Ok(event) => pending.add(event),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        for update in flush(&folders, &pending) {
+            emit(&update);
+        }
+        if disconnected {
+            return;
+        }
+    }
+}
+
+impl PendingChanges {
+    fn add(&mut self, event: Event) {
+        if event.need_rescan() {
+            self.rescan_all = true;
+        }
+        if let EventKind::Modify(ModifyKind::Name(RenameMode::Both)) = event.kind {
+            if let [from, to] = event.paths.as_slice() {
+                self.renames.push((from.clone(), to.clone()));
+            }
+        }
+
+        for path in event.paths {
+            if let Some(parent) = path.parent() {
+                self.folders.insert(parent.to_path_buf());
+            }
+            // The watched folder itself may have been removed or replaced.
+            self.folders.insert(path);
+        }
+    }
+}
+
+/// Lists every dirty watched folder again and returns the non-empty diffs.
+fn flush(folders: &WatchedFolders, pending: &PendingChanges) -> Vec<WatcherUpdate> {
+    let mut folders = lock_folders(folders);
+    let mut updates = Vec::new();
+
+    for (request, folder) in folders.iter_mut() {
+        if !pending.rescan_all && !pending.folders.contains(&folder.real_path) {
+            continue;
+        }
+
+        // Events name canonical paths; entries use the path the tab asked for.
+        let renames = pending
+            .renames
+            .iter()
+            .filter_map(|(from, to)| {
+                Some((
+                    request
+                        .path
+                        .join(from.strip_prefix(&folder.real_path).ok()?),
+                    request.path.join(to.strip_prefix(&folder.real_path).ok()?),
+                ))
+            })
+            .collect::<Vec<_>>();
+        // A folder that can no longer be listed reports all of its entries
+        // as removed.
+        let entries = scan_folder(request).unwrap_or_default();
+        let update = diff_entries(request, &folder.entries, &entries, &renames);
+        folder.entries = entries;
+        if !update_is_empty(&update) {
+            updates.push(update);
+        }
+    }
+
+    updates.sort_by(|left, right| {
+        (
+            &left.path,
+            left.hidden_files_visible,
+            left.file_extensions_visible,
+        )
+            .cmp(&(
+                &right.path,
+                right.hidden_files_visible,
+                right.file_extensions_visible,
+            ))
+    });
+    updates
+}
+
+fn scan_folder(request: &FolderWatchRequest) -> Option<HashMap<String, FileEntry>> {
+    let path = &request.path;
+    let directory = std::fs::read_dir(path).ok()?;
+    let entries = directory
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            file_entry_from_path(
+                path,
+                &entry.path(),
+                request.hidden_files_visible,
+                request.file_extensions_visible,
+            )
+            .ok()
+            .flatten()
+        })
+        .map(|entry| (entry.path.clone(), entry))
+        .collect();
+    Some(entries)
+}
+
+/// Diffs two listings of the same folder.
+///
+/// A removed and an added entry become a rename when the filesystem reported
+/// the pair as a rename, or when they are the only two entries that share the
+/// same type, size and modification time.
+fn diff_entries(
+    folder: &FolderWatchRequest,
+    before: &HashMap<String, FileEntry>,
+    after: &HashMap<String, FileEntry>,
+    renames: &[(PathBuf, PathBuf)],
+) -> WatcherUpdate {
+    let mut removed = before
+        .keys()
+        .filter(|path| !after.contains_key(*path))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut added = after
+        .iter()
+        .filter(|(path, _)| !before.contains_key(*path))
+        .map(|(_, entry)| entry.clone())
+        .collect::<Vec<_>>();
+    let mut modified = after
+        .iter()
+        .filter(|(path, entry)| {
+            before
+                .get(*path)
+                .map(|previous| previous != *entry)
+                .unwrap_or(false)
+        })
+        .map(|(_, entry)| entry.clone())
+        .collect::<Vec<_>>();
+
+    let mut renamed = Vec::new();
+    for (from, to) in renames {
+        let from = from.to_string_lossy();
+        let to = to.to_string_lossy();
+        let Some(removed_index) = removed.iter().position(|path| *path == from) else {
+            continue;
+        };
+        let Some(added_index) = added.iter().position(|entry| entry.path == to) else {
+            continue;
+        };
+        renamed.push(RenamedEntry {
+            old_path: removed.remove(removed_index),
+            entry: added.remove(added_index),
+        });
+    }
+
+    let signature = |entry: &FileEntry| (entry.is_dir, entry.size, entry.modified_at.clone());
+    let mut index = 0;
+    while index < removed.len() {
+        let previous = &before[&removed[index]];
+        let removed_matches = removed
+            .iter()
+            .filter(|path| signature(&before[*path]) == signature(previous))
+            .count();
+        let candidates = added
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| signature(entry) == signature(previous))
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        match candidates.as_slice() {
+            [only] if removed_matches == 1 && previous.modified_at.is_some() => {
+                let entry = added.remove(*only);
+                renamed.push(RenamedEntry {
+                    old_path: removed.remove(index),
+                    entry,
+                });
+            }
+            _ => index += 1,
+        }
+    }
+
+    removed.sort();
+    added.sort_by(|left, right| left.path.cmp(&right.path));
+    modified.sort_by(|left, right| left.path.cmp(&right.path));
+    renamed.sort_by(|left, right| left.entry.path.cmp(&right.entry.path));
+
+    WatcherUpdate {
+        path: folder.path.to_string_lossy().into_owned(),
+        hidden_files_visible: folder.hidden_files_visible,
+        file_extensions_visible: folder.file_extensions_visible,
+        added,
+        removed,
+        modified,
+        renamed,
+    }
+}
+
+fn update_is_empty(update: &WatcherUpdate) -> bool {
+    update.added.is_empty()
+        && update.removed.is_empty()
+        && update.modified.is_empty()
+        && update.renamed.is_empty()
+}
+
+fn lock_folders(
+    folders: &WatchedFolders,
+) -> MutexGuard<'_, HashMap<FolderWatchRequest, WatchedFolder>> {
+    folders
+        .lock()
+        .expect("watched folders should not be poisoned")
+}
+
+fn watch_error(error: notify::Error) -> CommandError {
+    CommandError::unavailable(
+        "Frogger could not watch folders for changes.",
+        Some(error.to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        CloudState, FileIcon, FolderViewState, SortDirection, SortKey, SortState, TabState,
+        ViewMode, WindowGeometry,
+    };
+    use tempfile::tempdir;
+
+    fn entry(path: &str, size: u64, modified_at: &str) -> FileEntry {
+        let name = path.rsplit('/').next().unwrap_or(path).to_string();
+        FileEntry {
+            path: path.to_string(),
+            parent_path: "/folder".to_string(),
+            name: name.clone(),
+            display_name: name,
+            kind: "Plain Text".to_string(),
+            is_dir: false,
+            size: Some(size),
+            modified_at: Some(modified_at.to_string()),
+            created_at: None,
+            hidden: false,
+            extension: Some("txt".to_string()),
+            read_only: false,
+            icon: FileIcon {
+                name: "file-text".to_string(),
+                color: None,
+            },
+            cloud: CloudState::Local,
+            is_symlink: false,
+            symlink_broken: false,
+            symlink_target: None,
+        }
+    }
+
+    fn folder() -> FolderWatchRequest {
+        FolderWatchRequest {
+            path: PathBuf::from("/folder"),
+            hidden_files_visible: false,
+            file_extensions_visible: true,
+        }
+    }
+
+    fn listing(entries: &[FileEntry]) -> HashMap<String, FileEntry> {
+        entries
+            .iter()
+            .map(|entry| (entry.path.clone(), entry.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn diff_reports_added_removed_modified_and_renamed_entries() {
+        let before = listing(&[
+            entry("/folder/kept.txt", 1, "2025-01-01T00:00:00Z"),
+            entry("/folder/edited.txt", 1, "2025-01-01T00:00:00Z"),
+            entry("/folder/old-name.txt", 7, "2025-01-02T00:00:00Z"),
+            entry("/folder/deleted.txt", 3, "2025-01-03T00:00:00Z"),
+        ]);
+        let after = listing(&[
+            entry("/folder/kept.txt", 1, "2025-01-01T00:00:00Z"),
+            entry("/folder/edited.txt", 2, "2025-01-05T00:00:00Z"),
+            entry("/folder/new-name.txt", 7, "2025-01-02T00:00:00Z"),
+            entry("/folder/created.txt", 4, "2025-01-04T00:00:00Z"),
+        ]);
+
+        let update = diff_entries(&folder(), &before, &after, &[]);
+
+        assert_eq!(update.path, "/folder");
+        assert_eq!(update.removed, vec!["/folder/deleted.txt".to_string()]);
+        assert_eq!(
+            update
+                .added
+                .iter()
+                .map(|entry| entry.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["/folder/created.txt"]
+        );
+        assert_eq!(update.modified.len(), 1);
+        assert_eq!(update.modified[0].path, "/folder/edited.txt");
+        assert_eq!(update.renamed.len(), 1);
+        assert_eq!(update.renamed[0].old_path, "/folder/old-name.txt");
+        assert_eq!(update.renamed[0].entry.path, "/folder/new-name.txt");
+    }
+
+    #[test]
+    fn diff_prefers_reported_renames_over_ambiguous_matches() {
+        let before = listing(&[
+            entry("/folder/a.txt", 1, "2025-01-01T00:00:00Z"),
+            entry("/folder/b.txt", 1, "2025-01-01T00:00:00Z"),
+        ]);
+        let after = listing(&[
+            entry("/folder/c.txt", 1, "2025-01-01T00:00:00Z"),
+            entry("/folder/d.txt", 5, "2025-01-06T00:00:00Z"),
+        ]);
+        let renames = vec![(
+            PathBuf::from("/folder/b.txt"),
+            PathBuf::from("/folder/c.txt"),
+        )];
+
+        let update = diff_entries(&folder(), &before, &after, &renames);
+
+        assert_eq!(update.renamed.len(), 1);
+        assert_eq!(update.renamed[0].old_path, "/folder/b.txt");
+        assert_eq!(update.renamed[0].entry.path, "/folder/c.txt");
+        assert_eq!(update.removed, vec!["/folder/a.txt".to_string()]);
+        assert_eq!(update.added[0].path, "/folder/d.txt");
+    }
+
+    #[test]
+    fn session_tabs_map_to_one_watch_per_folder() {
+        let tab = |id: &str, path: &str, hidden_files_visible: bool| TabState {
+            id: id.to_string(),
+            path: path.to_string(),
+            title: id.to_string(),
+            position: 0,
+            is_active: false,
+            folder_state: FolderViewState {
+                view_mode: ViewMode::List,
+                sort: SortState {
+                    key: SortKey::Name,
+                    direction: SortDirection::Asc,
+                },
+                folders_first: true,
+                hidden_files_visible,
+                file_extensions_visible: false,
+                scroll_offset: 0.0,
+                selected_item_path: None,
+            },
+        };
+        let window = WindowState {
+            id: "window".to_string(),
+            label: "main".to_string(),
+            geometry: WindowGeometry {
+                x: None,
+                y: None,
+                width: 800.0,
+                height: 600.0,
+                fullscreen: false,
+                maximized: false,
+            },
+            active_tab_id: None,
+            tabs: vec![
+                tab("one", "/Users/example", false),
+                tab("two", "/Users/example", true),
+                tab("three", "recents", false),
+                tab("four", "/Users/example", false),
+            ],
+            sidebar_width: 240.0,
+            sidebar_collapsed: false,
+        };
+
+        let folders = folders_for_windows(&[window]);
+
+        assert_eq!(
+            folders,
+            vec![
+                FolderWatchRequest {
+                    path: PathBuf::from("/Users/example"),
+                    hidden_files_visible: false,
+                    file_extensions_visible: false,
+                },
+                FolderWatchRequest {
+                    path: PathBuf::from("/Users/example"),
+                    hidden_files_visible: true,
+                    file_extensions_visible: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn watcher_emits_debounced_updates_and_releases_closed_folders() {
+        let temp = tempdir().expect("tempdir should exist");
+        let watched = temp.path().to_path_buf();
+        let (sender, updates) = mpsc::channel();
+        let watcher = FolderWatcher::new(Arc::new(move |update: &WatcherUpdate| {
+            sender.send(update.clone()).ok();
+        }))
+        .expect("watcher should start");
+        let request = FolderWatchRequest {
+            path: watched.clone(),
+            hidden_files_visible: false,
+            file_extensions_visible: true,
+        };
+
+        watcher.watch_folders(std::slice::from_ref(&request));
+        assert_eq!(watcher.watched_folders(), vec![watched.clone()]);
+        std::fs::write(watched.join("first.txt"), "1").expect("file should write");
+        std::fs::write(watched.join("second.txt"), "2").expect("file should write");
+
+        let mut added = HashSet::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while added.len() < 2 && Instant::now() < deadline {
+            if let Ok(update) = updates.recv_timeout(Duration::from_millis(500)) {
+                assert_eq!(update.path, watched.to_string_lossy());
+                added.extend(update.added.into_iter().map(|entry| entry.name));
+            }
+        }
+        assert_eq!(
+            added,
+            HashSet::from(["first.txt".to_string(), "second.txt".to_string()])
+        );
+
+        watcher.watch_folders(&[]);
+        assert!(watcher.watched_folders().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn watcher_reports_symlinked_folders_per_view_in_the_tab_format() {
+        let temp = tempdir().expect("tempdir should exist");
+        let real = temp.path().join("real");
+        let link = temp.path().join("link");
+        std::fs::create_dir(&real).expect("folder should exist");
+        std::os::unix::fs::symlink(&real, &link).expect("symlink should exist");
+        let (sender, updates) = mpsc::channel();
+        let watcher = FolderWatcher::new(Arc::new(move |update: &WatcherUpdate| {
+            sender.send(update.clone()).ok();
+        }))
+        .expect("watcher should start");
+        let request = |file_extensions_visible| FolderWatchRequest {
+            path: link.clone(),
+            hidden_files_visible: false,
+            file_extensions_visible,
+        };
+
+        watcher.watch_folders(&[request(true), request(false)]);
+        std::fs::write(real.join("notes.txt"), "1").expect("file should write");
+
+        let mut added = HashMap::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while added.len() < 2 && Instant::now() < deadline {
+            if let Ok(update) = updates.recv_timeout(Duration::from_millis(500)) {
+                assert_eq!(update.path, link.to_string_lossy());
+                for entry in update.added {
+                    assert_eq!(entry.path, link.join("notes.txt").to_string_lossy());
+                    added.insert(update.file_extensions_visible, entry.display_name);
+                }
+            }
+        }
+        assert_eq!(
+            added,
+            HashMap::from([
+                (true, "notes.txt".to_string()),
+                (false, "notes".to_string())
+            ])
+        );
+    }
+}
@@ -42,6 +42,8 @@ interface ListColumn {
   minWidth: number;
 }
 
+const THUMBNAIL_EXTENSIONS = ["png", "jpg", "jpeg", "webp", "gif", "bmp", "tif", "tiff", "ico", "svg"];
+
 @Component({
   selector: "app-root",
   imports: [ScrollingModule],
@@ -1069,7 +1071,7 @@ export class AppComponent implements OnInit, OnDestroy {
       return false;
     }
 
-    return ["png", "jpg", "jpeg", "webp"].includes((entry.extension ?? "").toLowerCase());
+    return THUMBNAIL_EXTENSIONS.includes((entry.extension ?? "").toLowerCase());
   }
 
   private extensionForName(name: string): string | null {
@@ -1102,7 +1104,7 @@ export class AppComponent implements OnInit, OnDestroy {
     if (kind.includes("archive")) {
       return { name: "archive", color: null };
     }
-    if (extension && ["png", "jpg", "jpeg", "webp"].includes(extension)) {
+    if (extension && THUMBNAIL_EXTENSIONS.includes(extension)) {
       return { name: "file", color: null };
     }
 
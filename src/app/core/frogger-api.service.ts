@@ -1,7 +1,7 @@
 import { Injectable } from "@angular/core";
 import { invoke } from "@tauri-apps/api/core";
 
-import type { AppBootstrap, AppSettings, DirectoryListRequest, DirectoryListing, FolderViewState, SearchResult, SidebarState, SortState, ThumbnailDescriptor, WindowState } from "./frogger-api.types";
+import type { AppBootstrap, AppSettings, ConflictPolicy, DirectoryListRequest, DirectoryListing, FolderSearchPage, FolderSearchRequest, FolderViewState, IndexConfiguration, IndexExclusionKind, OperationActivity, PreviewDescriptor, SearchHistoryEntry, SearchPatternMode, SearchResult, SearchResultPage, SearchSession, SearchSuggestion, SidebarState, SortState, TextPreview, ThumbnailCacheStats, ThumbnailDescriptor, TrashedItem, WindowState } from "./frogger-api.types";
 
 @Injectable({ providedIn: "root" })
 export class FroggerApiService {
@@ -43,6 +43,60 @@ export class FroggerApiService {
     return invoke<SearchResult[]>("search_metadata", { query, limit });
   }
 
+  searchPattern(
+    pattern: string,
+    mode: SearchPatternMode,
+    limit: number | null = 100,
+  ): Promise<SearchResult[]> {
+    return invoke<SearchResult[]>("search_pattern", { pattern, mode, limit });
+  }
+
+  startSearch(query: string, pageSize: number | null = 50): Promise<SearchSession> {
+    return invoke<SearchSession>("start_search", { query, pageSize });
+  }
+
+  cancelSearch(sessionId: string): Promise<void> {
+    return invoke<void>("cancel_search", { sessionId });
+  }
+
+  recordSearchHistory(query: string): Promise<SearchHistoryEntry[]> {
+    return invoke<SearchHistoryEntry[]>("record_search_history", { query });
+  }
+
+  listSearchHistory(limit: number | null = null): Promise<SearchHistoryEntry[]> {
+    return invoke<SearchHistoryEntry[]>("list_search_history", { limit });
+  }
+
+  deleteSearchHistoryEntry(id: string): Promise<SearchHistoryEntry[]> {
+    return invoke<SearchHistoryEntry[]>("delete_search_history_entry", { id });
+  }
+
+  clearSearchHistory(): Promise<SearchHistoryEntry[]> {
+    return invoke<SearchHistoryEntry[]>("clear_search_history");
+  }
+
+  getSearchSuggestions(query: string, limit: number | null = 8): Promise<SearchSuggestion[]> {
+    return invoke<SearchSuggestion[]>("get_search_suggestions", { query, limit });
+  }
+
+  getSearchPage(
+    sessionId: string,
+    cursor: string | null = null,
+    limit: number | null = 50,
+  ): Promise<SearchResultPage> {
+    return invoke<SearchResultPage>("get_search_page", { sessionId, cursor, limit });
+  }
+
+  searchInFolder(
+    path: string,
+    query: string,
+    cursor: string | null = null,
+    limit: number | null = 100,
+  ): Promise<FolderSearchPage> {
+    const request: FolderSearchRequest = { path, query, cursor, limit };
+    return invoke<FolderSearchPage>("search_in_folder", { request });
+  }
+
   openFileWithDefaultApp(path: string): Promise<SidebarState> {
     return invoke<SidebarState>("open_file_with_default_app", { path });
   }
@@ -51,10 +105,26 @@ export class FroggerApiService {
     return invoke<ThumbnailDescriptor | null>("get_thumbnail", { path });
   }
 
+  getPreview(path: string): Promise<PreviewDescriptor> {
+    return invoke<PreviewDescriptor>("get_preview", { path });
+  }
+
+  getTextPreview(path: string, maxBytes: number | null = null): Promise<TextPreview> {
+    return invoke<TextPreview>("get_text_preview", { path, maxBytes });
+  }
+
   cleanupThumbnailCache(): Promise<number> {
     return invoke<number>("cleanup_thumbnail_cache");
   }
 
+  getThumbnailCacheStats(): Promise<ThumbnailCacheStats> {
+    return invoke<ThumbnailCacheStats>("get_thumbnail_cache_stats");
+  }
+
+  setThumbnailCacheBudget(budgetBytes: number): Promise<AppSettings> {
+    return invoke<AppSettings>("set_thumbnail_cache_budget", { budgetBytes });
+  }
+
   getSidebarState(): Promise<SidebarState> {
     return invoke<SidebarState>("get_sidebar_state");
   }
@@ -83,7 +153,135 @@ export class FroggerApiService {
     return invoke<SidebarState>("set_sidebar_section_visibility", { sectionId, visible });
   }
 
+  saveSmartFolder(label: string, query: string, id: string | null = null): Promise<SidebarState> {
+    return invoke<SidebarState>("save_smart_folder", { id, label, query });
+  }
+
+  removeSmartFolder(id: string): Promise<SidebarState> {
+    return invoke<SidebarState>("remove_smart_folder", { id });
+  }
+
   recordRecentItem(path: string): Promise<SidebarState> {
     return invoke<SidebarState>("record_recent_item", { path });
   }
+
+  recordSearchResultOpen(path: string): Promise<SidebarState> {
+    return invoke<SidebarState>("record_search_result_open", { path });
+  }
+
+  createFolder(parentPath: string, name: string | null = null): Promise<OperationActivity> {
+    return invoke<OperationActivity>("create_folder", { parentPath, name });
+  }
+
+  renameItem(path: string, newName: string): Promise<OperationActivity> {
+    return invoke<OperationActivity>("rename_item", { path, newName });
+  }
+
+  moveItemsToTrash(paths: string[]): Promise<OperationActivity> {
+    return invoke<OperationActivity>("move_items_to_trash", { paths });
+  }
+
+  copyItems(
+    paths: string[],
+    destination: string,
+    conflictPolicy: ConflictPolicy | null = null,
+  ): Promise<OperationActivity> {
+    return invoke<OperationActivity>("copy_items", { paths, destination, conflictPolicy });
+  }
+
+  moveItems(
+    paths: string[],
+    destination: string,
+    conflictPolicy: ConflictPolicy | null = null,
+  ): Promise<OperationActivity> {
+    return invoke<OperationActivity>("move_items", { paths, destination, conflictPolicy });
+  }
+
+  resolveFileOperationConflict(
+    id: string,
+    resolution: ConflictPolicy,
+    applyToAll: boolean,
+  ): Promise<OperationActivity> {
+    return invoke<OperationActivity>("resolve_file_operation_conflict", {
+      id,
+      resolution,
+      applyToAll,
+    });
+  }
+
+  listFileOperations(): Promise<OperationActivity[]> {
+    return invoke<OperationActivity[]>("list_file_operations");
+  }
+
+  cancelFileOperation(id: string): Promise<OperationActivity> {
+    return invoke<OperationActivity>("cancel_file_operation", { id });
+  }
+
+  undoFileOperation(): Promise<OperationActivity> {
+    return invoke<OperationActivity>("undo_file_operation");
+  }
+
+  redoFileOperation(): Promise<OperationActivity> {
+    return invoke<OperationActivity>("redo_file_operation");
+  }
+
+  listTrash(): Promise<TrashedItem[]> {
+    return invoke<TrashedItem[]>("list_trash");
+  }
+
+  putBackItems(ids: string[]): Promise<OperationActivity> {
+    return invoke<OperationActivity>("put_back_items", { ids });
+  }
+
+  emptyTrash(): Promise<OperationActivity> {
+    return invoke<OperationActivity>("empty_trash");
+  }
+
+  pauseIndexing(): Promise<void> {
+    return invoke<void>("pause_indexing");
+  }
+
+  resumeIndexing(): Promise<void> {
+    return invoke<void>("resume_indexing");
+  }
+
+  cancelIndexing(): Promise<void> {
+    return invoke<void>("cancel_indexing");
+  }
+
+  setIndexingThrottle(entriesPerSecond: number | null): Promise<AppSettings> {
+    return invoke<AppSettings>("set_indexing_throttle", { entriesPerSecond });
+  }
+
+  setSearchIgnoreDiacritics(ignoreDiacritics: boolean): Promise<AppSettings> {
+    return invoke<AppSettings>("set_search_ignore_diacritics", { ignoreDiacritics });
+  }
+
+  setContentIndexing(enabled: boolean): Promise<AppSettings> {
+    return invoke<AppSettings>("set_content_indexing", { enabled });
+  }
+
+  setPhotoMetadataIndexing(enabled: boolean): Promise<AppSettings> {
+    return invoke<AppSettings>("set_photo_metadata_indexing", { enabled });
+  }
+
+  getIndexConfiguration(): Promise<IndexConfiguration> {
+    return invoke<IndexConfiguration>("get_index_configuration");
+  }
+
+  addIndexRoot(path: string): Promise<IndexConfiguration> {
+    return invoke<IndexConfiguration>("add_index_root", { path });
+  }
+
+  removeIndexRoot(path: string): Promise<IndexConfiguration> {
+    return invoke<IndexConfiguration>("remove_index_root", { path });
+  }
+
+  addIndexExclusion(kind: IndexExclusionKind, pattern: string): Promise<IndexConfiguration> {
+    return invoke<IndexConfiguration>("add_index_exclusion", { kind, pattern });
+  }
+
+  removeIndexExclusion(id: string): Promise<IndexConfiguration> {
+    return invoke<IndexConfiguration>("remove_index_exclusion", { id });
+  }
 }
@@ -3,11 +3,32 @@ export type AppearanceMode = "system" | "light" | "dark";
 export type ViewMode = "list" | "grid" | "column" | "gallery";
 export type SortKey = "name" | "dateModified" | "size" | "kind" | "path";
 export type SortDirection = "asc" | "desc";
-export type SidebarSectionId = "recents" | "favorites" | "locations";
-export type SidebarItemType = "recent" | "favorite" | "drive" | "cloudFolder" | "home";
+export type SidebarSectionId = "recents" | "favorites" | "locations" | "smartFolders";
+export type SidebarItemType =
+  | "recent"
+  | "favorite"
+  | "drive"
+  | "cloudFolder"
+  | "home"
+  | "smartFolder";
 export type CloudState = "local" | "cloudAvailableOffline" | "cloudOnly" | "unknown";
-export type SearchMatchReason = "exact" | "prefix" | "substring" | "fuzzy";
-export type IndexingStatus = "notStarted" | "initialBuild" | "reconciling" | "ready" | "failed";
+export type SearchMatchReason =
+  | "exact"
+  | "prefix"
+  | "substring"
+  | "fuzzy"
+  | "filter"
+  | "content"
+  | "pattern";
+export type SearchPatternMode = "regex" | "glob";
+export type IndexingStatus =
+  | "notStarted"
+  | "initialBuild"
+  | "reconciling"
+  | "ready"
+  | "paused"
+  | "cancelled"
+  | "failed";
 export type FileOperationKind =
   | "newFolder"
   | "rename"
@@ -17,8 +38,19 @@ export type FileOperationKind =
   | "open"
   | "openWith"
   | "indexing"
-  | "preview";
-export type OperationStatus = "queued" | "running" | "succeeded" | "failed" | "cancelled";
+  | "preview"
+  | "undo"
+  | "redo"
+  | "putBack"
+  | "emptyTrash";
+export type OperationStatus =
+  | "queued"
+  | "running"
+  | "paused"
+  | "succeeded"
+  | "failed"
+  | "cancelled";
+export type ConflictPolicy = "replace" | "skip" | "keepBoth" | "merge" | "ask";
 export type PreviewRenderer = "image" | "video" | "audio" | "text" | "pdf" | "fallbackMetadata";
 
 export interface CommandError {
@@ -63,6 +95,11 @@ export interface AppSettings {
   restoreEnabled: boolean;
   localOnlyIndexing: boolean;
   previewsEnabled: boolean;
+  indexingEntriesPerSecond: number | null;
+  searchIgnoreDiacritics: boolean;
+  searchContentIndexing: boolean;
+  searchPhotoMetadataIndexing: boolean;
+  thumbnailCacheBudgetBytes: number;
   listColumnVisibility: Record<string, boolean>;
   listColumnWidths: Record<string, number>;
   raw: Record<string, string>;
@@ -116,6 +153,7 @@ export interface SidebarState {
   recentItems: SidebarItem[];
   favorites: SidebarItem[];
   locations: SidebarItem[];
+  smartFolders: SidebarItem[];
   recentsVirtualFolderId: string;
 }
 
@@ -189,6 +227,12 @@ export interface ThumbnailDescriptor {
   cacheHit: boolean;
 }
 
+export interface ThumbnailCacheStats {
+  entryCount: number;
+  totalBytes: number;
+  budgetBytes: number;
+}
+
 export interface SearchResult {
   path: string;
   parentPath: string;
@@ -200,6 +244,73 @@ export interface SearchResult {
   modifiedAt: string | null;
   rank: number;
   matchReason: SearchMatchReason;
+  snippet: SearchSnippet | null;
+}
+
+export interface SearchSnippet {
+  segments: SnippetSegment[];
+}
+
+export interface SnippetSegment {
+  text: string;
+  highlighted: boolean;
+}
+
+export interface SearchSession {
+  id: string;
+  query: string;
+}
+
+export interface SearchHistoryEntry {
+  id: string;
+  query: string;
+  lastUsedAt: string;
+  useCount: number;
+}
+
+export type SearchSuggestionSource = "history" | "favorite" | "kind" | "extension";
+
+export interface SearchSuggestion {
+  text: string;
+  label: string;
+  source: SearchSuggestionSource;
+}
+
+export interface SearchResultBatch {
+  sessionId: string;
+  query: string;
+  results: SearchResult[];
+  offset: number;
+  totalCount: number;
+  complete: boolean;
+}
+
+export interface SearchResultPage {
+  sessionId: string;
+  results: SearchResult[];
+  totalCount: number;
+  nextCursor: string | null;
+  loadingComplete: boolean;
+}
+
+export interface FolderSearchRequest {
+  path: string;
+  query: string;
+  cursor: string | null;
+  limit: number | null;
+}
+
+export type FolderSearchSource = "index" | "liveWalk";
+
+export interface FolderSearchPage {
+  path: string;
+  query: string;
+  results: SearchResult[];
+  totalCount: number;
+  nextCursor: string | null;
+  loadingComplete: boolean;
+  source: FolderSearchSource;
+  walkComplete: boolean;
 }
 
 export interface IndexingState {
@@ -209,6 +320,25 @@ export interface IndexingState {
   message: string | null;
 }
 
+export interface IndexConfiguration {
+  roots: IndexRoot[];
+  exclusions: IndexExclusion[];
+}
+
+export interface IndexRoot {
+  path: string;
+  isDefault: boolean;
+}
+
+export type IndexExclusionKind = "glob" | "path";
+
+export interface IndexExclusion {
+  id: string;
+  kind: IndexExclusionKind;
+  pattern: string;
+  isDefault: boolean;
+}
+
 export interface OperationActivity {
   id: string;
   operation: FileOperationKind;
@@ -217,6 +347,38 @@ export interface OperationActivity {
   message: string;
   progress: OperationProgress | null;
   recoverable: boolean;
+  conflict: FileOperationConflict | null;
+}
+
+export interface FileOperationConflict {
+  sourcePath: string;
+  targetPath: string;
+  sourceIsDir: boolean;
+  targetIsDir: boolean;
+}
+
+export interface WatcherUpdate {
+  path: string;
+  hiddenFilesVisible: boolean;
+  fileExtensionsVisible: boolean;
+  added: FileEntry[];
+  removed: string[];
+  modified: FileEntry[];
+  renamed: RenamedEntry[];
+}
+
+export interface RenamedEntry {
+  oldPath: string;
+  entry: FileEntry;
+}
+
+export interface TrashedItem {
+  id: string;
+  name: string;
+  originalPath: string;
+  isDir: boolean;
+  size: number | null;
+  trashedAt: string;
 }
 
 export interface OperationProgress {
@@ -233,6 +395,27 @@ export interface PreviewDescriptor {
   metadata: Record<string, string>;
 }
 
+export type TextEncoding = "utf8" | "utf16Le" | "utf16Be" | "latin1";
+
+export type SyntaxToken = "comment" | "keyword" | "number" | "string" | "tag";
+
+export interface SyntaxSpan {
+  start: number;
+  end: number;
+  token: SyntaxToken;
+}
+
+export interface TextPreview {
+  path: string;
+  content: string;
+  encoding: TextEncoding | null;
+  isBinary: boolean;
+  lineCount: number;
+  truncated: boolean;
+  language: string | null;
+  spans: SyntaxSpan[];
+}
+
 export interface AppCapabilities {
   nativeTitlebarTabs: boolean;
   openWithChooser: boolean;
@@ -245,7 +428,9 @@ export interface EventNames {
   directoryListingProgress: string;
   indexingProgress: string;
   fileOperationProgress: string;
+  fileOperationConflict: string;
   watcherUpdate: string;
+  searchResults: string;
   settingsChanged: string;
   activityFailure: string;
 }
@@ -6,13 +6,17 @@ import type {
   EventNames,
   IndexingState,
   OperationActivity,
+  SearchResultBatch,
+  WatcherUpdate,
 } from "./frogger-api.types";
 
 export interface FroggerEventPayloads {
   directoryListingProgress: DirectoryListing;
   indexingProgress: IndexingState;
   fileOperationProgress: OperationActivity;
-  watcherUpdate: { paths: string[]; reason: string };
+  fileOperationConflict: OperationActivity;
+  watcherUpdate: WatcherUpdate;
+  searchResults: SearchResultBatch;
   settingsChanged: { keys: string[] };
   activityFailure: OperationActivity;
 }
@@ -49,10 +53,20 @@ export class FroggerEventsService {
       );
     }
 
+    if (handlers.fileOperationConflict) {
+      registrations.push(
+        this.listenTo(events.fileOperationConflict, handlers.fileOperationConflict),
+      );
+    }
+
     if (handlers.watcherUpdate) {
       registrations.push(this.listenTo(events.watcherUpdate, handlers.watcherUpdate));
     }
 
+    if (handlers.searchResults) {
+      registrations.push(this.listenTo(events.searchResults, handlers.searchResults));
+    }
+
     if (handlers.settingsChanged) {
       registrations.push(this.listenTo(events.settingsChanged, handlers.settingsChanged));
     }